@@ -0,0 +1,87 @@
+//! Derive macros for `redux`, re-exported by it with the `derive` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as Tokens;
+use quote::quote;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Error, LitStr};
+
+/// Derives `redux::ActionKind` for an enum. Each variant's kind is its name,
+/// unless it's renamed with `#[action(kind = "...")]`, and its categories are
+/// the ones of every `#[action(category = "...")]` on the enum and on it.
+#[proc_macro_derive(ActionKind, attributes(action))]
+pub fn derive_action_kind(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match action_kind(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    kind: Option<String>,
+    categories: Vec<String>,
+}
+
+fn options(attrs: &[Attribute]) -> Result<Options, Error> {
+    let mut options = Options::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("action")) {
+        attr.parse_nested_meta(|meta| {
+            let value : LitStr = meta.value()?.parse()?;
+            if meta.path.is_ident("kind") {
+                if options.kind.is_some() {
+                    return Err(meta.error("the kind is already set"));
+                }
+                options.kind = Some(value.value());
+            } else if meta.path.is_ident("category") {
+                options.categories.push(value.value());
+            } else {
+                return Err(meta.error("expected `kind` or `category`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(options)
+}
+
+fn action_kind(input: &DeriveInput) -> Result<Tokens, Error> {
+    let variants = match input.data {
+        Data::Enum(ref data) => &data.variants,
+        _ => return Err(Error::new_spanned(&input.ident, "ActionKind can only be derived for enums")),
+    };
+
+    let shared = options(&input.attrs)?;
+    if let Some(ref kind) = shared.kind {
+        return Err(Error::new_spanned(&input.ident, format!("`kind = \"{}\"` names a variant, it goes on one", kind)));
+    }
+
+    let mut kinds = vec![];
+    let mut categories = vec![];
+    for variant in variants {
+        let options = options(&variant.attrs)?;
+        let ident = &variant.ident;
+        let kind = options.kind.unwrap_or_else(|| ident.to_string());
+        let all : Vec<&String> = shared.categories.iter().chain(&options.categories).collect();
+        kinds.push(quote! { Self::#ident { .. } => #kind, });
+        categories.push(quote! { Self::#ident { .. } => &[#(#all),*], });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::redux::ActionKind for #name #ty_generics #where_clause {
+            fn kind(&self) -> &'static str {
+                match *self {
+                    #(#kinds)*
+                }
+            }
+
+            fn categories(&self) -> &'static [&'static str] {
+                match *self {
+                    #(#categories)*
+                }
+            }
+        }
+    })
+}
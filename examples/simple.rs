@@ -2,6 +2,7 @@ extern crate redux;
 use redux::{Store, Reducer};
 use std::default::Default;
 
+#[allow(dead_code)]
 #[derive(Clone, Debug)]
 struct Todo {
 	name: &'static str,
@@ -42,7 +43,7 @@ impl Reducer for TodoState {
 	fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
 		match action {
             TodoAction::Insert(name) => {
-                let todo = Todo { name: name, };
+                let todo = Todo { name };
                 self.push(todo);
             },
 		}
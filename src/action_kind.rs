@@ -0,0 +1,121 @@
+use std::str::FromStr;
+
+use crate::{DispatchFunc, Middleware, Reducer, Store};
+
+/// A name for each kind of action, so middleware can tell actions apart
+/// without a `match` on the user's enum: to filter them, log them or count
+/// them. Kinds can also belong to categories, say every action that writes.
+///
+/// With the `derive` feature it can be derived for enums, each variant's kind
+/// being its name unless it's renamed:
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use redux::ActionKind;
+///
+/// #[derive(ActionKind)]
+/// #[action(category = "todos")]
+/// enum TodoAction {
+///     NewTodo { name: String },
+///     #[action(kind = "todos/complete", category = "writes")]
+///     Complete(usize),
+/// }
+///
+/// assert_eq!("NewTodo", TodoAction::NewTodo { name: String::from("a") }.kind());
+/// assert_eq!("todos/complete", TodoAction::Complete(0).kind());
+/// assert_eq!(&["todos", "writes"], TodoAction::Complete(0).categories());
+/// # }
+/// ```
+pub trait ActionKind {
+    fn kind(&self) -> &'static str;
+
+    /// The categories the action belongs to, none by default.
+    fn categories(&self) -> &'static [&'static str] {
+        &[]
+    }
+}
+
+/// Picks actions by kind or category, for example from configuration. A
+/// name matches an action if it's its kind or one of its categories, and `*`
+/// matches every action.
+///
+/// It's a middleware too, one that drops every action it doesn't match:
+/// they're never reduced, but the dispatch still succeeds with the current
+/// state, so they still show up in `Store::actions`.
+///
+/// ```
+/// # use redux::KindFilter;
+/// let filter = KindFilter::new().allow(["todos"]).deny(["Tick"]);
+/// // or, from a setting
+/// let filter : KindFilter = "todos, -Tick".parse().unwrap();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KindFilter {
+    // `None` allows everything that isn't denied
+    allowed: Option<Vec<String>>,
+    denied: Vec<String>,
+}
+
+impl KindFilter {
+    /// Matches every action.
+    pub fn new() -> KindFilter {
+        KindFilter::default()
+    }
+
+    /// Only matches actions with one of these kinds or categories, or any
+    /// that an earlier `allow` let through.
+    pub fn allow<I, S>(mut self, names: I) -> KindFilter
+        where I: IntoIterator<Item = S>,
+              S: Into<String>
+    {
+        self.allowed.get_or_insert_with(Vec::new).extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Never matches actions with one of these kinds or categories, even
+    /// allowed ones.
+    pub fn deny<I, S>(mut self, names: I) -> KindFilter
+        where I: IntoIterator<Item = S>,
+              S: Into<String>
+    {
+        self.denied.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn matches<A: ActionKind>(&self, action: &A) -> bool {
+        let named = |names: &[String]| names.iter().any(|name| {
+            name == "*" || name == action.kind() || action.categories().contains(&name.as_str())
+        });
+
+        self.allowed.as_deref().is_none_or(named) && !named(&self.denied)
+    }
+}
+
+/// Reads a comma separated list of names to allow, where names starting with
+/// `-` are denied instead. An empty list matches everything.
+impl FromStr for KindFilter {
+    type Err = String;
+
+    fn from_str(names: &str) -> Result<KindFilter, String> {
+        let mut filter = KindFilter::new();
+        for name in names.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            filter = match name.strip_prefix('-').map(str::trim) {
+                Some("") => return Err(String::from("`-` isn't followed by a kind or category")),
+                Some(denied) => filter.deny([denied]),
+                None => filter.allow([name]),
+            };
+        }
+
+        Ok(filter)
+    }
+}
+
+impl<T: 'static + Reducer> Middleware<T> for KindFilter where T::Action: ActionKind {
+    fn dispatch(&self, store: &Store<T>, action: T::Action, next: &DispatchFunc<T>) -> Result<T, String> {
+        match self.matches(&action) {
+            true => next(store, action),
+            false => Ok(store.get_state()),
+        }
+    }
+}
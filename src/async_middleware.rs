@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+
+use crate::{Reducer, Store};
+
+pub type AsyncDispatchFunc<T> = Box<dyn for<'a> Fn(&'a Store<T>, <T as Reducer>::Action) -> BoxFuture<'a, Result<T, String>> + Send + Sync>;
+
+/// The async counterpart to `Middleware`, for middleware that needs to await
+/// something (I/O, a timer, another task) before or after passing the action
+/// on. Async middlewares only run for `Store::dispatch_async`; they wrap the
+/// synchronous middlewares, which in turn wrap the reducer.
+///
+/// The chain's futures are `Send`, so `dispatch_async` can be awaited on a
+/// multi-threaded executor. That means async middlewares have to be `Send` and
+/// `Sync`, and can't hold anything that isn't `Send` across an `.await`.
+///
+/// Implementations use the re-exported `async_trait` attribute:
+///
+/// ```
+/// # #[allow(dead_code)]
+/// # use redux::{Store, Reducer, AsyncMiddleware, AsyncDispatchFunc, async_trait};
+/// #
+/// # #[derive(Clone, Debug)]
+/// # enum FooAction {}
+/// #
+/// # #[derive(Clone, Default, Debug)]
+/// # struct Foo {}
+/// # impl Reducer for Foo {
+/// #   type Action = FooAction;
+/// #   type Error = String;
+/// #
+/// #   fn reduce(&mut self, _: Self::Action) -> Result<Self, Self::Error> {
+/// #       Ok(self.clone())
+/// #   }
+/// # }
+/// # async fn validate(_: &FooAction) -> Result<(), String> { Ok(()) }
+///
+/// struct Validator {}
+///
+/// #[async_trait]
+/// impl AsyncMiddleware<Foo> for Validator {
+///     async fn dispatch(&self, store: &Store<Foo>, action: FooAction, next: &AsyncDispatchFunc<Foo>) -> Result<Foo, String> {
+///         validate(&action).await?;
+///         next(store, action).await
+///     }
+/// }
+///
+/// let store : Store<Foo> = Store::new_async(vec![], vec![Box::new(Validator{})]);
+/// ```
+///
+/// ## Overlapping dispatches
+///
+/// Two `dispatch_async` calls polled concurrently (say, from two tasks on the
+/// same executor) can interleave inside the async middlewares wherever one of
+/// them awaits. What is guaranteed:
+///
+///  - each action passes through every async middleware, outermost first,
+///    exactly as for a single dispatch
+///  - the synchronous middlewares and the reducer run without yielding, so a
+///    reduce is never interleaved with another one
+///  - actions are reduced, published to `changes`/`actions` and handed to
+///    subscriptions in the order they reach the reducer, which is **not**
+///    necessarily the order `dispatch_async` was called in. A middleware that
+///    awaits can be overtaken by a later dispatch that doesn't.
+///  - an action is published and its subscriptions called before `next`
+///    resolves, so a middleware that awaits after `next` doesn't hold back
+///    the notifications of the dispatches reduced after it
+///
+/// If actions must be reduced in call order, a middleware has to serialize
+/// them itself, for example by holding an async lock around `next`.
+#[async_trait]
+pub trait AsyncMiddleware<T: Reducer>: Send + Sync {
+    async fn dispatch(&self, store: &Store<T>, action: T::Action, next: &AsyncDispatchFunc<T>) -> Result<T, String>;
+}
+
+/// The innermost link of the async chain: hands the action to the synchronous
+/// middlewares and reducer, and notifies straight after.
+pub(crate) fn sync_chain<T: 'static + Reducer + Send>() -> AsyncDispatchFunc<T> where T::Action: Send {
+    Box::new(|store, action| {
+        Box::pin(async move {
+            let state = store.run_chain(action.clone())?;
+            store.notify(&action, &state);
+            Ok(state)
+        })
+    })
+}
+
+pub(crate) fn build_next_async<T: 'static + Reducer + Send>(next: AsyncDispatchFunc<T>, middleware: Box<dyn AsyncMiddleware<T>>) -> AsyncDispatchFunc<T>
+    where T::Action: Send
+{
+    // the returned future outlives this call, so it needs its own handles on
+    // the middleware and the rest of the chain
+    let next = Arc::new(next);
+    let middleware: Arc<dyn AsyncMiddleware<T>> = Arc::from(middleware);
+    Box::new(move |store, action| {
+        let next = next.clone();
+        let middleware = middleware.clone();
+        Box::pin(async move { middleware.dispatch(store, action, &next).await })
+    })
+}
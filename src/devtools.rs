@@ -0,0 +1,616 @@
+use std::fmt::Debug;
+use std::io::{self, ErrorKind};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use tungstenite::Message;
+
+use crate::{DispatchFunc, History, HistoryAction, HistoryConfig, Middleware, RecentActions, RecordEverything, Reducer, Store};
+
+/// A session recorded by a `History`, in the JSON format the Redux DevTools
+/// export and import: the lifted state, with the committed state, every
+/// action and the state computed after each one.
+///
+/// Save one to a file for a colleague, who can `replay` it into a fresh store
+/// of their own:
+///
+/// ```
+/// # use redux::{DevtoolsSession, History, HistoryAction, Reducer, Store};
+/// # #[derive(Clone, Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+/// # struct Counter { count: usize }
+/// # impl Reducer for Counter {
+/// #     type Action = usize;
+/// #     type Error = String;
+/// #     fn reduce(&mut self, n: usize) -> Result<Self, String> {
+/// #         self.count += n;
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+/// let store : Store<History<Counter>> = Store::new(vec![]);
+/// let _ = store.dispatch(HistoryAction::Perform(2));
+/// let json = DevtoolsSession::from_history(&store.get_state()).to_json().unwrap();
+///
+/// let replayed : Store<History<Counter>> = DevtoolsSession::from_json(&json).unwrap().replay(vec![]).unwrap();
+/// assert_eq!(2, replayed.get_state().count);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DevtoolsSession<R: Reducer> {
+    /// The state the actions start from.
+    pub committed_state: R,
+    pub actions: Vec<R::Action>,
+    /// The indices in `actions` of the skipped ones.
+    pub skipped: Vec<usize>,
+    /// How many actions the state being shown was after.
+    pub current: usize,
+    /// The committed state, then the state after each action, as the store
+    /// that recorded the session computed them. Can be empty if the file
+    /// didn't have them.
+    pub computed_states: Vec<R>,
+}
+
+impl<R> DevtoolsSession<R>
+    where R: 'static + Reducer + Serialize + DeserializeOwned,
+          R::Action: Serialize + DeserializeOwned
+{
+    /// Everything `history` has recorded.
+    pub fn from_history<C: HistoryConfig>(history: &History<R, C>) -> DevtoolsSession<R> {
+        DevtoolsSession {
+            committed_state: history.base().clone(),
+            actions: history.actions().map(|recorded| recorded.action.clone()).collect(),
+            skipped: history.actions().enumerate()
+                .filter(|(_, recorded)| recorded.skipped)
+                .map(|(index, _)| index)
+                .collect(),
+            current: history.current(),
+            computed_states: (0..=history.len()).filter_map(|index| history.state_at(index)).collect(),
+        }
+    }
+
+    /// Reads an exported lifted state, or the `payload` and `preloadedState`
+    /// pair some versions of the DevTools write instead.
+    pub fn from_json(json: &str) -> Result<DevtoolsSession<R>, String> {
+        let exported : Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let (lifted, preloaded) = match exported["payload"].as_str() {
+            Some(payload) => {
+                let lifted : Value = serde_json::from_str(payload).map_err(|e| e.to_string())?;
+                (lifted, exported["preloadedState"].clone())
+            },
+            None => (exported.clone(), Value::Null),
+        };
+
+        let (actions, skipped, current) = match import_lifted_state(&lifted) {
+            Some(HistoryAction::Import { actions, skipped, current }) => (actions, skipped, current),
+            _ => return Err(String::from("Not a DevTools export, or its actions don't match the reducer")),
+        };
+        let committed_state = match (&lifted["committedState"], &preloaded) {
+            (Value::Null, Value::Null) => R::default(),
+            (Value::Null, state) | (state, _) => decode(state).ok_or("Can't read the committed state")?,
+        };
+        let computed_states = match lifted["computedStates"].as_array() {
+            Some(states) => states.iter()
+                .map(|computed| decode(&computed["state"]))
+                .collect::<Option<Vec<R>>>()
+                .ok_or("Can't read the computed states")?,
+            None => vec![],
+        };
+
+        Ok(DevtoolsSession { committed_state, actions, skipped, current, computed_states })
+    }
+
+    /// The session as a lifted state the DevTools can import.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        // however many actions there are, where a default `History` would
+        // commit the oldest ones
+        let mut history : History<R, RecordEverything> = History::new(self.committed_state.clone());
+        let _ = history.reduce(HistoryAction::Import {
+            actions: self.actions.clone(),
+            skipped: self.skipped.clone(),
+            current: self.current,
+        });
+        let mut lifted = lifted_state(&history)?;
+        // keep the states as they were recorded, in case this reducer differs
+        if !self.computed_states.is_empty() {
+            let computed : Vec<Value> = self.computed_states.iter()
+                .map(|state| serde_json::to_value(state).map(|state| json!({ "state": state })))
+                .collect::<Result<_, _>>()?;
+            lifted["computedStates"] = Value::Array(computed);
+        }
+
+        Ok(lifted.to_string())
+    }
+
+    /// Replays the session into a fresh store that starts from the committed
+    /// state. If the session has its computed states, the replayed final state
+    /// has to match the recorded one or it's an error.
+    pub fn replay<C>(&self, middlewares: Vec<Box<dyn Middleware<History<R, C>>>>) -> Result<Store<History<R, C>>, String>
+        where R: PartialEq + Debug,
+              C: 'static + HistoryConfig
+    {
+        let store = Store::with_state(History::new(self.committed_state.clone()), middlewares);
+        store.dispatch(HistoryAction::Import {
+            actions: self.actions.clone(),
+            skipped: self.skipped.clone(),
+            current: self.current,
+        })?;
+
+        if let Some(recorded) = self.computed_states.last() {
+            let replayed = store.get_state().latest();
+            if replayed != *recorded {
+                return Err(format!("Replaying the session ended with {:?}, but it was recorded ending with {:?}", replayed, recorded));
+            }
+        }
+
+        Ok(store)
+    }
+}
+
+// how long the server threads block before checking whether they should stop
+const POLL: Duration = Duration::from_millis(20);
+
+/// The lifted state the Redux DevTools work with: every recorded action by
+/// id, the state computed after each one, which are skipped and which state
+/// is being shown. Id `0` is the `@@INIT` action that leads to the committed
+/// state; the action recorded at index `i` has id `i + 1`, counting the ones
+/// the history trimmed, so an action keeps its id for as long as it's
+/// recorded. That's how the DevTools number them past their `maxAge` too.
+pub(crate) fn lifted_state<R, C>(history: &History<R, C>) -> Result<Value, serde_json::Error>
+    where R: Reducer + Serialize,
+          R::Action: Serialize,
+          C: HistoryConfig
+{
+    let mut actions = Map::new();
+    actions.insert(String::from("0"), perform_action(&json!({ "type": "@@INIT" }))?);
+    let mut computed = vec![json!({ "state": history.base() })];
+    let mut skipped = vec![];
+    let mut staged = vec![0];
+    for (index, recorded) in history.actions().enumerate() {
+        let id = history.trimmed() + index + 1;
+        actions.insert(id.to_string(), perform_action(&recorded.action)?);
+        staged.push(id);
+        let mut state = json!({ "state": history.state_at(index + 1) });
+        if let Some(ref error) = recorded.error {
+            state["error"] = json!(error);
+        }
+        computed.push(state);
+        if recorded.skipped {
+            skipped.push(id);
+        }
+    }
+
+    Ok(json!({
+        "actionsById": actions,
+        "computedStates": computed,
+        "committedState": history.base(),
+        "currentStateIndex": history.current(),
+        "nextActionId": history.trimmed() + history.len() + 1,
+        "skippedActionIds": skipped,
+        "stagedActionIds": staged,
+        "isLocked": false,
+        "isPaused": false,
+    }))
+}
+
+/// The `Import` that recreates a lifted state, or `None` if it isn't one or
+/// its actions don't deserialize. It's replayed on top of the committed state
+/// already in the store rather than the one in the lifted state.
+pub(crate) fn import_lifted_state<A: DeserializeOwned>(lifted: &Value) -> Option<HistoryAction<A>> {
+    let staged = lifted["stagedActionIds"].as_array()?;
+    let skipped_ids : Vec<u64> = match lifted["skippedActionIds"].as_array() {
+        Some(ids) => ids.iter().filter_map(Value::as_u64).collect(),
+        None => vec![],
+    };
+
+    let mut actions = vec![];
+    let mut skipped = vec![];
+    // the first staged action is always `@@INIT`
+    for (index, id) in staged.iter().skip(1).enumerate() {
+        let id = id.as_u64()?;
+        actions.push(decode(&lifted["actionsById"][id.to_string()]["action"])?);
+        if skipped_ids.contains(&id) {
+            skipped.push(index);
+        }
+    }
+    let current = lifted["currentStateIndex"].as_u64().map_or(actions.len(), |index| index as usize);
+
+    Some(HistoryAction::Import { actions, skipped, current })
+}
+
+fn perform_action<A: Serialize>(action: &A) -> Result<Value, serde_json::Error> {
+    Ok(json!({ "type": "PERFORM_ACTION", "action": serde_json::to_value(action)?, "timestamp": 0 }))
+}
+
+// the remote protocol sends actions and states as JSON inside strings, but
+// take them either way
+fn decode<D: DeserializeOwned>(value: &Value) -> Option<D> {
+    if let Some(text) = value.as_str() {
+        if let Ok(decoded) = serde_json::from_str(text) {
+            return Some(decoded);
+        }
+    }
+
+    D::deserialize(value).ok()
+}
+
+fn state_message<R, C>(history: &History<R, C>) -> Result<Value, serde_json::Error>
+    where R: Reducer + Serialize,
+          R::Action: Serialize,
+          C: HistoryConfig
+{
+    Ok(json!({ "type": "STATE", "payload": lifted_state(history)?.to_string() }))
+}
+
+fn action_message<R, C>(action: &R::Action, history: &History<R, C>) -> Result<Value, serde_json::Error>
+    where R: Reducer + Serialize,
+          R::Action: Serialize,
+          C: HistoryConfig
+{
+    Ok(json!({
+        "type": "ACTION",
+        "action": perform_action(action)?.to_string(),
+        "payload": serde_json::to_string(history.present())?,
+        "nextActionId": history.trimmed() + history.len() + 1,
+    }))
+}
+
+// turns a message from the monitor into the history action it asks for, in a
+// history that trimmed `trimmed` actions. The remote monitor puts what to
+// `DISPATCH` in `action`, the browser extension in `payload`
+fn monitor_action<A: DeserializeOwned>(message: &Value, trimmed: usize) -> Option<HistoryAction<A>> {
+    // the recorded index of an action id, if it's still recorded
+    let index = |id: u64| (id as usize).checked_sub(trimmed + 1);
+    match message["type"].as_str()? {
+        "ACTION" => decode(&message["action"]).map(HistoryAction::Perform),
+        "IMPORT" => import_lifted_state(&decode::<Value>(&message["state"])?),
+        "DISPATCH" => {
+            let payload = match message["action"] {
+                Value::Object(_) => &message["action"],
+                _ => &message["payload"],
+            };
+            match payload["type"].as_str()? {
+                "JUMP_TO_STATE" => Some(HistoryAction::JumpTo(payload["index"].as_u64()? as usize)),
+                "JUMP_TO_ACTION" => match payload["actionId"].as_u64()? {
+                    0 => Some(HistoryAction::JumpTo(0)),
+                    id => index(id).map(|index| HistoryAction::JumpTo(index + 1)),
+                },
+                "TOGGLE_ACTION" => index(payload["id"].as_u64()?).map(HistoryAction::Toggle),
+                "COMMIT" => Some(HistoryAction::Commit),
+                "IMPORT_STATE" => import_lifted_state(&payload["nextLiftedState"]),
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+// The SocketCluster side of the protocol, as remotedev-server speaks it:
+// events are `{"event", "data", "cid"}` objects, answered by `{"rid", "data"}`
+// or `{"rid", "error"}` with the `cid` they answer, channel messages come in
+// `#publish` events, and `#1` is a ping answered by `#2`.
+
+// how long a client may go without hearing from the server, and how often
+// it's pinged so it never does
+const PING_TIMEOUT: Duration = Duration::from_secs(20);
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+
+// monitors log in to watch the `log` channel, where the app publishes, and
+// send to the app on `respond` or on the app's own `sc-<id>` channel
+const LOG_CHANNEL: &str = "log";
+const RESPOND_CHANNEL: &str = "respond";
+
+fn reply(cid: &Value, data: Value) -> String {
+    json!({ "rid": cid, "data": data }).to_string()
+}
+
+fn reply_error(cid: &Value, error: &str) -> String {
+    json!({ "rid": cid, "error": error }).to_string()
+}
+
+fn publish(channel: &str, data: Value) -> String {
+    json!({ "event": "#publish", "data": { "channel": channel, "data": data } }).to_string()
+}
+
+struct Server<R: Reducer, C: HistoryConfig> {
+    store: Mutex<Weak<Store<History<R, C>>>>,
+    // the monitors subscribed to the `log` channel
+    clients: Mutex<Vec<Sender<String>>>,
+    next_socket: AtomicUsize,
+    closed: AtomicBool,
+    address: SocketAddr,
+}
+
+impl<R, C> Server<R, C>
+    where R: 'static + Reducer + Serialize + Send + Sync,
+          R::Action: Serialize + DeserializeOwned + Send,
+          C: 'static + HistoryConfig
+{
+    // the store is the one app on this server, and names itself after it
+    fn app_id(&self) -> String {
+        format!("redux-{}", self.address.port())
+    }
+
+    // whether any monitor is connected to hear a broadcast
+    fn has_clients(&self) -> bool {
+        !self.clients.lock().unwrap().is_empty()
+    }
+
+    // publishes an app message on the `log` channel, to every monitor
+    fn broadcast(&self, message: Value) {
+        let message = publish(LOG_CHANNEL, self.signed(message));
+        self.clients.lock().unwrap().retain(|client| client.send(message.clone()).is_ok());
+    }
+
+    // app messages carry the id monitors answer the app on, and its name
+    fn signed(&self, mut message: Value) -> Value {
+        message["id"] = json!(self.app_id());
+        message["name"] = json!("redux");
+        message
+    }
+
+    fn is_running(server: &Weak<Server<R, C>>) -> bool {
+        server.upgrade().is_some_and(|server| !server.closed.load(Ordering::SeqCst))
+    }
+
+    fn accept(server: Weak<Server<R, C>>, listener: TcpListener) {
+        while Server::is_running(&server) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let server = server.clone();
+                    thread::spawn(move || Server::serve(server, stream));
+                },
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(POLL),
+                Err(_) => return,
+            }
+        }
+    }
+
+    fn serve(server: Weak<Server<R, C>>, stream: TcpStream) {
+        // some platforms hand out accepted streams non-blocking like the listener
+        if stream.set_nonblocking(false).is_err() || stream.set_read_timeout(Some(POLL)).is_err() {
+            return;
+        }
+        let mut socket = match tungstenite::accept(stream) {
+            Ok(socket) => socket,
+            Err(_) => return,
+        };
+
+        let socket_id = match server.upgrade() {
+            Some(server) => server.next_socket.fetch_add(1, Ordering::SeqCst).to_string(),
+            None => return,
+        };
+        let (sender, outgoing) = channel();
+        let mut pinged = Instant::now();
+        while Server::is_running(&server) {
+            for message in outgoing.try_iter() {
+                if socket.send(Message::Text(message)).is_err() {
+                    return;
+                }
+            }
+            if pinged.elapsed() >= PING_INTERVAL {
+                if socket.send(Message::Text(String::from("#1"))).is_err() {
+                    return;
+                }
+                pinged = Instant::now();
+            }
+            match socket.read() {
+                Ok(Message::Text(text)) => {
+                    if let Some(server) = server.upgrade() {
+                        server.receive(&socket_id, &text, &sender);
+                    }
+                },
+                Ok(_) => {},
+                Err(tungstenite::Error::Io(ref e)) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {},
+                Err(_) => return,
+            }
+        }
+    }
+
+    // answers one frame from a client, through `client`
+    fn receive(&self, socket_id: &str, text: &str, client: &Sender<String>) {
+        if text == "#1" {
+            let _ = client.send(String::from("#2"));
+            return;
+        }
+        let frame : Value = match serde_json::from_str(text) {
+            Ok(frame) => frame,
+            Err(_) => return,
+        };
+        let cid = &frame["cid"];
+        let data = &frame["data"];
+        let event = match frame["event"].as_str() {
+            Some(event) => event,
+            None => return,
+        };
+
+        // only events with a `cid` expect an answer, and it comes before
+        // anything the event makes the server publish
+        if !cid.is_null() {
+            let answer = match event {
+                "#handshake" => reply(cid, json!({
+                    "id": socket_id,
+                    "pingTimeout": PING_TIMEOUT.as_millis() as u64,
+                    "isAuthenticated": false,
+                })),
+                // another app, which this server has no room for
+                "login" if data == "master" => reply_error(cid, "This server only serves its own store"),
+                "login" => reply(cid, json!(LOG_CHANNEL)),
+                _ => reply(cid, Value::Null),
+            };
+            let _ = client.send(answer);
+        }
+
+        match event {
+            "#subscribe" if data["channel"] == LOG_CHANNEL => self.connect(client.clone()),
+            "#publish" => self.respond(&data["channel"], &data["data"], client),
+            event => self.respond(&json!(event), data, client),
+        }
+    }
+
+    // the monitor starts from the whole lifted state, then gets updates
+    fn connect(&self, client: Sender<String>) {
+        let mut clients = self.clients.lock().unwrap();
+        let _ = client.send(publish(LOG_CHANNEL, self.signed(json!({ "type": "START" }))));
+        self.send_state(&client);
+        clients.push(client);
+    }
+
+    fn send_state(&self, client: &Sender<String>) {
+        if let Some(store) = self.store.lock().unwrap().upgrade() {
+            if let Ok(message) = state_message(&store.get_state()) {
+                let _ = client.send(publish(LOG_CHANNEL, self.signed(message)));
+            }
+        }
+    }
+
+    // handles a message a monitor sent to the app on `channel`
+    fn respond(&self, channel: &Value, message: &Value, client: &Sender<String>) {
+        let channel = channel.as_str().unwrap_or("");
+        if channel != RESPOND_CHANNEL && channel != format!("sc-{}", self.app_id()) {
+            return;
+        }
+        if message["type"] == "START" || message["type"] == "UPDATE" {
+            self.send_state(client);
+            return;
+        }
+
+        let store = match self.store.lock().unwrap().upgrade() {
+            Some(store) => store,
+            None => return,
+        };
+        if let Some(action) = monitor_action(message, store.get_state().trimmed()) {
+            let _ = store.dispatch(action);
+        }
+    }
+}
+
+/// Serves a store to the Redux DevTools remote monitor over a local
+/// WebSocket, standing in for the remotedev-server the monitor expects to
+/// connect to, with the store as the one app on it.
+///
+/// It speaks the SocketCluster protocol remotedev-server does: monitors
+/// `#handshake`, `login` to be told to watch the `log` channel, and
+/// `#subscribe` to it, after which the store is published there. Monitors
+/// are pinged with `#1` and answer `#2`.
+///
+/// The store's reducer has to be a `History`, which is what the DevTools
+/// travel through. A monitor that subscribes is sent a `START` then the whole
+/// lifted state in a `STATE` message, then an `ACTION` message for each
+/// action performed after that, with both serialized by serde. On `respond`,
+/// or the app's own `sc-<id>` channel, the monitor can send `START` or
+/// `UPDATE` for a fresh `STATE`, `ACTION` and `IMPORT`, or `DISPATCH` a
+/// `JUMP_TO_STATE`, `JUMP_TO_ACTION`, `TOGGLE_ACTION`, `COMMIT` or
+/// `IMPORT_STATE`. Each is dispatched to the store as the matching
+/// `HistoryAction`, and every monitor is sent the result. Actions keep their
+/// ids when the history trims the oldest ones, the way the DevTools' own
+/// `maxAge` works.
+///
+/// Like `ListenerMiddleware`, it's a middleware that has to be `attach`ed to
+/// the store it was given to:
+///
+/// ```
+/// # use redux::{DevtoolsServer, History, Reducer, Store};
+/// # use std::sync::Arc;
+/// # #[derive(Clone, Default, serde::Serialize)]
+/// # struct Counter { count: usize }
+/// # impl Reducer for Counter {
+/// #     type Action = usize;
+/// #     type Error = String;
+/// #     fn reduce(&mut self, n: usize) -> Result<Self, String> {
+/// #         self.count += n;
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+/// let devtools = DevtoolsServer::bind("127.0.0.1:0").unwrap();
+/// let store : Arc<Store<History<Counter>>> = Arc::new(Store::new(vec![Box::new(devtools.clone())]));
+/// devtools.attach(&store);
+/// println!("connect the monitor to ws://{}", devtools.local_addr());
+/// ```
+///
+/// The server stops once it's `close`d or every clone of it is dropped.
+pub struct DevtoolsServer<R: Reducer, C: HistoryConfig = RecentActions> {
+    server: Arc<Server<R, C>>,
+}
+
+impl<R: Reducer, C: HistoryConfig> Clone for DevtoolsServer<R, C> {
+    fn clone(&self) -> Self {
+        DevtoolsServer { server: self.server.clone() }
+    }
+}
+
+impl<R, C> DevtoolsServer<R, C>
+    where R: 'static + Reducer + Serialize + Send + Sync,
+          R::Action: Serialize + DeserializeOwned + Send,
+          C: 'static + HistoryConfig
+{
+    /// Starts listening for monitors on `address`. Bind to port `0` to have
+    /// one picked, then ask `local_addr` which.
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<DevtoolsServer<R, C>> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let server = Arc::new(Server {
+            store: Mutex::new(Weak::new()),
+            clients: Mutex::new(vec![]),
+            next_socket: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+            address: listener.local_addr()?,
+        });
+
+        let accepting = Arc::downgrade(&server);
+        thread::spawn(move || Server::accept(accepting, listener));
+
+        Ok(DevtoolsServer { server })
+    }
+
+    /// Points the server at `store`, which should be the store this
+    /// middleware was given to. Only a weak reference is kept.
+    pub fn attach(&self, store: &Arc<Store<History<R, C>>>) {
+        *self.server.store.lock().unwrap() = Arc::downgrade(store);
+    }
+
+    /// The address monitors connect to, as they would to remotedev-server.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.server.address
+    }
+
+    /// Stops accepting monitors and disconnects the ones connected.
+    pub fn close(&self) {
+        self.server.closed.store(true, Ordering::SeqCst);
+    }
+}
+
+impl<R, C> Middleware<History<R, C>> for DevtoolsServer<R, C>
+    where R: 'static + Reducer + Serialize + Send + Sync,
+          R::Action: Serialize + DeserializeOwned + Send,
+          C: 'static + HistoryConfig
+{
+    fn dispatch(&self, store: &Store<History<R, C>>, action: HistoryAction<R::Action>, next: &DispatchFunc<History<R, C>>) -> Result<History<R, C>, String> {
+        let performed = match action {
+            HistoryAction::Perform(ref action) => Some(action.clone()),
+            _ => None,
+        };
+        let history = next(store, action)?;
+        // a monitor that connects later is sent the whole state anyway, so
+        // with none listening there's nothing to serialize
+        if !self.server.has_clients() {
+            return Ok(history);
+        }
+
+        // an action performed while looking at the past doesn't change what
+        // the monitor shows, so it needs the whole lifted state
+        let message = match performed {
+            Some(ref action) if history.is_at_latest() => action_message(action, &history),
+            _ => state_message(&history),
+        };
+        if let Ok(message) = message {
+            self.server.broadcast(message);
+        }
+
+        Ok(history)
+    }
+}
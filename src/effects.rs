@@ -0,0 +1,164 @@
+use std::fmt::Display;
+use std::ops::Deref;
+
+use crate::{Middleware, MiddlewareApi, Reducer, Store};
+
+/// A reducer that, besides changing its state, describes the side effects an
+/// action should cause (Elm's `update` returning commands). Effects are plain
+/// data; an `EffectRunner` decides what to do with them once the new state has
+/// been committed.
+///
+/// Because `reduce` just returns the effects, tests can call it directly and
+/// assert on them without anything being run.
+///
+/// ```
+/// use redux::EffectReducer;
+///
+/// #[derive(Clone, Default)]
+/// struct Session {
+///     user: Option<String>,
+/// }
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// enum SessionEffect {
+///     LoadProfile(String),
+/// }
+///
+/// impl EffectReducer for Session {
+///     type Action = String;
+///     type Error = String;
+///     type Effect = SessionEffect;
+///
+///     fn reduce(&mut self, user: Self::Action) -> Result<Vec<Self::Effect>, Self::Error> {
+///         self.user = Some(user.clone());
+///         Ok(vec![SessionEffect::LoadProfile(user)])
+///     }
+/// }
+///
+/// let mut session = Session::default();
+/// let effects = session.reduce(String::from("jared")).unwrap();
+/// assert_eq!(vec![SessionEffect::LoadProfile(String::from("jared"))], effects);
+/// ```
+pub trait EffectReducer: Clone + Default {
+    /// The type of action that this reducer can accept, probably an enum
+    type Action: Clone;
+
+    /// The type of error this reducer can return in the `Result`
+    type Error: Display;
+
+    /// The type of effect this reducer can ask for, probably an enum
+    type Effect: Clone;
+
+    /// Reduce a given state based upon an action, returning the effects the
+    /// action should cause.
+    fn reduce(&mut self, action: Self::Action) -> Result<Vec<Self::Effect>, Self::Error>;
+}
+
+/// Wraps an `EffectReducer` so it can be used as the state of a `Store`,
+/// collecting the effects returned by each reduce until the store hands them
+/// to its `EffectRunner`.
+///
+/// A `Store<Effects<R>>` made with `Store::new` never runs anything, so the
+/// effects just pile up where `effects()` can see them; use
+/// `Store::with_effects` to have them run.
+pub struct Effects<R: EffectReducer> {
+    state: R,
+    pending: Vec<R::Effect>,
+}
+
+impl<R: EffectReducer> Effects<R> {
+    /// The wrapped state.
+    pub fn state(&self) -> &R {
+        &self.state
+    }
+
+    /// Effects that have been returned by the reducer but not run yet.
+    pub fn effects(&self) -> &[R::Effect] {
+        &self.pending
+    }
+}
+
+impl<R: EffectReducer> Deref for Effects<R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        &self.state
+    }
+}
+
+impl<R: EffectReducer> Clone for Effects<R> {
+    fn clone(&self) -> Self {
+        Effects {
+            state: self.state.clone(),
+            pending: self.pending.clone(),
+        }
+    }
+}
+
+impl<R: EffectReducer> Default for Effects<R> {
+    fn default() -> Self {
+        Effects {
+            state: R::default(),
+            pending: vec![],
+        }
+    }
+}
+
+impl<R: EffectReducer> Reducer for Effects<R> {
+    type Action = R::Action;
+    type Error = R::Error;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        let effects = self.state.reduce(action)?;
+        self.pending.extend(effects);
+
+        Ok(self.clone())
+    }
+}
+
+/// Executes the effects returned by an `EffectReducer`. Runners are called
+/// after the reduce that produced the effect has committed and the store's
+/// lock has been released, so they're free to dispatch follow-up actions
+/// through `api`.
+///
+/// Any `Fn(R::Effect, &dyn MiddlewareApi<Effects<R>>)` is a runner.
+pub trait EffectRunner<R: EffectReducer> {
+    fn run(&self, effect: R::Effect, api: &dyn MiddlewareApi<Effects<R>>);
+}
+
+impl<R, F> EffectRunner<R> for F
+    where R: EffectReducer,
+          F: Fn(R::Effect, &dyn MiddlewareApi<Effects<R>>)
+{
+    fn run(&self, effect: R::Effect, api: &dyn MiddlewareApi<Effects<R>>) {
+        self(effect, api)
+    }
+}
+
+impl<R: 'static + EffectReducer> Store<Effects<R>> {
+    /// Initialize a new `Store` whose effects are handed to `runner`.
+    ///
+    /// After every successful dispatch the store takes the effects that are
+    /// waiting and runs them in the order they were returned, before any
+    /// subscription is called. Effects caused by follow-up dispatches made
+    /// from the runner are run by those dispatches, before the runner moves
+    /// on to the next effect.
+    pub fn with_effects<E>(middlewares: Vec<Box<dyn Middleware<Effects<R>>>>, runner: E) -> Store<Effects<R>>
+        where E: 'static + Send + Sync + EffectRunner<R>
+    {
+        let mut store = Store::new(middlewares);
+        store.effect_runner = Some(Box::new(move |store| {
+            // take the effects while holding the lock, but run them after
+            // it's been released so they can dispatch
+            let effects = {
+                let mut internal = store.internal_store.lock().unwrap();
+                std::mem::take(&mut internal.data.pending)
+            };
+            for effect in effects {
+                runner.run(effect, store);
+            }
+        }));
+
+        store
+    }
+}
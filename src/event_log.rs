@@ -0,0 +1,393 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::migrations::{stamp, unstamp};
+use crate::{DispatchFunc, Middleware, MigrationError, Migrations, Reducer, Store};
+
+// every record starts with its length and checksum, both little endian u32s
+const HEADER: usize = 8;
+
+// the payloads of a log's records, with their offsets
+pub(crate) type Records<'a> = Vec<(u64, &'a [u8])>;
+
+/// When an `ActionLog` asks the OS to flush appended records to disk.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SyncPolicy {
+    /// After every action. Nothing committed is ever lost, but it's slow.
+    EveryAction,
+    /// After every this many actions, and when the log is dropped. A crash
+    /// can lose the actions since the last sync.
+    Batched(usize),
+    /// Never; the OS writes the records out when it likes.
+    Never,
+}
+
+/// How `Store::from_log` opens a log.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LogOptions {
+    pub sync: SyncPolicy,
+    /// Only replay the log: it's never written to, not even to drop a torn
+    /// last record, and actions dispatched to the store aren't logged.
+    pub read_only: bool,
+}
+
+impl Default for LogOptions {
+    fn default() -> Self {
+        LogOptions {
+            sync: SyncPolicy::EveryAction,
+            read_only: false,
+        }
+    }
+}
+
+/// What can go wrong reading or writing an `ActionLog`. Offsets are in bytes
+/// from the start of the log file, pointing at the start of a record.
+#[derive(Debug)]
+pub enum LogError {
+    Io(io::Error),
+    /// A record is cut short, empty or doesn't match its checksum, and there
+    /// are whole records after it. A bad record with nothing whole after it
+    /// is a torn write instead, and is dropped.
+    Corrupt { offset: u64 },
+    /// An action couldn't be serialized or a record deserialized.
+    Serde { offset: u64, message: String },
+    /// A logged action failed when it was replayed through the reducer.
+    Replay { offset: u64, message: String },
+    /// A logged action couldn't be upcast from the version it was logged at
+    /// to the current one.
+    Upcast { offset: u64, message: String },
+    /// The snapshot of the first this many actions couldn't be migrated to
+    /// the current schema version.
+    Migration { sequence: u64, message: String },
+    /// The actions from this sequence number on aren't in the log, usually
+    /// because every snapshot that covered them is corrupt and the log was
+    /// compacted.
+    Missing { sequence: u64 },
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LogError::Io(ref e) => write!(f, "{}", e),
+            LogError::Corrupt { offset } => write!(f, "The record at offset {} is corrupt", offset),
+            LogError::Serde { offset, ref message } => write!(f, "The record at offset {} can't be read: {}", offset, message),
+            LogError::Replay { offset, ref message } => write!(f, "The action at offset {} failed to replay: {}", offset, message),
+            LogError::Upcast { offset, ref message } => write!(f, "The action at offset {} can't be upcast: {}", offset, message),
+            LogError::Migration { sequence, ref message } => write!(f, "The snapshot of the first {} actions can't be migrated: {}", sequence, message),
+            LogError::Missing { sequence } => write!(f, "The log is missing the actions from number {} on", sequence),
+        }
+    }
+}
+
+impl Error for LogError {}
+
+impl From<io::Error> for LogError {
+    fn from(e: io::Error) -> Self {
+        LogError::Io(e)
+    }
+}
+
+// a record holding `payload`
+pub(crate) fn encode(payload: &[u8]) -> Vec<u8> {
+    let mut record = Vec::with_capacity(HEADER + payload.len());
+    record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+    record.extend_from_slice(payload);
+    record
+}
+
+// the payload of the record at `offset`, if a whole one with a matching
+// checksum starts there. Nothing ever logs an empty payload, so a zero length
+// isn't a record either: the checksum of nothing is 0, so otherwise a tail of
+// zeroes would pass for records.
+fn record_at(bytes: &[u8], offset: usize) -> Option<&[u8]> {
+    let header = bytes.get(offset..offset + HEADER)?;
+    let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let checksum = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    let payload = bytes.get(offset + HEADER..offset + HEADER + len)?;
+    if len == 0 || crc32fast::hash(payload) != checksum {
+        return None;
+    }
+
+    Some(payload)
+}
+
+// splits a log file into its records, plus how many bytes of it are whole
+// records
+pub(crate) fn scan(bytes: &[u8]) -> Result<(Records<'_>, usize), LogError> {
+    let mut records = vec![];
+    let mut offset = 0;
+    while offset < bytes.len() {
+        match record_at(bytes, offset) {
+            Some(payload) => {
+                records.push((offset as u64, payload));
+                offset += HEADER + payload.len();
+            },
+            // a torn write only ever leaves a bad record at the very end, so
+            // one with a whole record anywhere after it is corrupt, even if
+            // its length claims it runs past the end of the file
+            None if (offset + 1..bytes.len()).any(|next| record_at(bytes, next).is_some()) => {
+                return Err(LogError::Corrupt { offset: offset as u64 });
+            },
+            None => break,
+        }
+    }
+
+    Ok((records, offset))
+}
+
+// `MigrationError` talks about saved state, which a logged action isn't
+fn upcast_error(e: MigrationError) -> String {
+    match e {
+        MigrationError::FutureVersion { version, current } => format!("The action is from schema version {}, but this is only version {}", version, current),
+        e => e.to_string(),
+    }
+}
+
+/// An append-only file of actions. Each record is the action serialized as
+/// JSON and stamped with the schema version it was logged at, prefixed with
+/// its length and a CRC-32 checksum.
+///
+/// A crash in the middle of an append leaves a torn last record, which
+/// reading skips and opening for writing cuts off. Anything else that doesn't
+/// read back is `LogError::Corrupt`, and the file is left as it is.
+pub struct ActionLog<A> {
+    file: File,
+    path: PathBuf,
+    sync: SyncPolicy,
+    version: u32,
+    len: u64,
+    unsynced: usize,
+    actions: PhantomData<fn(A)>,
+}
+
+impl<A: Serialize + DeserializeOwned> ActionLog<A> {
+    /// Opens the log at `path` for appending, creating it if it's missing.
+    /// Actions are logged at version 0 unless it's changed with `versioned`.
+    pub fn open<P: AsRef<Path>>(path: P, sync: SyncPolicy) -> Result<ActionLog<A>, LogError> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().read(true).append(true).create(true).open(&path)?;
+        let bytes = fs::read(&path)?;
+        let (_, len) = scan(&bytes)?;
+        if len < bytes.len() {
+            file.set_len(len as u64)?;
+            file.sync_all()?;
+        }
+
+        Ok(ActionLog {
+            file,
+            path,
+            sync,
+            version: 0,
+            len: len as u64,
+            unsynced: 0,
+            actions: PhantomData,
+        })
+    }
+
+    /// Logs actions from now on at `version`, which should be the current
+    /// version of the upcasters the log is read with.
+    pub fn versioned(mut self, version: u32) -> ActionLog<A> {
+        self.version = version;
+        self
+    }
+
+    /// Every action in the log at `path` with its offset, without changing
+    /// the file.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Vec<(u64, A)>, LogError> {
+        ActionLog::read_upcasting(path, &Migrations::default())
+    }
+
+    /// Like `read`, but actions logged at older versions are brought up to
+    /// date with `upcasters` before they're deserialized.
+    pub fn read_upcasting<P: AsRef<Path>>(path: P, upcasters: &Migrations) -> Result<Vec<(u64, A)>, LogError> {
+        let bytes = fs::read(path)?;
+        let (records, _) = scan(&bytes)?;
+        records.into_iter()
+            .map(|(offset, payload)| {
+                let serde = |e: serde_json::Error| LogError::Serde { offset, message: e.to_string() };
+                let (version, action) = unstamp(serde_json::from_slice(payload).map_err(serde)?, "action");
+                let action = upcasters.migrate(version, action).map_err(|e| LogError::Upcast { offset, message: upcast_error(e) })?;
+                serde_json::from_value(action).map(|action| (offset, action)).map_err(serde)
+            })
+            .collect()
+    }
+
+    /// Adds `action` to the end of the log, returning its offset.
+    pub fn append(&mut self, action: &A) -> Result<u64, LogError> {
+        let offset = self.len;
+        let payload = serde_json::to_value(action)
+            .and_then(|action| serde_json::to_vec(&stamp(self.version, "action", action)))
+            .map_err(|e| LogError::Serde { offset, message: e.to_string() })?;
+        let record = encode(&payload);
+        self.file.write_all(&record)?;
+        self.len += record.len() as u64;
+
+        self.unsynced += 1;
+        match self.sync {
+            SyncPolicy::EveryAction => self.sync()?,
+            SyncPolicy::Batched(every) if self.unsynced >= every => self.sync()?,
+            _ => {},
+        }
+
+        Ok(offset)
+    }
+
+    /// Flushes every appended record to disk.
+    pub fn sync(&mut self) -> Result<(), LogError> {
+        self.file.sync_data()?;
+        self.unsynced = 0;
+        Ok(())
+    }
+
+    /// Where the log is.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The size of the log in bytes, which is also the offset the next
+    /// record goes at.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<A> Drop for ActionLog<A> {
+    fn drop(&mut self) {
+        if self.unsynced > 0 && self.sync != SyncPolicy::Never {
+            let _ = self.file.sync_data();
+        }
+    }
+}
+
+/// Replays the log at `path` on top of `state`, reducing each action in
+/// turn.
+pub(crate) fn replay<T, P>(path: P, mut state: T, upcasters: &Migrations) -> Result<T, LogError>
+    where T: Reducer,
+          T::Action: Serialize + DeserializeOwned,
+          P: AsRef<Path>
+{
+    for (offset, action) in ActionLog::<T::Action>::read_upcasting(path, upcasters)? {
+        state.reduce(action).map_err(|e| LogError::Replay { offset, message: e.to_string() })?;
+    }
+
+    Ok(state)
+}
+
+/// Appends every action that reduces successfully to an `ActionLog`, so the
+/// state can be rebuilt from it with `Store::from_log`.
+///
+/// The log is locked from before the action is reduced until it's written,
+/// so the log has actions in the order they were reduced. That means a
+/// middleware that comes before it in the list can't dispatch from inside
+/// `dispatch`, so this one should usually go first.
+pub struct PersistenceMiddleware<T: Reducer> {
+    log: Arc<Mutex<ActionLog<T::Action>>>,
+}
+
+impl<T: Reducer> Clone for PersistenceMiddleware<T> {
+    fn clone(&self) -> Self {
+        PersistenceMiddleware { log: self.log.clone() }
+    }
+}
+
+impl<T: Reducer> PersistenceMiddleware<T> where T::Action: Serialize + DeserializeOwned {
+    pub fn new(log: ActionLog<T::Action>) -> PersistenceMiddleware<T> {
+        PersistenceMiddleware { log: Arc::new(Mutex::new(log)) }
+    }
+
+    /// Flushes every logged action to disk, whatever the `SyncPolicy`.
+    pub fn sync(&self) -> Result<(), LogError> {
+        self.log.lock().unwrap().sync()
+    }
+}
+
+impl<T: Reducer> Middleware<T> for PersistenceMiddleware<T> where T::Action: Serialize + DeserializeOwned {
+    fn dispatch(&self, store: &Store<T>, action: T::Action, next: &DispatchFunc<T>) -> Result<T, String> {
+        let mut log = self.log.lock().unwrap();
+        let state = next(store, action.clone())?;
+        log.append(&action).map_err(|e| format!("The action was reduced but not logged: {}", e))?;
+
+        Ok(state)
+    }
+}
+
+impl<T: 'static + Reducer> Store<T> where T::Action: Serialize + DeserializeOwned {
+    /// Rebuilds a store's state by replaying the log at `path` through the
+    /// reducer, starting from the default state. Unless the log is opened
+    /// read only, a `PersistenceMiddleware` is put in front of `middlewares`
+    /// so every action from now on is appended to the log too.
+    ///
+    /// ```no_run
+    /// # use redux::{LogOptions, Reducer, Store, SyncPolicy};
+    /// # #[derive(Clone, Default)]
+    /// # struct Counter { count: usize }
+    /// # impl Reducer for Counter {
+    /// #     type Action = usize;
+    /// #     type Error = String;
+    /// #     fn reduce(&mut self, n: usize) -> Result<Self, String> {
+    /// #         self.count += n;
+    /// #         Ok(self.clone())
+    /// #     }
+    /// # }
+    /// let options = LogOptions { sync: SyncPolicy::Batched(100), ..LogOptions::default() };
+    /// let store : Store<Counter> = Store::from_log("counter.log", options, vec![]).unwrap();
+    /// ```
+    pub fn from_log<P: AsRef<Path>>(path: P, options: LogOptions, middlewares: Vec<Box<dyn Middleware<T>>>) -> Result<Store<T>, LogError> {
+        Store::from_versioned_log(path, options, &Migrations::default(), middlewares)
+    }
+
+    /// Like `from_log`, for actions whose serialized form has changed: new
+    /// actions are logged at the current version of `upcasters`, and older
+    /// ones are brought up to it before they're replayed. A replay that fails
+    /// to upcast says at which offset.
+    ///
+    /// ```no_run
+    /// # use redux::{LogOptions, Migrations, Reducer, Store};
+    /// # #[derive(Clone, serde::Serialize, serde::Deserialize)]
+    /// # enum TodoAction { NewTodo { title: String } }
+    /// # #[derive(Clone, Default)]
+    /// # struct Todos { titles: Vec<String> }
+    /// # impl Reducer for Todos {
+    /// #     type Action = TodoAction;
+    /// #     type Error = String;
+    /// #     fn reduce(&mut self, action: TodoAction) -> Result<Self, String> {
+    /// #         let TodoAction::NewTodo { title } = action;
+    /// #         self.titles.push(title);
+    /// #         Ok(self.clone())
+    /// #     }
+    /// # }
+    /// // version 1 renamed `NewTodo`'s `name` to `title`
+    /// let upcasters = Migrations::new(1).add(0, |mut action| {
+    ///     if let Some(new_todo) = action.get_mut("NewTodo") {
+    ///         new_todo["title"] = new_todo["name"].take();
+    ///     }
+    ///     Ok(action)
+    /// });
+    /// let store : Store<Todos> = Store::from_versioned_log("todos.log", LogOptions::default(), &upcasters, vec![]).unwrap();
+    /// ```
+    pub fn from_versioned_log<P: AsRef<Path>>(path: P, options: LogOptions, upcasters: &Migrations, mut middlewares: Vec<Box<dyn Middleware<T>>>) -> Result<Store<T>, LogError> {
+        if options.read_only {
+            let state = replay(path, T::default(), upcasters)?;
+            return Ok(Store::with_state(state, middlewares));
+        }
+
+        let log = ActionLog::open(&path, options.sync)?.versioned(upcasters.current());
+        let state = replay(path, T::default(), upcasters)?;
+        middlewares.insert(0, Box::new(PersistenceMiddleware::new(log)));
+
+        Ok(Store::with_state(state, middlewares))
+    }
+}
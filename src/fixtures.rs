@@ -0,0 +1,224 @@
+use std::fmt::{self, Debug};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use libtest_mimic::{Arguments, Trial};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::testing::given;
+use crate::Reducer;
+
+/// A fixture that didn't pass, and why.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixtureFailure {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for FixtureFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+/// Reducer scenarios written as data files, for people who'd rather not
+/// write Rust. Each `.json`, `.yaml` or `.yml` file in a directory, or any
+/// directory under it, is one scenario:
+///
+/// ```yaml
+/// # the default state when it's left out
+/// initial:
+///   names: []
+/// actions:
+///   - NewTodo: { name: write tests }
+///   - Complete: 3
+/// # the first action that fails must fail with this
+/// error: no todo 3
+/// # and afterwards, or after every action succeeded, this is the state
+/// state:
+///   names: [write tests]
+/// ```
+///
+/// At least one of `error` and `state` has to be there. States and actions
+/// are deserialized with serde, so they're written the way serde reads `T`
+/// and `T::Action`.
+///
+/// To have every scenario show up as a test of its own, so they can be
+/// filtered and each one passes or fails by itself, give them a test target
+/// without the default harness:
+///
+/// ```toml
+/// [[test]]
+/// name = "scenarios"
+/// harness = false
+/// ```
+///
+/// ```no_run
+/// # use redux::Reducer;
+/// # use redux::testing::Fixtures;
+/// # #[derive(Clone, Default, Debug, PartialEq, serde::Deserialize)]
+/// # struct TodoList { names: Vec<String> }
+/// # impl Reducer for TodoList {
+/// #     type Action = String;
+/// #     type Error = String;
+/// #     fn reduce(&mut self, name: String) -> Result<Self, String> {
+/// #         self.names.push(name);
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+/// // tests/scenarios.rs
+/// fn main() {
+///     Fixtures::new("tests/fixtures/todos").test_main::<TodoList>();
+/// }
+/// ```
+///
+/// Or run them all from one regular test:
+///
+/// ```no_run
+/// # use redux::Reducer;
+/// # use redux::testing::Fixtures;
+/// # #[derive(Clone, Default, Debug, PartialEq, serde::Deserialize)]
+/// # struct TodoList { names: Vec<String> }
+/// # impl Reducer for TodoList {
+/// #     type Action = String;
+/// #     type Error = String;
+/// #     fn reduce(&mut self, name: String) -> Result<Self, String> {
+/// #         self.names.push(name);
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+/// #[test]
+/// fn qa_scenarios() {
+///     Fixtures::new("tests/fixtures/todos").assert_all::<TodoList>();
+/// }
+/// ```
+pub struct Fixtures {
+    dir: PathBuf,
+}
+
+impl Fixtures {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Fixtures {
+        Fixtures { dir: dir.into() }
+    }
+
+    /// Every fixture file under the directory, in order.
+    pub fn files(&self) -> io::Result<Vec<PathBuf>> {
+        let mut files = vec![];
+        let mut dirs = vec![self.dir.clone()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if path.extension().is_some_and(|extension| extension == "json" || extension == "yaml" || extension == "yml") {
+                    files.push(path);
+                }
+            }
+        }
+        files.sort();
+
+        Ok(files)
+    }
+
+    /// Runs every fixture, each on its own, returning every one that
+    /// failed.
+    pub fn run<T>(&self) -> Result<Vec<FixtureFailure>, io::Error>
+        where T: Reducer + DeserializeOwned + Debug + PartialEq,
+              T::Action: DeserializeOwned + Debug
+    {
+        let failures = self.files()?.into_iter()
+            .filter_map(|path| match run_fixture::<T>(&path) {
+                Ok(()) => None,
+                Err(message) => Some(FixtureFailure { path, message }),
+            })
+            .collect();
+
+        Ok(failures)
+    }
+
+    /// A test for every fixture, named after its path in the directory, to
+    /// run with `libtest_mimic::run`.
+    pub fn trials<T>(&self) -> io::Result<Vec<Trial>>
+        where T: 'static + Reducer + DeserializeOwned + Debug + PartialEq,
+              T::Action: DeserializeOwned + Debug
+    {
+        let trials = self.files()?.into_iter()
+            .map(|path| {
+                let name = path.strip_prefix(&self.dir).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+                Trial::test(name, move || run_fixture::<T>(&path).map_err(Into::into))
+            })
+            .collect();
+
+        Ok(trials)
+    }
+
+    /// Runs every fixture as its own test, taking the usual test arguments
+    /// from the command line, and exits. This is the `main` of a test target
+    /// with `harness = false`.
+    pub fn test_main<T>(&self) -> !
+        where T: 'static + Reducer + DeserializeOwned + Debug + PartialEq,
+              T::Action: DeserializeOwned + Debug
+    {
+        let trials = self.trials::<T>().unwrap_or_else(|e| panic!("Can't read the fixtures in {}: {}", self.dir.display(), e));
+        if trials.is_empty() {
+            panic!("There are no fixtures in {}", self.dir.display());
+        }
+
+        libtest_mimic::run(&Arguments::from_args(), trials).exit()
+    }
+
+    /// Runs every fixture and panics listing all the ones that failed, or if
+    /// there are none at all, which is usually a wrong path.
+    pub fn assert_all<T>(&self)
+        where T: Reducer + DeserializeOwned + Debug + PartialEq,
+              T::Action: DeserializeOwned + Debug
+    {
+        let files = self.files().unwrap_or_else(|e| panic!("Can't read the fixtures in {}: {}", self.dir.display(), e));
+        if files.is_empty() {
+            panic!("There are no fixtures in {}", self.dir.display());
+        }
+
+        let failures = self.run::<T>().unwrap_or_else(|e| panic!("Can't read the fixtures in {}: {}", self.dir.display(), e));
+        if !failures.is_empty() {
+            let report : Vec<String> = failures.iter().map(|failure| failure.to_string()).collect();
+            panic!("{} of {} fixtures failed:\n\n{}", failures.len(), files.len(), report.join("\n\n"));
+        }
+    }
+}
+
+/// Runs the fixture in one file, describing what went wrong if it fails.
+pub fn run_fixture<T>(path: &Path) -> Result<(), String>
+    where T: Reducer + DeserializeOwned + Debug + PartialEq,
+          T::Action: DeserializeOwned + Debug
+{
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut fixture : Value = match path.extension().is_some_and(|extension| extension == "json") {
+        true => serde_json::from_str(&text).map_err(|e| e.to_string())?,
+        false => serde_norway::from_str(&text).map_err(|e| e.to_string())?,
+    };
+
+    let initial : T = match fixture.get_mut("initial") {
+        Some(initial) => field(initial.take(), "initial")?,
+        None => T::default(),
+    };
+    let actions : Vec<T::Action> = match fixture.get_mut("actions") {
+        Some(actions) => field(actions.take(), "actions")?,
+        None => return Err(String::from("there are no `actions`")),
+    };
+    let error : Option<String> = fixture.get_mut("error").map(|error| field(error.take(), "error")).transpose()?;
+    let state : Option<T> = fixture.get_mut("state").map(|state| field(state.take(), "state")).transpose()?;
+
+    let mut scenario = given(initial).when(actions);
+    match (error, state) {
+        (None, None) => Err(String::from("there's no expected `state` or `error`")),
+        (Some(error), None) => scenario.expect_error(&error),
+        (None, Some(state)) => scenario.expect_state(&state),
+        (Some(error), Some(state)) => scenario.expect_error(&error).and_then(|_| scenario.expect_state(&state)),
+    }
+}
+
+fn field<D: DeserializeOwned>(value: Value, name: &str) -> Result<D, String> {
+    serde_json::from_value(value).map_err(|e| format!("`{}` can't be read: {}", name, e))
+}
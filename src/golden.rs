@@ -0,0 +1,116 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::testing::diff;
+use crate::Reducer;
+
+/// Set to anything but `0` to write snapshots instead of checking them.
+const UPDATE: &str = "REDUX_UPDATE_SNAPSHOTS";
+
+/// Golden-file tests for a reducer: runs a script of actions and compares the
+/// state they lead to with a snapshot file checked in next to the tests.
+///
+/// Snapshots are pretty-printed JSON with the fields of every object in a
+/// stable order, so they only change when the state does and review well.
+/// When the state changes on purpose, run the tests again with
+/// `REDUX_UPDATE_SNAPSHOTS=1` to rewrite the snapshots, and review the diff.
+/// A missing snapshot fails the test too, until it's written that way.
+///
+/// ```no_run
+/// # use redux::Reducer;
+/// # use redux::testing::Golden;
+/// # #[derive(Clone, Default, serde::Serialize)]
+/// # struct Counter { count: usize }
+/// # impl Reducer for Counter {
+/// #     type Action = usize;
+/// #     type Error = String;
+/// #     fn reduce(&mut self, n: usize) -> Result<Self, String> {
+/// #         self.count += n;
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+/// Golden::new(Counter::default()).check("tests/snapshots/counter.json", vec![1, 2, 3]);
+/// ```
+pub struct Golden<T: Reducer> {
+    initial: T,
+    intermediate: bool,
+    update: bool,
+}
+
+impl<T> Golden<T> where T: Reducer + Serialize, T::Action: Serialize {
+    /// Runs scripts from `initial`, updating snapshots if the environment
+    /// says to.
+    pub fn new(initial: T) -> Golden<T> {
+        Golden {
+            initial,
+            intermediate: false,
+            update: env::var(UPDATE).is_ok_and(|update| update != "0"),
+        }
+    }
+
+    /// Snapshots every action with the state after it and its error, if any,
+    /// not just the final state.
+    pub fn with_intermediate_states(mut self) -> Golden<T> {
+        self.intermediate = true;
+        self
+    }
+
+    /// Overrides `REDUX_UPDATE_SNAPSHOTS`.
+    pub fn updating(mut self, update: bool) -> Golden<T> {
+        self.update = update;
+        self
+    }
+
+    /// Runs `actions` and panics, with a diff, unless the snapshot at `path`
+    /// matches, or writes it when updating.
+    pub fn check<P: AsRef<Path>>(&self, path: P, actions: Vec<T::Action>) {
+        let path = path.as_ref();
+        let snapshot = self.snapshot(actions);
+
+        if self.update {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).unwrap_or_else(|e| panic!("Can't create {}: {}", dir.display(), e));
+            }
+            fs::write(path, snapshot).unwrap_or_else(|e| panic!("Can't write {}: {}", path.display(), e));
+            return;
+        }
+
+        let saved = match fs::read_to_string(path) {
+            Ok(saved) => saved.replace("\r\n", "\n"),
+            Err(e) => panic!("Can't read the snapshot {} ({}); run with {}=1 to write it", path.display(), e, UPDATE),
+        };
+        if saved != snapshot {
+            panic!("The state doesn't match the snapshot {} (- snapshot, + actual); run with {}=1 to update it:\n{}", path.display(), UPDATE, diff(&saved, &snapshot));
+        }
+    }
+
+    fn snapshot(&self, actions: Vec<T::Action>) -> String {
+        let mut state = self.initial.clone();
+        let mut steps = vec![];
+        for action in actions {
+            let recorded = if self.intermediate { Some(to_value(&action)) } else { None };
+            let error = state.reduce(action).err().map(|e| e.to_string());
+            if let Some(action) = recorded {
+                let mut step = json!({ "action": action, "state": to_value(&state) });
+                if let Some(error) = error {
+                    step["error"] = Value::String(error);
+                }
+                steps.push(step);
+            }
+        }
+
+        let snapshot = match self.intermediate {
+            true => json!({ "initial": to_value(&self.initial), "steps": steps }),
+            false => to_value(&state),
+        };
+        format!("{}\n", serde_json::to_string_pretty(&snapshot).unwrap())
+    }
+}
+
+fn to_value<S: Serialize>(value: &S) -> Value {
+    serde_json::to_value(value).unwrap_or_else(|e| panic!("Can't serialize for a snapshot: {}", e))
+}
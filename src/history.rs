@@ -0,0 +1,383 @@
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::Reducer;
+
+/// The actions a `History` reducer accepts: the wrapped reducer's actions,
+/// plus the time-travel ones.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HistoryAction<A> {
+    /// An action for the wrapped reducer, recorded at the end of the history.
+    Perform(A),
+    /// Show the state after this many recorded actions; `0` is the committed
+    /// base state. Jumping past the end does nothing.
+    JumpTo(usize),
+    /// Skip the recorded action at this index if it's applied, or apply it
+    /// again if it's skipped. Every later state is recomputed.
+    Toggle(usize),
+    /// Skip (`true`) or apply (`false`) the recorded action at this index.
+    SetSkipped(usize, bool),
+    /// Make the state being shown the new base and forget every recorded
+    /// action.
+    Commit,
+    /// Replace every recorded action with these, applied on top of the
+    /// committed base, skipping the ones at the `skipped` indices, and show
+    /// the state after `current` of them.
+    Import {
+        actions: Vec<A>,
+        skipped: Vec<usize>,
+        current: usize,
+    },
+}
+
+/// How much a `History` keeps around. Both methods have defaults:
+///
+/// ```
+/// # use redux::HistoryConfig;
+/// struct LastThousand;
+///
+/// impl HistoryConfig for LastThousand {
+///     fn max_actions() -> Option<usize> {
+///         Some(1000)
+///     }
+///
+///     fn checkpoint_every() -> usize {
+///         50
+///     }
+/// }
+/// ```
+pub trait HistoryConfig {
+    /// The most actions to record. Past that the oldest ones are committed
+    /// into the base state. `None` keeps everything.
+    fn max_actions() -> Option<usize> {
+        None
+    }
+
+    /// Keep a copy of the state after every this many actions; the states in
+    /// between are recomputed from the nearest copy when they're needed. The
+    /// state after the latest action is always kept.
+    fn checkpoint_every() -> usize {
+        1
+    }
+}
+
+/// The `HistoryConfig` used when none is given: the last 50 actions, which is
+/// what the Redux DevTools keep too, with the state after each one.
+pub struct RecentActions;
+
+impl HistoryConfig for RecentActions {
+    fn max_actions() -> Option<usize> {
+        Some(50)
+    }
+}
+
+/// Every action, with the state after each one. Nothing is ever committed on
+/// its own, so the history grows for as long as the store is used.
+pub struct RecordEverything;
+
+impl HistoryConfig for RecordEverything {}
+
+/// An action in a `History`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recorded<A> {
+    pub action: A,
+    /// Skipped actions stay in the history but aren't applied.
+    pub skipped: bool,
+    /// The error the action failed with the last time it was applied, if any.
+    /// A failed action leaves the state as it was.
+    pub error: Option<String>,
+}
+
+/// A higher-order reducer that records every action with the state it led to,
+/// so you can step back through what happened, skip actions to see what
+/// would have happened without them, and commit once you're done.
+///
+/// It works with a `Store` like any other `Reducer`. Dispatching `Perform`
+/// while looking at an earlier state records the action at the end but leaves
+/// the view where it is, the way the redux devtools do:
+///
+/// ```
+/// # use redux::{History, HistoryAction, Reducer, Store};
+/// #[derive(Clone, Default)]
+/// struct Counter {
+///     count: usize,
+/// }
+///
+/// impl Reducer for Counter {
+///     type Action = usize;
+///     type Error = String;
+///
+///     fn reduce(&mut self, n: Self::Action) -> Result<Self, Self::Error> {
+///         self.count += n;
+///         Ok(self.clone())
+///     }
+/// }
+///
+/// let store : Store<History<Counter>> = Store::new(vec![]);
+/// let _ = store.dispatch(HistoryAction::Perform(1));
+/// let _ = store.dispatch(HistoryAction::Perform(10));
+/// let _ = store.dispatch(HistoryAction::Toggle(0));
+/// assert_eq!(10, store.get_state().count);
+/// let _ = store.dispatch(HistoryAction::JumpTo(0));
+/// assert_eq!(0, store.get_state().count);
+/// ```
+///
+/// `C` picks the `HistoryConfig`, which sets the memory limits.
+pub struct History<R: Reducer, C: HistoryConfig = RecentActions> {
+    base: R,
+    entries: VecDeque<Recorded<R::Action>>,
+    // the state after each entry, where one is kept. They're shared, since
+    // the store clones the whole history with every dispatch.
+    checkpoints: VecDeque<Option<Arc<R>>>,
+    current: usize,
+    present: R,
+    // how many actions `trim` committed since the last `Commit` or `Import`
+    trimmed: usize,
+    config: PhantomData<fn() -> C>,
+}
+
+impl<R: Reducer, C: HistoryConfig> History<R, C> {
+    /// Start recording from `base` instead of the default state.
+    pub fn new(base: R) -> Self {
+        History {
+            present: base.clone(),
+            base,
+            entries: VecDeque::new(),
+            checkpoints: VecDeque::new(),
+            current: 0,
+            trimmed: 0,
+            config: PhantomData,
+        }
+    }
+
+    /// The state being shown.
+    pub fn present(&self) -> &R {
+        &self.present
+    }
+
+    /// The committed state the recorded actions start from.
+    pub fn base(&self) -> &R {
+        &self.base
+    }
+
+    /// The recorded actions, oldest first.
+    pub fn actions(&self) -> impl Iterator<Item = &Recorded<R::Action>> {
+        self.entries.iter()
+    }
+
+    /// How many actions are recorded.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// How many of the oldest actions were committed into the base to stay
+    /// within `HistoryConfig::max_actions`, since the last `Commit` or
+    /// `Import`.
+    pub fn trimmed(&self) -> usize {
+        self.trimmed
+    }
+
+    /// How many recorded actions the state being shown is after.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Whether the latest state is the one being shown.
+    pub fn is_at_latest(&self) -> bool {
+        self.current == self.entries.len()
+    }
+
+    /// The state after `index` recorded actions, or `None` past the end.
+    pub fn state_at(&self, index: usize) -> Option<R> {
+        if index > self.entries.len() {
+            return None;
+        }
+
+        Some(self.compute(index))
+    }
+
+    /// The state after every recorded action.
+    pub fn latest(&self) -> R {
+        self.compute(self.entries.len())
+    }
+
+    // whether the state after the entry at `index` is kept. It goes by the
+    // action's place since the last `Commit` or `Import`, which `trim`
+    // doesn't move.
+    fn keeps(&self, index: usize) -> bool {
+        (self.trimmed + index + 1).is_multiple_of(C::checkpoint_every().max(1))
+    }
+
+    // reduces a copy so a failed action leaves the state as it was
+    fn apply(state: &mut R, action: &R::Action) -> Result<(), R::Error> {
+        let mut next = state.clone();
+        next.reduce(action.clone())?;
+        *state = next;
+        Ok(())
+    }
+
+    fn compute(&self, index: usize) -> R {
+        let mut start = index;
+        while start > 0 && self.checkpoints[start - 1].is_none() {
+            start -= 1;
+        }
+        let mut state = match start {
+            0 => self.base.clone(),
+            _ => self.checkpoints[start - 1].as_deref().cloned().unwrap(),
+        };
+        for entry in self.entries.range(start..index) {
+            if !entry.skipped && entry.error.is_none() {
+                let _ = History::<R, C>::apply(&mut state, &entry.action);
+            }
+        }
+
+        state
+    }
+
+    fn recompute_from(&mut self, from: usize) {
+        let mut state = self.compute(from);
+        let last = self.entries.len() - 1;
+        for index in from..=last {
+            let entry = &mut self.entries[index];
+            entry.error = None;
+            if !entry.skipped {
+                if let Err(e) = History::<R, C>::apply(&mut state, &entry.action) {
+                    entry.error = Some(e.to_string());
+                }
+            }
+            self.checkpoints[index] = match self.keeps(index) || index == last {
+                true => Some(Arc::new(state.clone())),
+                false => None,
+            };
+        }
+        self.present = self.compute(self.current);
+    }
+
+    fn perform(&mut self, action: R::Action) -> Result<(), R::Error> {
+        let mut state = self.latest();
+        History::<R, C>::apply(&mut state, &action)?;
+
+        let previous = self.entries.len();
+        if previous > 0 && !self.keeps(previous - 1) {
+            self.checkpoints[previous - 1] = None;
+        }
+        let was_at_latest = self.is_at_latest();
+        self.entries.push_back(Recorded { action, skipped: false, error: None });
+        self.checkpoints.push_back(Some(Arc::new(state.clone())));
+        if was_at_latest {
+            self.current += 1;
+            self.present = state;
+        }
+        self.trim();
+
+        Ok(())
+    }
+
+    fn trim(&mut self) {
+        let max = match C::max_actions() {
+            Some(max) => max,
+            None => return,
+        };
+        while self.entries.len() > max {
+            self.base = self.compute(1);
+            self.entries.pop_front();
+            self.checkpoints.pop_front();
+            self.trimmed += 1;
+            if self.current == 0 {
+                self.present = self.base.clone();
+            } else {
+                self.current -= 1;
+            }
+        }
+    }
+
+    fn import(&mut self, actions: Vec<R::Action>, skipped: Vec<usize>, current: usize) {
+        self.entries = actions.into_iter().enumerate()
+            .map(|(index, action)| Recorded { action, skipped: skipped.contains(&index), error: None })
+            .collect();
+        self.checkpoints = self.entries.iter().map(|_| None).collect();
+        self.current = current.min(self.entries.len());
+        self.trimmed = 0;
+        if self.entries.is_empty() {
+            self.present = self.base.clone();
+        } else {
+            self.recompute_from(0);
+        }
+        self.trim();
+    }
+
+    fn set_skipped(&mut self, index: usize, skipped: bool) {
+        if index < self.entries.len() && self.entries[index].skipped != skipped {
+            self.entries[index].skipped = skipped;
+            self.recompute_from(index);
+        }
+    }
+}
+
+impl<R: Reducer, C: HistoryConfig> Deref for History<R, C> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        &self.present
+    }
+}
+
+impl<R: Reducer, C: HistoryConfig> Clone for History<R, C> {
+    fn clone(&self) -> Self {
+        History {
+            base: self.base.clone(),
+            entries: self.entries.clone(),
+            checkpoints: self.checkpoints.clone(),
+            current: self.current,
+            present: self.present.clone(),
+            trimmed: self.trimmed,
+            config: PhantomData,
+        }
+    }
+}
+
+impl<R: Reducer, C: HistoryConfig> Default for History<R, C> {
+    fn default() -> Self {
+        History::new(R::default())
+    }
+}
+
+impl<R: Reducer, C: HistoryConfig> Reducer for History<R, C> {
+    type Action = HistoryAction<R::Action>;
+    type Error = R::Error;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            HistoryAction::Perform(action) => self.perform(action)?,
+            HistoryAction::JumpTo(index) => {
+                if index <= self.entries.len() {
+                    self.current = index;
+                    self.present = self.compute(index);
+                }
+            },
+            HistoryAction::Toggle(index) => {
+                if let Some(entry) = self.entries.get(index) {
+                    let skipped = !entry.skipped;
+                    self.set_skipped(index, skipped);
+                }
+            },
+            HistoryAction::SetSkipped(index, skipped) => self.set_skipped(index, skipped),
+            HistoryAction::Commit => {
+                self.base = self.present.clone();
+                self.entries.clear();
+                self.checkpoints.clear();
+                self.current = 0;
+                self.trimmed = 0;
+            },
+            HistoryAction::Import { actions, skipped, current } => self.import(actions, skipped, current),
+        }
+
+        Ok(self.clone())
+    }
+}
@@ -1,6 +1,77 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
+use std::any::Any;
+use std::cell::RefCell;
 use std::default::Default;
 use std::fmt::Display;
+use std::marker::PhantomData;
+
+mod action_kind;
+#[cfg(feature = "async")]
+mod async_middleware;
+#[cfg(feature = "devtools")]
+mod devtools;
+#[cfg(feature = "async")]
+mod streams;
+mod effects;
+#[cfg(feature = "persistence")]
+mod event_log;
+#[cfg(feature = "fixtures")]
+mod fixtures;
+#[cfg(feature = "snapshot-testing")]
+mod golden;
+mod history;
+mod listener;
+#[cfg(feature = "logger")]
+mod logger;
+mod metrics;
+#[cfg(feature = "persistence")]
+mod migrations;
+#[cfg(feature = "persistence")]
+mod persist;
+#[cfg(feature = "property-testing")]
+mod properties;
+mod reversible;
+mod saga;
+#[cfg(feature = "persistence")]
+mod snapshots;
+pub mod testing;
+mod thunk;
+#[cfg(feature = "tracing")]
+mod trace;
+mod undo;
+
+pub use action_kind::{ActionKind, KindFilter};
+pub use effects::{EffectReducer, EffectRunner, Effects};
+pub use history::{History, HistoryAction, HistoryConfig, RecentActions, RecordEverything, Recorded};
+pub use listener::{Listener, ListenerApi, ListenerId, ListenerMiddleware};
+pub use metrics::{Dispatched, MetricsMiddleware};
+pub use reversible::{Reversible, UndoLog, Verified};
+pub use saga::{Cancelled, Pattern, Race, Saga, SagaMiddleware, SagaResult, SagaTask, SagaTester};
+pub use thunk::{Thunk, ThunkAction, ThunkMiddleware};
+pub use undo::{KeepEverything, UndoAction, UndoConfig, Undoable};
+#[cfg(feature = "async")]
+pub use async_middleware::{AsyncDispatchFunc, AsyncMiddleware};
+#[cfg(feature = "async")]
+pub use async_trait::async_trait;
+#[cfg(feature = "derive")]
+pub use redux_derive::ActionKind;
+#[cfg(feature = "devtools")]
+pub use devtools::{DevtoolsServer, DevtoolsSession};
+#[cfg(feature = "persistence")]
+pub use event_log::{ActionLog, LogError, LogOptions, PersistenceMiddleware, SyncPolicy};
+#[cfg(feature = "logger")]
+pub use logger::{LoggerMiddleware, StateLogging};
+#[cfg(feature = "persistence")]
+pub use migrations::{MigrationError, Migrations};
+#[cfg(feature = "persistence")]
+pub use persist::{FileStorage, MemoryStorage, PersistAction, PersistConfig, Persisted, Persistor, Slices, Storage};
+#[cfg(feature = "persistence")]
+pub use snapshots::{SnapshotMiddleware, SnapshotOptions};
+#[cfg(feature = "async")]
+pub use streams::StoreStream;
+#[cfg(feature = "async")]
+use streams::Broadcast;
 
 /// The `Reducer` trait is meant to be applied to the object that contains your
 /// applications state. Because each application will have their own custom state
@@ -56,12 +127,17 @@ pub trait Reducer: Clone + Default {
     /// Reduce a given state based upon an action. This won't be called externally
     /// because your application will never have a reference to the state object
     /// directly. Instead, it'll be called with you call `store.dispatch`.
-    fn reduce(&mut self, Self::Action) -> Result<Self, Self::Error>;
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error>;
 }
 
-fn build_next<T: 'static + Reducer>(next: DispatchFunc<T>, middleware: Box<Middleware<T>>) -> DispatchFunc<T> {
+fn build_next<T: 'static + Reducer>(next: DispatchFunc<T>, middleware: Box<dyn Middleware<T>>) -> DispatchFunc<T> {
     Box::new(move |store, action| {
-        middleware.dispatch(store, action, &next)
+        #[cfg(feature = "tracing")]
+        let span = trace::middleware(middleware.name(), store.kind(&action));
+        let dispatch = || middleware.dispatch(store, action, &next);
+        #[cfg(feature = "tracing")]
+        let dispatch = || trace::traced(span, dispatch);
+        dispatch()
     })
 }
 
@@ -77,12 +153,12 @@ fn build_next<T: 'static + Reducer>(next: DispatchFunc<T>, middleware: Box<Middl
 ///
 /// #[derive(Clone, Debug)]
 /// struct Todo {
-/// 	name: &'static str,
+///     name: &'static str,
 /// }
 /// 
 /// #[derive(Clone, Debug)]
 /// struct TodoState {
-/// 	todos: Vec<Todo>,
+///     todos: Vec<Todo>,
 /// }
 /// 
 /// impl TodoState {
@@ -92,14 +168,14 @@ fn build_next<T: 'static + Reducer>(next: DispatchFunc<T>, middleware: Box<Middl
 ///         }
 ///     }
 /// 
-/// 	fn push(&mut self, todo: Todo) {
-/// 		self.todos.push(todo);
-/// 	}
+///     fn push(&mut self, todo: Todo) {
+///         self.todos.push(todo);
+///     }
 /// }
 /// 
 /// #[derive(Clone)]
 /// enum TodoAction {
-/// 	Insert(&'static str),
+///     Insert(&'static str),
 /// }
 /// 
 /// impl Default for TodoState {
@@ -109,57 +185,113 @@ fn build_next<T: 'static + Reducer>(next: DispatchFunc<T>, middleware: Box<Middl
 /// }
 /// 
 /// impl Reducer for TodoState {
-/// 	type Action = TodoAction;
-/// 	type Error = String;
+///     type Action = TodoAction;
+///     type Error = String;
 /// 
-/// 	fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
-/// 		match action {
+///     fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+///         match action {
 ///             TodoAction::Insert(name) => {
 ///                 let todo = Todo { name: name, };
 ///                 self.push(todo);
 ///             },
-/// 		}
+///         }
 /// 
 ///         Ok(self.clone())
-/// 	}
+///     }
 /// }
 /// 
 /// fn main() {
-/// 	let store : Store<TodoState> = Store::new(vec![]);
-/// 	let action = TodoAction::Insert("Clean the bathroom");
-/// 	let _ = store.dispatch(action);
+///     let store : Store<TodoState> = Store::new(vec![]);
+///     let action = TodoAction::Insert("Clean the bathroom");
+///     let _ = store.dispatch(action);
 /// 
-/// 	println!("{:?}", store.get_state());
+///     println!("{:?}", store.get_state());
 /// }
 /// ```
+///
+/// ## Threads
+///
+/// A `Store` is `Send` and `Sync` when its state and actions are `Send`, so it
+/// can be shared between threads in an `Arc`. Middleware and subscriptions run
+/// on whichever thread dispatches, so they have to be `Send` and `Sync`.
+/// Dispatches from different threads take turns at the reducer; one from
+/// inside a reducer into its own store fails instead of waiting on itself.
+///
+/// A state that can't leave its thread keeps the store on that thread too:
+///
+/// ```compile_fail
+/// # use redux::{Reducer, Store};
+/// use std::rc::Rc;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// #[derive(Clone, Default)]
+/// struct Counter {
+///     count: Rc<usize>,
+/// }
+/// # impl Reducer for Counter {
+/// #     type Action = usize;
+/// #     type Error = String;
+/// #
+/// #     fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+/// #         self.count = Rc::new(*self.count + action);
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+///
+/// let store : Arc<Store<Counter>> = Arc::new(Store::new(vec![]));
+/// thread::spawn(move || store.dispatch(1));
+/// ```
 pub struct Store<T: Reducer> {
     internal_store: Arc<Mutex<InternalStore<T>>>,
     subscriptions: Arc<RwLock<Vec<Arc<Subscription<T>>>>>,
+    next_subscription_id: AtomicUsize,
     dispatch_chain: DispatchFunc<T>,
+    effect_runner: Option<EffectRunnerFunc<T>>,
+    #[cfg(feature = "tracing")]
+    action_kind: trace::KindFunc<T::Action>,
+    // `None` without async middlewares, `dispatch_async` then goes straight
+    // to the synchronous chain
+    #[cfg(feature = "async")]
+    async_dispatch_chain: Option<AsyncDispatchFunc<T>>,
+    #[cfg(feature = "async")]
+    changes: Broadcast<T>,
+    #[cfg(feature = "async")]
+    actions: Broadcast<T::Action>,
 }
 
-// Would love to get rid of these someday
-unsafe impl<T: Reducer> Send for Store<T> {}
-unsafe impl<T: Reducer> Sync for Store<T> {}
-
 impl<T: 'static + Reducer> Store<T> {
     /// Initialize a new `Store`. 
-    pub fn new(middlewares: Vec<Box<Middleware<T>>>) -> Store<T> {
-        let initial_data = T::default();
+    pub fn new(middlewares: Vec<Box<dyn Middleware<T>>>) -> Store<T> {
+        Store::with_state(T::default(), middlewares)
+    }
+
+    /// Initialize a new `Store` starting from `initial_data` instead of the
+    /// default state, for example one loaded from disk.
+    pub fn with_state(initial_data: T, middlewares: Vec<Box<dyn Middleware<T>>>) -> Store<T> {
         let internal = Arc::new(Mutex::new(InternalStore {
             data: initial_data,
-            is_dispatching: false,
         }));
-        let is = internal.clone();
-        let mut next : DispatchFunc<T> = Box::new(move |_, action| {
-            match is.try_lock() {
-                Ok(mut guard) => {
-                    guard.dispatch(action.clone())
+        let mut next : DispatchFunc<T> = Box::new(|store, action| {
+            #[cfg(feature = "tracing")]
+            let span = trace::reduce(store.kind(&action));
+            // a reducer that dispatches into its own store would wait on the
+            // lock it holds; any other thread's reduce is safe to wait for
+            let reduce = || match Reducing::enter(&store.internal_store) {
+                Some(_reducing) => match store.internal_store.lock() {
+                    Ok(mut guard) => {
+                        Before::record(&store.internal_store, &guard.data);
+                        guard.dispatch(action.clone())
+                    },
+                    Err(_) => {
+                        Err(String::from("Can't dispatch, a previous reduce panicked. The internal data is poisoned."))
+                    }
                 },
-                Err(_) => {
-                    Err(String::from("Can't dispatch during a reduce. The internal data is locked."))
-                }
-            }
+                None => Err(String::from("Can't dispatch during a reduce.")),
+            };
+            #[cfg(feature = "tracing")]
+            let reduce = || trace::traced(span, reduce);
+            reduce()
         });
         for middleware in middlewares {
             next = build_next(next, middleware);
@@ -168,17 +300,120 @@ impl<T: 'static + Reducer> Store<T> {
         Store {
             internal_store: internal,
             subscriptions: Arc::new(RwLock::new(Vec::new())),
+            next_subscription_id: AtomicUsize::new(0),
             dispatch_chain: next,
+            effect_runner: None,
+            #[cfg(feature = "tracing")]
+            action_kind: trace::type_kind,
+            #[cfg(feature = "async")]
+            async_dispatch_chain: None,
+            #[cfg(feature = "async")]
+            changes: Broadcast::new(),
+            #[cfg(feature = "async")]
+            actions: Broadcast::new(),
         }
     }
 
+    /// Initialize a new `Store` that also runs `async_middlewares` for every
+    /// `dispatch_async`. The async middlewares wrap the synchronous ones; see
+    /// `AsyncMiddleware` for how overlapping dispatches are ordered.
+    #[cfg(feature = "async")]
+    pub fn new_async(middlewares: Vec<Box<dyn Middleware<T>>>, async_middlewares: Vec<Box<dyn AsyncMiddleware<T>>>) -> Store<T>
+        where T: Send,
+              T::Action: Send
+    {
+        let mut store = Store::new(middlewares);
+        let mut next = async_middleware::sync_chain();
+        for middleware in async_middlewares {
+            next = async_middleware::build_next_async(next, middleware);
+        }
+        store.async_dispatch_chain = Some(next);
+
+        store
+    }
+
     /// Dispatch an event to the stores, returning an `Result`. Only one dispatch
     /// can be happening at a time.
     pub fn dispatch(&self, action: T::Action) -> Result<T::Action, String> {
-        let ref dispatch = self.dispatch_chain;
-        match dispatch(&self, action.clone()) {
-            Err(e) => return Err(format!("Error during dispatch: {}", e)),
-            _ => {}
+        #[cfg(feature = "tracing")]
+        let span = trace::dispatch(self.kind(&action));
+        let dispatch = move || {
+            let result = self.run_chain(action.clone());
+            self.finish_dispatch(action, result)
+        };
+        #[cfg(feature = "tracing")]
+        let dispatch = || trace::traced(span, dispatch);
+        dispatch()
+    }
+
+    /// Names actions in the store's `tracing` spans with `kind`, say the name
+    /// of their variant. Until then they're named after their type.
+    #[cfg(feature = "tracing")]
+    pub fn action_kinds(mut self, kind: fn(&T::Action) -> &'static str) -> Store<T> {
+        self.action_kind = kind;
+        self
+    }
+
+    /// Names actions in the store's `tracing` spans with their
+    /// `ActionKind::kind`.
+    #[cfg(feature = "tracing")]
+    pub fn named_by_kind(self) -> Store<T> where T::Action: ActionKind {
+        self.action_kinds(<T::Action as ActionKind>::kind)
+    }
+
+    #[cfg(feature = "tracing")]
+    fn kind(&self, action: &T::Action) -> &'static str {
+        (self.action_kind)(action)
+    }
+
+    /// Async flavour of `dispatch`, for use from inside a task. The action goes
+    /// through the async middlewares first, and the returned future resolves
+    /// once it has been reduced and every subscription has been called.
+    ///
+    /// Async middlewares are skipped by the synchronous `dispatch`.
+    #[cfg(feature = "async")]
+    pub async fn dispatch_async(&self, action: T::Action) -> Result<T::Action, String> {
+        match &self.async_dispatch_chain {
+            // the innermost link of the chain notifies as soon as the action
+            // is reduced, before any async middleware gets to await again
+            Some(dispatch) => match dispatch(self, action.clone()).await {
+                Ok(_) => Ok(action),
+                Err(e) => Err(format!("Error during dispatch: {}", e)),
+            },
+            None => {
+                let result = self.run_chain(action.clone());
+                self.finish_dispatch(action, result)
+            },
+        }
+    }
+
+    // sends an action down the middleware chain to the reducer
+    fn run_chain(&self, action: T::Action) -> Result<T, String> {
+        let _dispatch = Before::<T>::dispatch();
+        (self.dispatch_chain)(self, action)
+    }
+
+    fn finish_dispatch(&self, action: T::Action, result: Result<T, String>) -> Result<T::Action, String> {
+        match result {
+            Ok(state) => {
+                self.notify(&action, &state);
+                Ok(action)
+            },
+            Err(e) => Err(format!("Error during dispatch: {}", e)),
+        }
+    }
+
+    // publishes a dispatch that went through, runs its effects and calls the
+    // subscriptions
+    fn notify(&self, action: &T::Action, state: &T) {
+        // streams get the exact state this dispatch produced, before any
+        // subscription has a chance to dispatch again
+        self.publish(action, state);
+
+        // effects run once the reduce has committed and before subscriptions,
+        // so a subscription that dispatches can't pick them up first
+        if let Some(ref run_effects) = self.effect_runner {
+            run_effects(self);
         }
 
         // snapshot the active subscriptions here before calling them. This both
@@ -196,11 +431,58 @@ impl<T: 'static + Reducer> Store<T> {
         // actually run the subscriptions here; after this method is over the subs_to_use
         // vec gets dropped, and all the Arcs of subscriptions get decremented
         for subscription in subs_to_use {
+            #[cfg(feature = "tracing")]
+            let _span = trace::subscription(subscription.id, self.kind(action)).entered();
             let cb = &subscription.callback;
-            cb(&self, &subscription);
+            cb(self, &subscription);
         }
+    }
+
+    /// Returns a `Stream` that yields a `Clone` of the state after every
+    /// successful dispatch made after this call. Nothing is buffered for
+    /// dispatches made before the stream was created.
+    ///
+    /// ```
+    /// # use redux::{Reducer, Store};
+    /// # use futures::executor::block_on;
+    /// # use futures::stream::StreamExt;
+    /// #
+    /// # #[derive(Clone, Default)]
+    /// # struct Counter { count: usize }
+    /// # impl Reducer for Counter {
+    /// #     type Action = usize;
+    /// #     type Error = String;
+    /// #
+    /// #     fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+    /// #         self.count += action;
+    /// #         Ok(self.clone())
+    /// #     }
+    /// # }
+    /// #
+    /// let store : Store<Counter> = Store::new(vec![]);
+    /// let mut changes = store.changes();
+    ///
+    /// block_on(async {
+    ///     store.dispatch_async(2).await.unwrap();
+    ///     assert_eq!(2, changes.next().await.unwrap().count);
+    /// });
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn changes(&self) -> StoreStream<T> {
+        self.changes.listen()
+    }
 
-        Ok(action)
+    /// Returns a `Stream` of every action successfully dispatched after this
+    /// call, in the order their dispatches finished.
+    ///
+    /// These are the actions as they were dispatched, not as they reached the
+    /// reducer: one a middleware answered without passing it on, say a thunk
+    /// or an action a `KindFilter` dropped, is in the stream even though it
+    /// was never reduced. Actions dispatched from inside another dispatch
+    /// finish first, so they come before it.
+    #[cfg(feature = "async")]
+    pub fn actions(&self) -> StoreStream<T::Action> {
+        self.actions.listen()
     }
 
     /// Returns a `Clone` of the store's state. If called during a dispatch, this
@@ -209,6 +491,12 @@ impl<T: 'static + Reducer> Store<T> {
         self.internal_store.lock().unwrap().data.clone()
     }
 
+    // for a middleware that needs the state its `next` reduces from, which
+    // `get_state` can't give it: another dispatch may land in between
+    pub(crate) fn capture_before(&self) -> Before<T> {
+        Before::open(&self.internal_store)
+    }
+
     /// Create a new subscription to this store. Subscriptions are called for every
     /// dispatch made. 
     /// 
@@ -252,12 +540,22 @@ impl<T: 'static + Reducer> Store<T> {
     /// the caller of the method and the internal list of subscriptions need
     /// a reference to it
     pub fn subscribe(&self, callback: SubscriptionFunc<T>) -> Arc<Subscription<T>> {
-        let subscription = Arc::new(Subscription::new(callback));
+        let id = self.next_subscription_id.fetch_add(1, Ordering::SeqCst);
+        let subscription = Arc::new(Subscription::new(id, callback));
         let s = subscription.clone();
         self.subscriptions.write().unwrap().push(s);
-        return subscription;
+        subscription
+    }
+
+    #[cfg(feature = "async")]
+    fn publish(&self, action: &T::Action, state: &T) {
+        self.actions.send(action);
+        self.changes.send(state);
     }
 
+    #[cfg(not(feature = "async"))]
+    fn publish(&self, _: &T::Action, _: &T) {}
+
     fn get_subscriptions(&self) -> (Vec<usize>, Vec<Arc<Subscription<T>>>) {
         let mut i = 0;
         let mut subs_to_remove = vec![];
@@ -278,14 +576,11 @@ impl<T: 'static + Reducer> Store<T> {
     }
 
     fn try_to_remove_subscriptions(&self, subs_to_remove: Vec<usize>) {
-        if subs_to_remove.len() > 0 {
-            match self.subscriptions.try_write() {
-                Ok(mut subscriptions) => {
-                    for sub_index in subs_to_remove {
-                        subscriptions.remove(sub_index);
-                    }
-                },
-                _ => {}
+        if !subs_to_remove.is_empty() {
+            if let Ok(mut subscriptions) = self.subscriptions.try_write() {
+                for sub_index in subs_to_remove {
+                    subscriptions.remove(sub_index);
+                }
             }
         }
     }
@@ -293,43 +588,138 @@ impl<T: 'static + Reducer> Store<T> {
 
 struct InternalStore<T: Reducer> {
     data: T,
-    is_dispatching: bool,
 }
 
 impl<T: Reducer> InternalStore<T> {
     fn dispatch(&mut self, action: T::Action) -> Result<T, String> {
-        if self.is_dispatching {
-            return Err(String::from("Can't dispatch during a reduce."));
+        match self.data.reduce(action) {
+            Ok(_) => Ok(self.data.clone()),
+            Err(e) => Err(format!("{}", e)),
         }
+    }
+}
 
-        self.is_dispatching = true;
-        match self.data.reduce(action.clone()) {
-            Ok(_) => {}
-            Err(e) => {
-                return Err(format!("{}", e));
+thread_local! {
+    // the stores this thread is reducing, by the address of their state
+    static REDUCING: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+// marks a store as being reduced on this thread until it's dropped, panics
+// included
+struct Reducing(usize);
+
+impl Reducing {
+    // `None` if this thread is already reducing the store
+    fn enter<S>(internal: &Arc<S>) -> Option<Reducing> {
+        let address = Arc::as_ptr(internal) as *const () as usize;
+        REDUCING.with(|reducing| {
+            let mut reducing = reducing.borrow_mut();
+            if reducing.contains(&address) {
+                return None;
             }
-        }
-        self.is_dispatching = false;
+            reducing.push(address);
+            Some(Reducing(address))
+        })
+    }
+}
+
+impl Drop for Reducing {
+    fn drop(&mut self) {
+        REDUCING.with(|reducing| reducing.borrow_mut().retain(|address| *address != self.0));
+    }
+}
+
+enum Frame {
+    // a dispatch starting down the chain; a reduce only fills the captures
+    // opened since the innermost one, which are its own
+    Dispatch,
+    // a store's address and, once its reduce has run, the state it reduced
+    // from
+    Capture(usize, Option<Box<dyn Any>>),
+}
+
+thread_local! {
+    // the dispatches running on this thread and the states captured for the
+    // middlewares that asked with `Store::capture_before`, innermost last
+    static BEFORE: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+}
+
+// asks the reduce of the dispatch a middleware is about to pass on for the
+// state it reduces from. Dispatches nested inside it open their own, and it's
+// closed when it's dropped, panics included.
+pub(crate) struct Before<T> {
+    index: usize,
+    state: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static + Clone> Before<T> {
+    fn push(frame: Frame) -> Before<T> {
+        BEFORE.with(|before| {
+            let mut before = before.borrow_mut();
+            before.push(frame);
+            Before { index: before.len() - 1, state: PhantomData }
+        })
+    }
+
+    // marks the start of a dispatch until it's dropped
+    fn dispatch() -> Before<T> {
+        Before::push(Frame::Dispatch)
+    }
+
+    fn open<S>(internal: &Arc<S>) -> Before<T> {
+        Before::push(Frame::Capture(Arc::as_ptr(internal) as *const () as usize, None))
+    }
 
-        Ok(self.data.clone())
+    // called with the store locked, so nothing can change the state between
+    // this and the reduce
+    fn record<S>(internal: &Arc<S>, state: &T) {
+        let address = Arc::as_ptr(internal) as *const () as usize;
+        BEFORE.with(|before| {
+            for frame in before.borrow_mut().iter_mut().rev() {
+                match frame {
+                    Frame::Dispatch => break,
+                    Frame::Capture(open, captured @ None) if *open == address => {
+                        *captured = Some(Box::new(state.clone()));
+                    },
+                    Frame::Capture(..) => {},
+                }
+            }
+        });
+    }
+
+    /// The state the dispatch was reduced from, or `None` if nothing reached
+    /// the reducer.
+    pub(crate) fn take(self) -> Option<T> {
+        let captured = BEFORE.with(|before| match before.borrow_mut()[self.index] {
+            Frame::Capture(_, ref mut captured) => captured.take(),
+            Frame::Dispatch => None,
+        });
+        captured.and_then(|state| state.downcast().ok()).map(|state| *state)
     }
 }
 
-type SubscriptionFunc<T: Reducer> = Box<Fn(&Store<T>, &Subscription<T>)>;
+impl<T> Drop for Before<T> {
+    fn drop(&mut self) {
+        BEFORE.with(|before| before.borrow_mut().truncate(self.index));
+    }
+}
+
+type EffectRunnerFunc<T> = Box<dyn Fn(&Store<T>) + Send + Sync>;
+
+type SubscriptionFunc<T> = Box<dyn Fn(&Store<T>, &Subscription<T>) + Send + Sync>;
 
 /// Represents a subscription to a `Store` which can be cancelled.
 pub struct Subscription<T: Reducer> {
+    id: usize,
     callback: SubscriptionFunc<T>,
     active: Mutex<bool>,
 }
 
-unsafe impl<T: Reducer> Send for Subscription<T> {}
-unsafe impl<T: Reducer> Sync for Subscription<T> {}
-
 impl<T: Reducer> Subscription<T> {
-    fn new(callback: SubscriptionFunc<T>) -> Subscription<T> {
+    fn new(id: usize, callback: SubscriptionFunc<T>) -> Subscription<T> {
         Subscription {
-            callback: callback,
+            id,
+            callback,
             active: Mutex::new(true),
         }
     }
@@ -344,13 +734,19 @@ impl<T: Reducer> Subscription<T> {
         *active = false;
     }
 
+    /// Identifies the subscription among the others of its store, in the
+    /// order they were made.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
     /// Returns whether or not a subscription has been cancelled.
     pub fn is_active(&self) -> bool {
         *self.active.lock().unwrap()
     }
 }
 
-pub type DispatchFunc<T: Reducer> = Box<Fn(&Store<T>, T::Action) -> Result<T, String>>;
+pub type DispatchFunc<T> = Box<dyn Fn(&Store<T>, <T as Reducer>::Action) -> Result<T, String> + Send + Sync>;
 
 /// A decent approximation of a redux-js middleware wrapper. This lets you have
 /// wrap calls to dispatch, performing actions right before and right after a
@@ -393,8 +789,38 @@ pub type DispatchFunc<T: Reducer> = Box<Fn(&Store<T>, T::Action) -> Result<T, St
 /// let logger = Box::new(Logger{});
 /// let store : Store<Foo> = Store::new(vec![logger]);
 /// ```
-pub trait Middleware<T: Reducer> {
+///
+/// A middleware is called from every thread that dispatches to its store, so
+/// it has to be `Send` and `Sync`.
+pub trait Middleware<T: Reducer>: Send + Sync {
     fn dispatch(&self, store: &Store<T>, action: T::Action, next: &DispatchFunc<T>) -> Result<T, String>;
+
+    /// What the middleware is called in `tracing` spans, its type by default.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// The slice of a `Store` that middleware-driven code gets to use: dispatching
+/// more actions and reading the current state. Thunks are handed one of these
+/// instead of the `Store` itself.
+pub trait MiddlewareApi<T: Reducer> {
+    /// Dispatch an action through the whole middleware chain, like
+    /// `Store::dispatch`.
+    fn dispatch(&self, action: T::Action) -> Result<T::Action, String>;
+
+    /// Returns a `Clone` of the current state, like `Store::get_state`.
+    fn get_state(&self) -> T;
+}
+
+impl<T: 'static + Reducer> MiddlewareApi<T> for Store<T> {
+    fn dispatch(&self, action: T::Action) -> Result<T::Action, String> {
+        Store::dispatch(self, action)
+    }
+
+    fn get_state(&self) -> T {
+        Store::get_state(self)
+    }
 }
 
 #[cfg(test)]
@@ -403,7 +829,7 @@ impl Reducer for usize {
     type Error = String;
 
     fn reduce(&mut self, _: Self::Action) -> Result<Self, Self::Error> {
-        Ok(self.clone())
+        Ok(*self)
     }
 }
 
@@ -452,7 +878,7 @@ fn try_remove_subscriptions_no_lock() {
 
     let (remove, _) = store.get_subscriptions();
     {
-        let subscriptions = store.subscriptions.write().unwrap();
+        let _subscriptions = store.subscriptions.write().unwrap();
         store.try_to_remove_subscriptions(remove);
     }
     assert_eq!(1, store.subscriptions.read().unwrap().len());
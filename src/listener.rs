@@ -0,0 +1,386 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Cancelled, DispatchFunc, Middleware, MiddlewareApi, Reducer, Store};
+
+type Handler<T> = Arc<dyn Fn(&<T as Reducer>::Action, &ListenerApi<T>) + Send + Sync>;
+
+type Predicate<I> = Arc<dyn Fn(&I) -> bool + Send + Sync>;
+
+enum Trigger<T: Reducer> {
+    Action(Predicate<T::Action>),
+    State(Predicate<T>),
+}
+
+impl<T: Reducer> Clone for Trigger<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Trigger::Action(predicate) => Trigger::Action(predicate.clone()),
+            Trigger::State(predicate) => Trigger::State(predicate.clone()),
+        }
+    }
+}
+
+/// Something to do when a matching action is dispatched, built with
+/// `Listener::on_action` or `Listener::on_state` and registered with
+/// `ListenerMiddleware::add`.
+pub struct Listener<T: Reducer> {
+    trigger: Trigger<T>,
+    handler: Handler<T>,
+}
+
+impl<T: Reducer> Listener<T> {
+    /// Runs `handler` for every action `predicate` returns true for.
+    pub fn on_action<P, H>(predicate: P, handler: H) -> Listener<T>
+        where P: 'static + Fn(&T::Action) -> bool + Send + Sync,
+              H: 'static + Fn(&T::Action, &ListenerApi<T>) + Send + Sync
+    {
+        Listener {
+            trigger: Trigger::Action(Arc::new(predicate)),
+            handler: Arc::new(handler),
+        }
+    }
+
+    /// Runs `handler` whenever an action flips `predicate` from false (on the
+    /// state before the action) to true (on the state after it).
+    pub fn on_state<P, H>(predicate: P, handler: H) -> Listener<T>
+        where P: 'static + Fn(&T) -> bool + Send + Sync,
+              H: 'static + Fn(&T::Action, &ListenerApi<T>) + Send + Sync
+    {
+        Listener {
+            trigger: Trigger::State(Arc::new(predicate)),
+            handler: Arc::new(handler),
+        }
+    }
+}
+
+/// Identifies a registered listener so it can be removed again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ListenerId(usize);
+
+// the cancellation flags of a listener's runs that haven't finished yet
+type Runs = Arc<Mutex<Vec<Arc<AtomicBool>>>>;
+
+struct Registered<T: Reducer> {
+    id: ListenerId,
+    listener: Listener<T>,
+    runs: Runs,
+}
+
+struct Shared<T: Reducer> {
+    listeners: Mutex<Vec<Registered<T>>>,
+    next_id: AtomicUsize,
+    store: Mutex<Weak<Store<T>>>,
+    // bumped after every dispatch so `condition` knows to look again
+    changes: Mutex<u64>,
+    changed: Condvar,
+    active: Mutex<usize>,
+    finished: Condvar,
+}
+
+impl<T: Reducer> Shared<T> {
+    fn wake_up(&self) {
+        let _changes = self.changes.lock().unwrap();
+        self.changed.notify_all();
+    }
+}
+
+/// Redux Toolkit's `createListenerMiddleware`: a registry of listeners, each
+/// a predicate on actions or state plus a handler to run when it matches.
+///
+/// Handlers run on their own thread once the action has been reduced, so they
+/// can block in `ListenerApi::condition` or `ListenerApi::delay` without
+/// holding up the dispatch. They talk to the store this middleware was
+/// `attach`ed to; until then matching actions are ignored.
+///
+/// ## Example
+///
+/// ```
+/// # use redux::{Listener, ListenerMiddleware, MiddlewareApi, Reducer, Store};
+/// # use std::sync::Arc;
+/// #
+/// # #[derive(Clone, Default)]
+/// # struct Cart { items: usize, checked_out: bool }
+/// # impl Reducer for Cart {
+/// #     type Action = CartAction;
+/// #     type Error = String;
+/// #
+/// #     fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+/// #         match action {
+/// #             CartAction::Add => self.items += 1,
+/// #             CartAction::CheckOut => self.checked_out = true,
+/// #         }
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+/// #[derive(Clone, PartialEq)]
+/// enum CartAction {
+///     Add,
+///     CheckOut,
+/// }
+///
+/// let listeners = ListenerMiddleware::new();
+/// let store : Arc<Store<Cart>> = Arc::new(Store::new(vec![Box::new(listeners.clone())]));
+/// listeners.attach(&store);
+///
+/// // check out as soon as there are three items in the cart
+/// listeners.add(Listener::on_state(|cart: &Cart| cart.items >= 3, |_, api| {
+///     let _ = api.dispatch(CartAction::CheckOut);
+/// }));
+///
+/// for _ in 0..3 {
+///     let _ = store.dispatch(CartAction::Add);
+/// }
+/// listeners.wait_for_handlers();
+/// assert!(store.get_state().checked_out);
+/// ```
+pub struct ListenerMiddleware<T: Reducer> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Reducer> Clone for ListenerMiddleware<T> {
+    fn clone(&self) -> Self {
+        ListenerMiddleware {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T: Reducer> Default for ListenerMiddleware<T> {
+    fn default() -> Self {
+        ListenerMiddleware::new()
+    }
+}
+
+impl<T: Reducer> ListenerMiddleware<T> {
+    pub fn new() -> ListenerMiddleware<T> {
+        ListenerMiddleware {
+            shared: Arc::new(Shared {
+                listeners: Mutex::new(vec![]),
+                next_id: AtomicUsize::new(0),
+                store: Mutex::new(Weak::new()),
+                changes: Mutex::new(0),
+                changed: Condvar::new(),
+                active: Mutex::new(0),
+                finished: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Points the handlers at `store`, which should be the store this
+    /// middleware was given to. Only a weak reference is kept.
+    pub fn attach(&self, store: &Arc<Store<T>>) {
+        *self.shared.store.lock().unwrap() = Arc::downgrade(store);
+    }
+
+    /// Registers a listener. It sees every action dispatched from now on,
+    /// including ones dispatched by handlers.
+    pub fn add(&self, listener: Listener<T>) -> ListenerId {
+        let id = ListenerId(self.shared.next_id.fetch_add(1, Ordering::SeqCst));
+        self.shared.listeners.lock().unwrap().push(Registered {
+            id,
+            listener,
+            runs: Arc::new(Mutex::new(vec![])),
+        });
+
+        id
+    }
+
+    /// Unregisters a listener, returning whether or not it was registered.
+    /// Handlers that are already running carry on unless `cancel_active` is
+    /// set.
+    pub fn remove(&self, id: ListenerId, cancel_active: bool) -> bool {
+        let removed = {
+            let mut listeners = self.shared.listeners.lock().unwrap();
+            let index = listeners.iter().position(|registered| registered.id == id);
+            index.map(|index| listeners.remove(index))
+        };
+        match removed {
+            Some(registered) => {
+                if cancel_active {
+                    for run in registered.runs.lock().unwrap().iter() {
+                        run.store(true, Ordering::SeqCst);
+                    }
+                    self.shared.wake_up();
+                }
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Blocks until no handler is running, which is mostly useful in tests.
+    pub fn wait_for_handlers(&self) {
+        let mut active = self.shared.active.lock().unwrap();
+        while *active > 0 {
+            active = self.shared.finished.wait(active).unwrap();
+        }
+    }
+}
+
+impl<T: 'static + Reducer + Send> ListenerMiddleware<T> where T::Action: Send {
+    fn spawn(&self, store: &Arc<Store<T>>, action: &T::Action, handler: Handler<T>, runs: Runs) {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        runs.lock().unwrap().push(cancelled.clone());
+        *self.shared.active.lock().unwrap() += 1;
+
+        let api = ListenerApi {
+            store: store.clone(),
+            shared: self.shared.clone(),
+            cancelled,
+            runs,
+        };
+        let action = action.clone();
+        thread::spawn(move || {
+            handler(&action, &api);
+        });
+    }
+}
+
+impl<T: 'static + Reducer + Send> Middleware<T> for ListenerMiddleware<T> where T::Action: Send {
+    fn dispatch(&self, store: &Store<T>, action: T::Action, next: &DispatchFunc<T>) -> Result<T, String> {
+        let watches_state = self.shared.listeners.lock().unwrap().iter().any(|registered| {
+            matches!(registered.listener.trigger, Trigger::State(_))
+        });
+        // taken by the reduce itself, since another dispatch can get in
+        // between a `get_state` here and the reduce
+        let capture = if watches_state { Some(store.capture_before()) } else { None };
+
+        let after = next(store, action.clone())?;
+        // nothing reduced means nothing changed
+        let before = capture.map(|capture| capture.take().unwrap_or_else(|| after.clone()));
+        {
+            let mut changes = self.shared.changes.lock().unwrap();
+            *changes += 1;
+            self.shared.changed.notify_all();
+        }
+
+        let store = match self.shared.store.lock().unwrap().upgrade() {
+            Some(store) => store,
+            None => return Ok(after),
+        };
+        // copied out so predicates can add and remove listeners
+        let listeners : Vec<_> = self.shared.listeners.lock().unwrap().iter()
+            .map(|registered| (registered.listener.trigger.clone(), registered.listener.handler.clone(), registered.runs.clone()))
+            .collect();
+        for (trigger, handler, runs) in listeners {
+            let matched = match trigger {
+                Trigger::Action(ref predicate) => predicate(&action),
+                Trigger::State(ref predicate) => {
+                    let was = before.as_ref().is_some_and(|before| predicate(before));
+                    !was && predicate(&after)
+                },
+            };
+            if matched {
+                self.spawn(&store, &action, handler, runs);
+            }
+        }
+
+        Ok(after)
+    }
+}
+
+/// What a listener's handler gets to work with: the store's `MiddlewareApi`
+/// plus ways to wait and to cancel.
+pub struct ListenerApi<T: Reducer> {
+    store: Arc<Store<T>>,
+    shared: Arc<Shared<T>>,
+    cancelled: Arc<AtomicBool>,
+    runs: Runs,
+}
+
+impl<T: 'static + Reducer> ListenerApi<T> {
+    /// Waits until `predicate` holds for the store's state, checking after
+    /// every dispatch. Returns `Ok(false)` if `timeout` passes first.
+    pub fn condition<P>(&self, predicate: P, timeout: Option<Duration>) -> Result<bool, Cancelled>
+        where P: Fn(&T) -> bool
+    {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let seen = *self.shared.changes.lock().unwrap();
+            if self.is_cancelled() {
+                return Err(Cancelled);
+            }
+            if predicate(&self.store.get_state()) {
+                return Ok(true);
+            }
+
+            let mut changes = self.shared.changes.lock().unwrap();
+            while *changes == seen {
+                if self.is_cancelled() {
+                    return Err(Cancelled);
+                }
+                changes = match deadline {
+                    Some(deadline) => {
+                        let now = Instant::now();
+                        if now >= deadline {
+                            return Ok(false);
+                        }
+                        self.shared.changed.wait_timeout(changes, deadline - now).unwrap().0
+                    },
+                    None => self.shared.changed.wait(changes).unwrap(),
+                };
+            }
+        }
+    }
+
+    /// Sleeps for `duration`, returning early with `Err(Cancelled)` if this
+    /// run gets cancelled.
+    pub fn delay(&self, duration: Duration) -> Result<(), Cancelled> {
+        let deadline = Instant::now() + duration;
+        let mut changes = self.shared.changes.lock().unwrap();
+        loop {
+            if self.is_cancelled() {
+                return Err(Cancelled);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(());
+            }
+            changes = self.shared.changed.wait_timeout(changes, deadline - now).unwrap().0;
+        }
+    }
+
+    /// Cancels the earlier runs of this listener that are still going, which
+    /// is how to debounce or keep only the latest run. Runs started after this
+    /// one are left alone, whichever thread gets here first.
+    pub fn cancel_active_listeners(&self) {
+        // runs are kept in the order they were started
+        for run in self.runs.lock().unwrap().iter() {
+            if Arc::ptr_eq(run, &self.cancelled) {
+                break;
+            }
+            run.store(true, Ordering::SeqCst);
+        }
+        self.shared.wake_up();
+    }
+
+    /// Returns whether or not this run has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl<T: 'static + Reducer> MiddlewareApi<T> for ListenerApi<T> {
+    fn dispatch(&self, action: T::Action) -> Result<T::Action, String> {
+        self.store.dispatch(action)
+    }
+
+    fn get_state(&self) -> T {
+        self.store.get_state()
+    }
+}
+
+impl<T: Reducer> Drop for ListenerApi<T> {
+    // runs when the handler returns or panics
+    fn drop(&mut self) {
+        self.runs.lock().unwrap().retain(|run| !Arc::ptr_eq(run, &self.cancelled));
+        let mut active = self.shared.active.lock().unwrap();
+        *active -= 1;
+        if *active == 0 {
+            self.shared.finished.notify_all();
+        }
+    }
+}
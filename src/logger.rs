@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+use log::Level;
+
+use crate::testing::diff;
+use crate::{ActionKind, DispatchFunc, KindFilter, Middleware, Reducer, Store};
+
+type ActionFilter<A> = Box<dyn Fn(&A) -> bool + Send + Sync>;
+type Redaction<T> = Box<dyn Fn(&T) -> T + Send + Sync>;
+
+/// How much of the state a `LoggerMiddleware` logs for each dispatch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StateLogging {
+    /// None of it.
+    Off,
+    /// The whole state before and after.
+    Full,
+    /// Only the lines of the state that changed, `-` before and `+` after.
+    Diff,
+}
+
+/// A middleware that logs every dispatch through the `log` crate, under the
+/// `redux` target: the action, how long it took, and optionally the state.
+///
+/// Dispatches from inside another dispatch, say by a thunk, are logged
+/// indented under it, and when `collapsed` as one line without the state.
+/// Sensitive fields can be hidden by redacting a copy of the action or state
+/// before it's printed.
+///
+/// ```
+/// # use redux::{LoggerMiddleware, Reducer, StateLogging, Store};
+/// # use log::Level;
+/// #[derive(Clone, Default, Debug)]
+/// struct Session {
+///     user: String,
+///     token: String,
+/// }
+/// # impl Reducer for Session {
+/// #     type Action = String;
+/// #     type Error = String;
+/// #     fn reduce(&mut self, user: String) -> Result<Self, String> {
+/// #         self.user = user;
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+///
+/// let logger = LoggerMiddleware::new()
+///     .level(Level::Debug)
+///     .states(StateLogging::Diff)
+///     .redact_state(|session: &Session| Session { token: String::from("***"), ..session.clone() });
+/// let store : Store<Session> = Store::new(vec![Box::new(logger)]);
+/// ```
+pub struct LoggerMiddleware<T: Reducer> {
+    level: Level,
+    error_level: Level,
+    states: StateLogging,
+    collapsed: bool,
+    filter: Option<ActionFilter<T::Action>>,
+    kind: Option<fn(&T::Action) -> &'static str>,
+    redact_action: Option<Redaction<T::Action>>,
+    redact_state: Option<Redaction<T>>,
+    // how deep in nested dispatches each thread is
+    depths: Mutex<HashMap<ThreadId, usize>>,
+}
+
+impl<T: Reducer> Default for LoggerMiddleware<T> {
+    fn default() -> Self {
+        LoggerMiddleware::new()
+    }
+}
+
+impl<T: Reducer> LoggerMiddleware<T> {
+    /// Logs every action at `Info` and errors at `Error`, without the state.
+    pub fn new() -> LoggerMiddleware<T> {
+        LoggerMiddleware {
+            level: Level::Info,
+            error_level: Level::Error,
+            states: StateLogging::Off,
+            collapsed: false,
+            filter: None,
+            kind: None,
+            redact_action: None,
+            redact_state: None,
+            depths: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The level dispatches are logged at.
+    pub fn level(mut self, level: Level) -> LoggerMiddleware<T> {
+        self.level = level;
+        self
+    }
+
+    /// The level dispatches that fail are logged at.
+    pub fn error_level(mut self, level: Level) -> LoggerMiddleware<T> {
+        self.error_level = level;
+        self
+    }
+
+    /// How much of the state to log after each dispatch, at `level`.
+    pub fn states(mut self, states: StateLogging) -> LoggerMiddleware<T> {
+        self.states = states;
+        self
+    }
+
+    /// Logs nested dispatches as one line each, without the state.
+    pub fn collapsed(mut self, collapsed: bool) -> LoggerMiddleware<T> {
+        self.collapsed = collapsed;
+        self
+    }
+
+    /// Only logs the actions `filter` returns true for. The others are still
+    /// dispatched, just quietly.
+    pub fn filter<F>(mut self, filter: F) -> LoggerMiddleware<T>
+        where F: 'static + Fn(&T::Action) -> bool + Send + Sync
+    {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Only logs the actions `kinds` matches, for example the kinds set in a
+    /// config file.
+    pub fn kinds(self, kinds: KindFilter) -> LoggerMiddleware<T> where T::Action: ActionKind {
+        self.filter(move |action| kinds.matches(action))
+    }
+
+    /// Logs each action's `ActionKind::kind` in brackets before it.
+    pub fn show_kinds(mut self) -> LoggerMiddleware<T> where T::Action: ActionKind {
+        self.kind = Some(<T::Action as ActionKind>::kind);
+        self
+    }
+
+    /// Logs what `redact` makes of each action instead of the action itself.
+    pub fn redact_action<F>(mut self, redact: F) -> LoggerMiddleware<T>
+        where F: 'static + Fn(&T::Action) -> T::Action + Send + Sync
+    {
+        self.redact_action = Some(Box::new(redact));
+        self
+    }
+
+    /// Logs what `redact` makes of the state instead of the state itself.
+    pub fn redact_state<F>(mut self, redact: F) -> LoggerMiddleware<T>
+        where F: 'static + Fn(&T) -> T + Send + Sync
+    {
+        self.redact_state = Some(Box::new(redact));
+        self
+    }
+
+    // counts the dispatch that's starting until the returned guard is
+    // dropped, even by a panic
+    fn enter(&self) -> Nesting<'_, T> {
+        let mut depths = self.depths.lock().unwrap();
+        let depth = depths.entry(thread::current().id()).or_insert(0);
+        *depth += 1;
+        Nesting { logger: self, depth: *depth - 1 }
+    }
+
+    fn leave(&self) {
+        let mut depths = self.depths.lock().unwrap();
+        let id = thread::current().id();
+        match depths.get_mut(&id) {
+            Some(depth) if *depth > 1 => *depth -= 1,
+            _ => {
+                depths.remove(&id);
+            },
+        }
+    }
+}
+
+// a dispatch that's being logged on this thread
+struct Nesting<'a, T: Reducer> {
+    logger: &'a LoggerMiddleware<T>,
+    // how many dispatches it's nested in
+    depth: usize,
+}
+
+impl<T: Reducer> Drop for Nesting<'_, T> {
+    fn drop(&mut self) {
+        self.logger.leave();
+    }
+}
+
+impl<T> LoggerMiddleware<T> where T: Reducer + Debug, T::Action: Debug {
+    fn show_action(&self, action: &T::Action) -> String {
+        let shown = match self.redact_action {
+            Some(ref redact) => format!("{:?}", redact(action)),
+            None => format!("{:?}", action),
+        };
+        match self.kind {
+            Some(kind) => format!("[{}] {}", kind(action), shown),
+            None => shown,
+        }
+    }
+
+    fn show_state(&self, state: &T) -> String {
+        match self.redact_state {
+            Some(ref redact) => format!("{:#?}", redact(state)),
+            None => format!("{:#?}", state),
+        }
+    }
+
+    fn log(&self, level: Level, depth: usize, message: &str) {
+        let indent = "  ".repeat(depth);
+        for line in message.lines() {
+            log::log!(target: "redux", level, "{}{}", indent, line);
+        }
+    }
+}
+
+fn millis(elapsed: Duration) -> String {
+    format!("{:.2}ms", elapsed.as_secs_f64() * 1000.0)
+}
+
+impl<T> Middleware<T> for LoggerMiddleware<T>
+    where T: 'static + Reducer + Debug,
+          T::Action: Debug
+{
+    fn dispatch(&self, store: &Store<T>, action: T::Action, next: &DispatchFunc<T>) -> Result<T, String> {
+        let logged = self.filter.as_ref().is_none_or(|filter| filter(&action))
+            && (log::log_enabled!(target: "redux", self.level) || log::log_enabled!(target: "redux", self.error_level));
+        if !logged {
+            return next(store, action);
+        }
+
+        let nesting = self.enter();
+        let depth = nesting.depth;
+        let brief = depth > 0 && self.collapsed;
+        let shown = self.show_action(&action);
+        // the reduce keeps the state it started from; another thread may
+        // dispatch between a `get_state` here and the reduce
+        let capture = match (self.states, brief) {
+            (StateLogging::Off, _) | (_, true) => None,
+            _ => Some(store.capture_before()),
+        };
+        if !brief {
+            self.log(self.level, depth, &format!("action {}", shown));
+        }
+
+        let started = Instant::now();
+        let result = next(store, action);
+        let elapsed = millis(started.elapsed());
+        drop(nesting);
+
+        let (level, outcome) = match result {
+            Ok(_) => (self.level, format!("done in {}", elapsed)),
+            Err(ref e) => (self.error_level, format!("failed in {}: {}", elapsed, e)),
+        };
+        if brief {
+            self.log(level, depth, &format!("action {} {}", shown, outcome));
+            return result;
+        }
+        self.log(level, depth, &outcome);
+
+        if let Some(capture) = capture {
+            let before = capture.take();
+            // a dispatch that failed or never reached the reducer left the
+            // state as it was
+            let after = match (&result, &before) {
+                (Ok(state), _) => state.clone(),
+                (Err(_), Some(before)) => before.clone(),
+                (Err(_), None) => store.get_state(),
+            };
+            let before = self.show_state(before.as_ref().unwrap_or(&after));
+            let after = self.show_state(&after);
+            match self.states {
+                StateLogging::Full => {
+                    self.log(self.level, depth, &format!("prev state {}", before));
+                    self.log(self.level, depth, &format!("next state {}", after));
+                },
+                _ => {
+                    let diff = diff(&before, &after);
+                    let changes : Vec<&str> = diff.lines().filter(|line| !line.starts_with(' ')).collect();
+                    let changes = changes.join("\n");
+                    match changes.is_empty() {
+                        true => self.log(self.level, depth, "state unchanged"),
+                        false => self.log(self.level, depth, &format!("state changes\n{}", changes)),
+                    }
+                },
+            }
+        }
+
+        result
+    }
+}
@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+use crate::{ActionKind, DispatchFunc, Middleware, Reducer, Store};
+
+/// What a `MetricsMiddleware` reports about each dispatch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dispatched {
+    pub kind: &'static str,
+    pub categories: &'static [&'static str],
+    /// How long the rest of the chain took, reduce included.
+    pub elapsed: Duration,
+    /// Why the dispatch failed, if it did.
+    pub error: Option<String>,
+}
+
+/// A middleware that reports every dispatch by `ActionKind` to a hook, to be
+/// counted and timed by whatever keeps the application's metrics.
+///
+/// Only what goes on after it in the chain is timed, so it usually goes
+/// last, and nested dispatches, say by a thunk, are reported on their own
+/// as well as inside the one that made them.
+///
+/// ```
+/// # use redux::{ActionKind, MetricsMiddleware, Reducer, Store};
+/// # use std::collections::HashMap;
+/// # use std::sync::{Arc, Mutex};
+/// # #[derive(Clone)]
+/// # struct Tick;
+/// # impl ActionKind for Tick {
+/// #     fn kind(&self) -> &'static str { "Tick" }
+/// # }
+/// # #[derive(Clone, Default)]
+/// # struct Clock(usize);
+/// # impl Reducer for Clock {
+/// #     type Action = Tick;
+/// #     type Error = String;
+/// #     fn reduce(&mut self, _: Tick) -> Result<Self, String> {
+/// #         self.0 += 1;
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+/// let counts : Arc<Mutex<HashMap<&str, usize>>> = Arc::default();
+/// let counted = counts.clone();
+/// let metrics = MetricsMiddleware::new(move |dispatched| {
+///     *counted.lock().unwrap().entry(dispatched.kind).or_insert(0) += 1;
+/// });
+/// let store : Store<Clock> = Store::new(vec![Box::new(metrics)]);
+/// store.dispatch(Tick).unwrap();
+/// assert_eq!(1, counts.lock().unwrap()["Tick"]);
+/// ```
+pub struct MetricsMiddleware {
+    report: Box<dyn Fn(&Dispatched) + Send + Sync>,
+}
+
+impl MetricsMiddleware {
+    pub fn new<F>(report: F) -> MetricsMiddleware
+        where F: 'static + Fn(&Dispatched) + Send + Sync
+    {
+        MetricsMiddleware { report: Box::new(report) }
+    }
+}
+
+impl<T: 'static + Reducer> Middleware<T> for MetricsMiddleware where T::Action: ActionKind {
+    fn dispatch(&self, store: &Store<T>, action: T::Action, next: &DispatchFunc<T>) -> Result<T, String> {
+        let (kind, categories) = (action.kind(), action.categories());
+        let started = Instant::now();
+        let result = next(store, action);
+        (self.report)(&Dispatched {
+            kind,
+            categories,
+            elapsed: started.elapsed(),
+            error: result.as_ref().err().cloned(),
+        });
+
+        result
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+use serde_json::Value;
+
+// turns a value saved at one version into the next version's
+type Migration = Box<dyn Fn(Value) -> Result<Value, String> + Send + Sync>;
+
+/// Why a saved value couldn't be brought up to the current schema version.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MigrationError {
+    /// The value was saved by a newer version of the app than this one.
+    FutureVersion { version: u32, current: u32 },
+    /// There's no migration from this version to the next.
+    Missing { from: u32 },
+    /// The migration from this version to the next failed.
+    Failed { from: u32, message: String },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MigrationError::FutureVersion { version, current } => write!(f, "The saved state is from schema version {}, but this is only version {}", version, current),
+            MigrationError::Missing { from } => write!(f, "There's no migration from schema version {} to {}", from, from + 1),
+            MigrationError::Failed { from, ref message } => write!(f, "The migration from schema version {} to {} failed: {}", from, from + 1, message),
+        }
+    }
+}
+
+impl Error for MigrationError {}
+
+/// The schema versions of some persisted state or actions, and the
+/// migrations between them. Each migration takes the serialized value from
+/// one version to the next, and `migrate` runs as many of them as it takes to
+/// reach the current version. For actions they're upcasters, see
+/// `Store::from_versioned_log`.
+///
+/// ```
+/// # use redux::Migrations;
+/// # use serde_json::json;
+/// let migrations = Migrations::new(2)
+///     // version 1 renamed `name` to `title`
+///     .add(0, |mut state| {
+///         let name = state["name"].take();
+///         state["title"] = name;
+///         Ok(state)
+///     })
+///     // version 2 added `done`
+///     .add(1, |mut state| {
+///         state["done"] = json!(false);
+///         Ok(state)
+///     });
+///
+/// let state = migrations.migrate(0, json!({ "name": "ship it" })).unwrap();
+/// assert_eq!(json!({ "title": "ship it", "name": null, "done": false }), state);
+/// ```
+pub struct Migrations {
+    current: u32,
+    migrations: BTreeMap<u32, Migration>,
+}
+
+impl Migrations {
+    /// No migrations yet, with `current` as the version state is saved at.
+    pub fn new(current: u32) -> Migrations {
+        Migrations {
+            current,
+            migrations: BTreeMap::new(),
+        }
+    }
+
+    /// Adds the migration from version `from` to `from + 1`, replacing any
+    /// there was.
+    pub fn add<F>(mut self, from: u32, migration: F) -> Migrations
+        where F: 'static + Fn(Value) -> Result<Value, String> + Send + Sync
+    {
+        self.migrations.insert(from, Box::new(migration));
+        self
+    }
+
+    pub fn current(&self) -> u32 {
+        self.current
+    }
+
+    /// Brings `value`, saved at `version`, up to the current version.
+    pub fn migrate(&self, version: u32, mut value: Value) -> Result<Value, MigrationError> {
+        if version > self.current {
+            return Err(MigrationError::FutureVersion { version, current: self.current });
+        }
+
+        for from in version..self.current {
+            let migration = self.migrations.get(&from).ok_or(MigrationError::Missing { from })?;
+            value = migration(value).map_err(|message| MigrationError::Failed { from, message })?;
+        }
+
+        Ok(value)
+    }
+
+    /// Checks that every version with a migration has a chain of them all
+    /// the way to the current version, and that none starts at or past it,
+    /// where it would never run. Meant for tests, so a gap shows up before
+    /// anyone's save file needs it.
+    pub fn check(&self) -> Result<(), MigrationError> {
+        if let Some(from) = self.migrations.keys().find(|from| **from >= self.current) {
+            return Err(MigrationError::FutureVersion { version: *from, current: self.current });
+        }
+        match self.migrations.keys().next() {
+            Some(oldest) => (*oldest..self.current)
+                .find(|from| !self.migrations.contains_key(from))
+                .map_or(Ok(()), |from| Err(MigrationError::Missing { from })),
+            None => Ok(()),
+        }
+    }
+
+    /// Panics unless `before`, saved at `version`, migrates to `after`. Meant
+    /// for tests, with a fixture per old version.
+    pub fn assert_migrates(&self, version: u32, before: Value, after: Value) {
+        match self.migrate(version, before) {
+            Ok(migrated) => assert_eq!(after, migrated, "migrating from schema version {}", version),
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+impl Default for Migrations {
+    fn default() -> Self {
+        Migrations::new(0)
+    }
+}
+
+// the key saved values keep their schema version under. It's unlikely to
+// be a field of any state, so an unversioned save that happens to have a
+// `version` isn't taken for a stamped one.
+const VERSION_KEY: &str = "__redux_version";
+
+// saved values are stamped with the schema version they were written at, as
+// `{ "__redux_version": 2, "<field>": ... }`
+pub(crate) fn stamp(version: u32, field: &str, value: Value) -> Value {
+    let mut saved = serde_json::Map::new();
+    saved.insert(String::from(VERSION_KEY), Value::from(version));
+    saved.insert(String::from(field), value);
+    Value::Object(saved)
+}
+
+// anything that isn't stamped was saved before there were versions
+pub(crate) fn unstamp(saved: Value, field: &str) -> (u32, Value) {
+    match saved {
+        Value::Object(mut saved) if saved.len() == 2 && saved.contains_key(field) && saved.get(VERSION_KEY).is_some_and(Value::is_u64) => {
+            let version = saved[VERSION_KEY].as_u64().unwrap_or(0) as u32;
+            (version, saved.remove(field).unwrap_or(Value::Null))
+        },
+        saved => (0, saved),
+    }
+}
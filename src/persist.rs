@@ -0,0 +1,548 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::migrations::{stamp, unstamp};
+use crate::{DispatchFunc, Middleware, Migrations, Reducer, Store};
+
+/// Where a `Persistor` keeps serialized state, by key.
+pub trait Storage: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<String>, String>;
+    fn set(&self, key: &str, value: &str) -> Result<(), String>;
+    fn remove(&self, key: &str) -> Result<(), String>;
+}
+
+/// A `Storage` in memory, for tests. Clones share their contents.
+#[derive(Clone, Default)]
+pub struct MemoryStorage {
+    items: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> MemoryStorage {
+        MemoryStorage::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.items.lock().unwrap().get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        self.items.lock().unwrap().insert(String::from(key), String::from(value));
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<(), String> {
+        self.items.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+/// A `Storage` that keeps each key in a `<key>.json` file in a directory,
+/// which is created when it's first written to.
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> FileStorage {
+        FileStorage { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+impl Storage for FileStorage {
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        match fs::read_to_string(self.path(key)) {
+            Ok(value) => Ok(Some(value)),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // written to a temporary file first so a crash never leaves half a value
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        let temporary = self.dir.join(format!("{}.json.tmp", key));
+        fs::write(&temporary, value).map_err(|e| e.to_string())?;
+        fs::rename(temporary, self.path(key)).map_err(|e| e.to_string())
+    }
+
+    fn remove(&self, key: &str) -> Result<(), String> {
+        match fs::remove_file(self.path(key)) {
+            Err(ref e) if e.kind() != ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Which top-level fields of the state, as serde sees them, get persisted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Slices {
+    All,
+    /// Only these fields.
+    Whitelist(Vec<String>),
+    /// Every field but these.
+    Blacklist(Vec<String>),
+}
+
+impl Slices {
+    fn select(&self, state: Value) -> Value {
+        match (self, state) {
+            (Slices::Whitelist(keep), Value::Object(mut fields)) => {
+                fields.retain(|field, _| keep.contains(field));
+                Value::Object(fields)
+            },
+            (Slices::Blacklist(drop), Value::Object(mut fields)) => {
+                fields.retain(|field, _| !drop.contains(field));
+                Value::Object(fields)
+            },
+            (_, state) => state,
+        }
+    }
+}
+
+/// What a `Persistor` saves, and how often.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PersistConfig {
+    /// The `Storage` key the state is saved under.
+    pub key: String,
+    pub slices: Slices,
+    /// The least time between two writes. Changes in between are written
+    /// together once it's up.
+    pub throttle: Duration,
+}
+
+impl PersistConfig {
+    /// Saves every field, on every change, under `key`.
+    pub fn new(key: &str) -> PersistConfig {
+        PersistConfig {
+            key: String::from(key),
+            slices: Slices::All,
+            throttle: Duration::from_millis(0),
+        }
+    }
+}
+
+/// The actions a `Persisted` reducer accepts.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PersistAction<A> {
+    /// Merges the saved fields into the state, or just marks it rehydrated
+    /// when nothing was saved.
+    Rehydrate(Option<Value>),
+    /// An action for the wrapped reducer.
+    Action(A),
+}
+
+/// A higher-order reducer for state a `Persistor` saves: the wrapped state,
+/// plus whether it's been rehydrated from storage yet.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Persisted<R> {
+    state: R,
+    rehydrated: bool,
+}
+
+impl<R> Persisted<R> {
+    /// The wrapped state.
+    pub fn state(&self) -> &R {
+        &self.state
+    }
+
+    pub fn is_rehydrated(&self) -> bool {
+        self.rehydrated
+    }
+}
+
+impl<R> Deref for Persisted<R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        &self.state
+    }
+}
+
+// the saved fields overwrite the current ones, the rest stay as they are
+fn merge<R: Serialize + DeserializeOwned>(state: &R, saved: Value) -> Result<R, String> {
+    let mut merged = serde_json::to_value(state).map_err(|e| e.to_string())?;
+    match (merged.as_object_mut(), saved) {
+        (Some(fields), Value::Object(saved)) => fields.extend(saved),
+        (_, saved) => merged = saved,
+    }
+
+    serde_json::from_value(merged).map_err(|e| format!("Can't rehydrate the state: {}", e))
+}
+
+impl<R> Reducer for Persisted<R> where R: Reducer + Serialize + DeserializeOwned {
+    type Action = PersistAction<R::Action>;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            PersistAction::Rehydrate(saved) => {
+                // a bad save still counts, so the app carries on without it
+                self.rehydrated = true;
+                if let Some(saved) = saved {
+                    self.state = merge(&self.state, saved)?;
+                }
+            },
+            PersistAction::Action(action) => {
+                self.state.reduce(action).map_err(|e| e.to_string())?;
+            },
+        }
+
+        Ok(self.clone())
+    }
+}
+
+// a held action and what reducing it gave
+type Replayed<R> = (<R as Reducer>::Action, Result<Persisted<R>, String>);
+
+struct Gate<R: Reducer> {
+    open: bool,
+    held: Vec<R::Action>,
+    // the held actions, reduced along with the rehydrate and waiting to be
+    // published
+    replayed: Vec<Replayed<R>>,
+    // the thread publishing them; dispatches from anywhere else wait
+    replaying: Option<ThreadId>,
+}
+
+#[derive(Default)]
+struct Writes {
+    last: Option<Instant>,
+    written: Option<Value>,
+    pending: Option<Value>,
+    scheduled: bool,
+    // set when the save couldn't be read or rehydrated, so it isn't
+    // overwritten
+    stopped: bool,
+    error: Option<String>,
+}
+
+struct Shared<R: Reducer> {
+    storage: Arc<dyn Storage>,
+    config: PersistConfig,
+    migrations: Migrations,
+    gate: Mutex<Gate<R>>,
+    opened: Condvar,
+    writes: Mutex<Writes>,
+    rehydrated: Mutex<Option<Result<(), String>>>,
+    finished: Condvar,
+}
+
+impl<R: Reducer> Shared<R> {
+    fn load(&self) -> Result<Option<Value>, String> {
+        match self.storage.get(&self.config.key)? {
+            Some(saved) => {
+                let saved = serde_json::from_str(&saved).map_err(|e| format!("Can't read the saved state: {}", e))?;
+                let (version, saved) = unstamp(saved, "state");
+                let saved = self.migrations.migrate(version, saved).map_err(|e| e.to_string())?;
+                Ok(Some(self.config.slices.select(saved)))
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn write(&self, writes: &mut Writes) {
+        if writes.stopped {
+            writes.pending = None;
+            return;
+        }
+        if let Some(value) = writes.pending.take() {
+            let saved = stamp(self.migrations.current(), "state", value.clone());
+            match self.storage.set(&self.config.key, &saved.to_string()) {
+                Ok(()) => writes.written = Some(value),
+                Err(e) => writes.error = Some(e),
+            }
+            writes.last = Some(Instant::now());
+        }
+    }
+}
+
+impl<R: 'static + Reducer + Serialize + Send> Shared<R> where R::Action: Send {
+    fn changed(shared: &Arc<Shared<R>>, state: &Persisted<R>) {
+        // writing before rehydrating would overwrite the save with defaults
+        if !state.is_rehydrated() {
+            return;
+        }
+        let value = match serde_json::to_value(state.state()) {
+            Ok(value) => shared.config.slices.select(value),
+            Err(e) => {
+                shared.writes.lock().unwrap().error = Some(e.to_string());
+                return;
+            },
+        };
+
+        let mut writes = shared.writes.lock().unwrap();
+        if writes.written.as_ref() == Some(&value) {
+            writes.pending = None;
+            return;
+        }
+        writes.pending = Some(value);
+
+        let since = writes.last.map_or(shared.config.throttle, |last| last.elapsed());
+        if since >= shared.config.throttle {
+            shared.write(&mut writes);
+        } else if !writes.scheduled {
+            writes.scheduled = true;
+            let wait = shared.config.throttle - since;
+            let shared = Arc::downgrade(shared);
+            thread::spawn(move || {
+                thread::sleep(wait);
+                if let Some(shared) = shared.upgrade() {
+                    let mut writes = shared.writes.lock().unwrap();
+                    writes.scheduled = false;
+                    shared.write(&mut writes);
+                }
+            });
+        }
+    }
+}
+
+impl<R: Reducer> Drop for Shared<R> {
+    fn drop(&mut self) {
+        let mut writes = std::mem::take(&mut *self.writes.lock().unwrap());
+        self.write(&mut writes);
+    }
+}
+
+/// redux-persist for a store of `Persisted<R>`: rehydrates the state from a
+/// `Storage` when it's attached, then saves the state whenever it changes.
+///
+/// Rehydration runs on its own thread, and until it's done the actions
+/// dispatched to the store are held back, then reduced in order once the
+/// saved state is in. Like any dispatch a middleware answers, a held one is
+/// published to the store's streams and subscriptions straight away, then
+/// again with the state it led to once it's been reduced. Held actions have
+/// already been through the middleware listed after the persistor, so it
+/// should usually go first, and since the dispatch that brought them has
+/// returned, their errors come back from `wait_until_rehydrated`. If a
+/// middleware before the persistor drops the rehydrate, nothing is loaded or
+/// saved, and the held actions are dropped with an error.
+///
+/// Saves are stamped with a schema version, and `with_migrations` brings
+/// older ones up to date as they're rehydrated. A save that can't be read or
+/// doesn't fit the state, say because it's from a newer version of the app,
+/// is left alone: the store starts from its default state and nothing is
+/// saved until it's `purge`d.
+///
+/// ```
+/// # use redux::{MemoryStorage, PersistAction, PersistConfig, Persisted, Persistor, Reducer, Slices, Store};
+/// # use std::sync::Arc;
+/// #[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+/// struct Settings {
+///     theme: String,
+///     // not worth saving
+///     scroll: usize,
+/// }
+/// # impl Reducer for Settings {
+/// #     type Action = String;
+/// #     type Error = String;
+/// #     fn reduce(&mut self, theme: String) -> Result<Self, String> {
+/// #         self.theme = theme;
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+///
+/// let storage = MemoryStorage::new();
+/// let config = PersistConfig { slices: Slices::Blacklist(vec![String::from("scroll")]), ..PersistConfig::new("settings") };
+/// let persistor = Persistor::new(storage, config);
+/// let store : Arc<Store<Persisted<Settings>>> = Arc::new(Store::new(vec![Box::new(persistor.clone())]));
+/// persistor.attach(&store);
+///
+/// let _ = store.dispatch(PersistAction::Action(String::from("dark")));
+/// persistor.wait_until_rehydrated().unwrap();
+/// assert_eq!("dark", store.get_state().theme);
+/// ```
+pub struct Persistor<R: Reducer> {
+    shared: Arc<Shared<R>>,
+}
+
+impl<R: Reducer> Clone for Persistor<R> {
+    fn clone(&self) -> Self {
+        Persistor { shared: self.shared.clone() }
+    }
+}
+
+impl<R> Persistor<R>
+    where R: 'static + Reducer + Serialize + DeserializeOwned + Send,
+          R::Action: Send
+{
+    /// A persistor for state with no schema versions, which saves it as
+    /// version 0.
+    pub fn new<S: 'static + Storage>(storage: S, config: PersistConfig) -> Persistor<R> {
+        Persistor::with_migrations(storage, config, Migrations::default())
+    }
+
+    /// A persistor that saves state at the current version of `migrations`,
+    /// and migrates older saves to it when rehydrating.
+    pub fn with_migrations<S: 'static + Storage>(storage: S, config: PersistConfig, migrations: Migrations) -> Persistor<R> {
+        Persistor {
+            shared: Arc::new(Shared {
+                storage: Arc::new(storage),
+                config,
+                migrations,
+                gate: Mutex::new(Gate { open: false, held: vec![], replayed: vec![], replaying: None }),
+                opened: Condvar::new(),
+                writes: Mutex::new(Writes::default()),
+                rehydrated: Mutex::new(None),
+                finished: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Starts rehydrating `store`, which should be the store this middleware
+    /// was given to, and subscribes to it to save its changes.
+    pub fn attach(&self, store: &Arc<Store<Persisted<R>>>) {
+        let shared = self.shared.clone();
+        store.subscribe(Box::new(move |store, _| Shared::changed(&shared, &store.get_state())));
+
+        let shared = self.shared.clone();
+        let store = Arc::downgrade(store);
+        thread::spawn(move || Persistor::rehydrate(shared, store));
+    }
+
+    fn rehydrate(shared: Arc<Shared<R>>, store: Weak<Store<Persisted<R>>>) {
+        let (saved, mut result) = match shared.load() {
+            Ok(saved) => (saved, Ok(())),
+            Err(e) => {
+                shared.writes.lock().unwrap().stopped = true;
+                (None, Err(e))
+            },
+        };
+        if let Some(store) = store.upgrade() {
+            let dispatched = store.dispatch(PersistAction::Rehydrate(saved));
+            let replayed = Persistor::publish_replayed(&shared, &store);
+            result = result.and(dispatched.map(|_| ())).and(replayed);
+        }
+
+        *shared.rehydrated.lock().unwrap() = Some(result);
+        shared.finished.notify_all();
+    }
+
+    // publishes the actions that were held back, in order, once the rehydrate
+    // itself has been, then lets everything else through
+    fn publish_replayed(shared: &Shared<R>, store: &Store<Persisted<R>>) -> Result<(), String> {
+        let mut errors = vec![];
+        let replayed = {
+            let mut gate = shared.gate.lock().unwrap();
+            // a middleware before this one dropped the rehydrate, so nothing
+            // was loaded and the held actions were never reduced
+            if gate.replaying.is_none() {
+                shared.writes.lock().unwrap().stopped = true;
+                errors.push(format!("The rehydrate never reached the persistor, so the saved state wasn't loaded and {} held actions were dropped.", gate.held.len()));
+                gate.held.clear();
+            }
+            gate.replaying = Some(thread::current().id());
+            std::mem::take(&mut gate.replayed)
+        };
+        for (action, result) in replayed {
+            match result {
+                Ok(state) => store.notify(&PersistAction::Action(action), &state),
+                Err(e) => errors.push(format!("Error during dispatch: {}", e)),
+            }
+        }
+
+        let mut gate = shared.gate.lock().unwrap();
+        gate.open = true;
+        gate.replaying = None;
+        shared.opened.notify_all();
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors.join("\n")),
+        }
+    }
+
+    /// Blocks until the store has been rehydrated, returning the error if the
+    /// saved state couldn't be read or migrated, or if an action held back
+    /// until then failed. The store carries on from the default state if the
+    /// save was the problem.
+    pub fn wait_until_rehydrated(&self) -> Result<(), String> {
+        let mut rehydrated = self.shared.rehydrated.lock().unwrap();
+        loop {
+            match *rehydrated {
+                Some(ref result) => return result.clone(),
+                None => rehydrated = self.shared.finished.wait(rehydrated).unwrap(),
+            }
+        }
+    }
+
+    /// Writes any change the throttle is holding back, returning the error
+    /// from the last write that failed, if any.
+    pub fn flush(&self) -> Result<(), String> {
+        let mut writes = self.shared.writes.lock().unwrap();
+        self.shared.write(&mut writes);
+        match writes.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Deletes the saved state, and starts saving again if the old save
+    /// couldn't be read.
+    pub fn purge(&self) -> Result<(), String> {
+        let mut writes = self.shared.writes.lock().unwrap();
+        writes.pending = None;
+        writes.written = None;
+        writes.stopped = false;
+        self.shared.storage.remove(&self.shared.config.key)
+    }
+}
+
+impl<R> Middleware<Persisted<R>> for Persistor<R>
+    where R: 'static + Reducer + Serialize + DeserializeOwned + Send,
+          R::Action: Send
+{
+    fn dispatch(&self, store: &Store<Persisted<R>>, action: PersistAction<R::Action>, next: &DispatchFunc<Persisted<R>>) -> Result<Persisted<R>, String> {
+        let mut gate = self.shared.gate.lock().unwrap();
+        // a dispatch from a subscription to a replayed action goes through
+        // after it, anything else waits for every one to be published
+        let me = thread::current().id();
+        while !gate.open && gate.replaying.is_some_and(|replaying| replaying != me) {
+            gate = self.shared.opened.wait(gate).unwrap();
+        }
+        match action {
+            PersistAction::Action(action) if !gate.open && gate.replaying.is_none() => {
+                gate.held.push(action);
+                drop(gate);
+                Ok(store.get_state())
+            },
+            PersistAction::Rehydrate(saved) if !gate.open && gate.replaying.is_none() => {
+                // the gate stays locked until the held actions are in, so
+                // nothing can get in front of them
+                let rehydrated = next(store, PersistAction::Rehydrate(saved));
+                // the save didn't fit the state, so it's left alone like one
+                // that couldn't be read
+                if rehydrated.is_err() {
+                    self.shared.writes.lock().unwrap().stopped = true;
+                }
+                for action in std::mem::take(&mut gate.held) {
+                    let result = next(store, PersistAction::Action(action.clone()));
+                    gate.replayed.push((action, result));
+                }
+                // published by the rehydrating thread after the rehydrate
+                // itself, which keeps the gate shut until then
+                gate.replaying = Some(me);
+                rehydrated
+            },
+            action => {
+                drop(gate);
+                next(store, action)
+            },
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt::{self, Debug};
+
+use proptest::collection::vec;
+use proptest::strategy::Strategy;
+use proptest::test_runner::{Config, TestCaseError, TestError, TestRunner};
+use quickcheck::{Arbitrary, Gen};
+
+use crate::{Reducer, Store};
+
+type Invariant<T> = Box<dyn Fn(&T) -> bool>;
+
+/// An invariant that stopped holding, with the smallest sequence of actions
+/// found that breaks it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Broken<A> {
+    pub invariant: String,
+    /// The index of the action after which it stopped holding, or `None` if
+    /// it didn't hold for the initial state.
+    pub after: Option<usize>,
+    pub actions: Vec<A>,
+}
+
+impl<A: Debug> fmt::Display for Broken<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.after {
+            Some(index) => write!(f, "The invariant `{}` broke after action #{} ({:?}) of {:?}", self.invariant, index, self.actions[index], self.actions),
+            None => write!(f, "The invariant `{}` doesn't hold for the initial state", self.invariant),
+        }
+    }
+}
+
+/// Invariants of a reducer, checked against random sequences of actions.
+///
+/// Each sequence is dispatched to a fresh `Store` starting from the initial
+/// state, and every invariant is checked before the first action and after
+/// each one, whether it reduced or failed. When one breaks, the sequence is
+/// shrunk to a minimal one that still breaks it.
+///
+/// Actions are generated by a proptest `Strategy` with `proptest`, or by
+/// their quickcheck `Arbitrary` implementation with `quickcheck`.
+///
+/// ```
+/// # use redux::Reducer;
+/// # use redux::testing::Invariants;
+/// # use proptest::prelude::*;
+/// #[derive(Clone, Default)]
+/// struct Stack {
+///     items: Vec<u8>,
+/// }
+///
+/// #[derive(Clone, Debug)]
+/// enum StackAction {
+///     Push(u8),
+///     Pop,
+/// }
+/// # impl Reducer for Stack {
+/// #     type Action = StackAction;
+/// #     type Error = String;
+/// #     fn reduce(&mut self, action: StackAction) -> Result<Self, String> {
+/// #         match action {
+/// #             StackAction::Push(item) => self.items.push(item),
+/// #             StackAction::Pop => { self.items.pop().ok_or("empty")?; },
+/// #         }
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+///
+/// let actions = prop_oneof![any::<u8>().prop_map(StackAction::Push), Just(StackAction::Pop)];
+/// Invariants::new(Stack::default())
+///     .holds("at most 32 items", |stack| stack.items.len() <= 32)
+///     .proptest(actions)
+///     .unwrap();
+/// ```
+pub struct Invariants<T: Reducer> {
+    initial: T,
+    invariants: Vec<(String, Invariant<T>)>,
+    cases: u32,
+    max_actions: usize,
+}
+
+impl<T: 'static + Reducer> Invariants<T> where T::Action: Debug {
+    /// No invariants yet, starting every sequence from `initial`. 256
+    /// sequences of up to 32 actions are tried by default.
+    pub fn new(initial: T) -> Invariants<T> {
+        Invariants {
+            initial,
+            invariants: vec![],
+            cases: 256,
+            max_actions: 32,
+        }
+    }
+
+    /// Adds an invariant, named for the failure message.
+    pub fn holds<F: 'static + Fn(&T) -> bool>(mut self, name: &str, invariant: F) -> Invariants<T> {
+        self.invariants.push((String::from(name), Box::new(invariant)));
+        self
+    }
+
+    /// How many sequences to try.
+    pub fn cases(mut self, cases: u32) -> Invariants<T> {
+        self.cases = cases;
+        self
+    }
+
+    /// How long the sequences can get.
+    pub fn max_actions(mut self, max_actions: usize) -> Invariants<T> {
+        self.max_actions = max_actions;
+        self
+    }
+
+    /// Runs one sequence of actions, checking every invariant along the way.
+    pub fn check(&self, actions: &[T::Action]) -> Result<(), Broken<T::Action>> {
+        let store = Store::with_state(self.initial.clone(), vec![]);
+        self.verify(&store.get_state(), None, actions)?;
+        for (index, action) in actions.iter().enumerate() {
+            let _ = store.dispatch(action.clone());
+            self.verify(&store.get_state(), Some(index), actions)?;
+        }
+
+        Ok(())
+    }
+
+    fn verify(&self, state: &T, after: Option<usize>, actions: &[T::Action]) -> Result<(), Broken<T::Action>> {
+        match self.invariants.iter().find(|(_, invariant)| !invariant(state)) {
+            Some((name, _)) => Err(Broken {
+                invariant: name.clone(),
+                after,
+                actions: actions.to_vec(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks sequences of actions generated by `actions`, leaving the
+    /// shrinking to proptest.
+    pub fn proptest<S: Strategy<Value = T::Action>>(&self, actions: S) -> Result<(), Broken<T::Action>> {
+        // failures aren't saved to a regressions file, there's no test file
+        // to put it next to
+        let config = Config {
+            cases: self.cases,
+            failure_persistence: None,
+            ..Config::default()
+        };
+        let mut runner = TestRunner::new(config);
+        let result = runner.run(&vec(actions, 0..=self.max_actions), |actions| {
+            self.check(&actions).map_err(|broken| TestCaseError::fail(broken.to_string()))
+        });
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(TestError::Fail(_, actions)) => self.check(&actions),
+            Err(TestError::Abort(reason)) => panic!("proptest gave up: {}", reason),
+        }
+    }
+
+    /// Checks sequences of arbitrary actions, shrinking a failing one by
+    /// taking the first smaller sequence quickcheck offers that still fails,
+    /// until none does.
+    pub fn quickcheck(&self) -> Result<(), Broken<T::Action>> where T::Action: Arbitrary {
+        let mut gen = Gen::new(self.max_actions + 1);
+        for _ in 0..self.cases {
+            let mut actions : Vec<T::Action> = Arbitrary::arbitrary(&mut gen);
+            if let Err(mut broken) = self.check(&actions) {
+                'shrinking: loop {
+                    for smaller in actions.shrink() {
+                        if let Err(smaller_broken) = self.check(&smaller) {
+                            actions = smaller;
+                            broken = smaller_broken;
+                            continue 'shrinking;
+                        }
+                    }
+                    return Err(broken);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
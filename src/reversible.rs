@@ -0,0 +1,237 @@
+use std::fmt::{Debug, Display};
+use std::ops::Deref;
+
+use crate::{Reducer, UndoAction};
+
+/// A reducer whose actions can be undone by other actions: reducing an action
+/// returns its inverse. For large states this makes undo much cheaper than
+/// the snapshots `Undoable` keeps, since only actions are stored.
+///
+/// A `reduce` that fails must leave the state untouched, because there's no
+/// snapshot to go back to.
+///
+/// ```
+/// use redux::Reversible;
+///
+/// #[derive(Clone, Default)]
+/// struct Document {
+///     text: String,
+/// }
+///
+/// #[derive(Clone, Debug)]
+/// enum Edit {
+///     Append(String),
+///     Truncate(usize),
+/// }
+///
+/// impl Reversible for Document {
+///     type Action = Edit;
+///     type Error = String;
+///
+///     fn reduce(&mut self, edit: Edit) -> Result<Edit, String> {
+///         match edit {
+///             Edit::Append(text) => {
+///                 self.text.push_str(&text);
+///                 Ok(Edit::Truncate(text.len()))
+///             },
+///             Edit::Truncate(len) => {
+///                 let at = self.text.len().checked_sub(len).ok_or("too short")?;
+///                 Ok(Edit::Append(self.text.split_off(at)))
+///             },
+///         }
+///     }
+/// }
+/// ```
+pub trait Reversible: Clone + Default {
+    /// The type of action that this reducer can accept, probably an enum
+    type Action: Clone;
+
+    /// The type of error this reducer can return in the `Result`
+    type Error: Display;
+
+    /// Reduce a given state based upon an action, returning the action that
+    /// puts the state back the way it was.
+    fn reduce(&mut self, action: Self::Action) -> Result<Self::Action, Self::Error>;
+}
+
+/// Undo and redo for a `Reversible` reducer, keeping a log of inverse actions
+/// instead of copies of the state. It takes the same `UndoAction`s as
+/// `Undoable` and works with a `Store` the same way:
+///
+/// ```
+/// # use redux::{Reversible, Store, UndoAction, UndoLog};
+/// # #[derive(Clone, Default)]
+/// # struct Counter { count: isize }
+/// # impl Reversible for Counter {
+/// #     type Action = isize;
+/// #     type Error = String;
+/// #     fn reduce(&mut self, n: isize) -> Result<isize, String> {
+/// #         self.count += n;
+/// #         Ok(-n)
+/// #     }
+/// # }
+/// let store : Store<UndoLog<Counter>> = Store::new(vec![]);
+/// let _ = store.dispatch(UndoAction::Action(5));
+/// let _ = store.dispatch(UndoAction::Undo);
+/// assert_eq!(0, store.get_state().count);
+/// ```
+pub struct UndoLog<R: Reversible> {
+    present: R,
+    undo: Vec<R::Action>,
+    redo: Vec<R::Action>,
+}
+
+impl<R: Reversible> UndoLog<R> {
+    /// The current state.
+    pub fn present(&self) -> &R {
+        &self.present
+    }
+
+    /// How many steps `Undo` can go back.
+    pub fn past_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// How many steps `Redo` can go forward.
+    pub fn future_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // applies the top of `from`, pushing its inverse on to `to`
+    fn step(present: &mut R, from: &mut Vec<R::Action>, to: &mut Vec<R::Action>) -> Result<(), R::Error> {
+        if let Some(action) = from.pop() {
+            match present.reduce(action.clone()) {
+                Ok(inverse) => to.push(inverse),
+                Err(e) => {
+                    from.push(action);
+                    return Err(e);
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    // travels on a copy, so a step that fails part way leaves the log as it
+    // was
+    fn jump(&mut self, steps: isize) -> Result<(), R::Error> {
+        let available = if steps < 0 { self.undo.len() } else { self.redo.len() };
+        if steps.unsigned_abs() > available {
+            return Ok(());
+        }
+        let mut next = self.clone();
+        for _ in 0..steps.unsigned_abs() {
+            if steps < 0 {
+                UndoLog::step(&mut next.present, &mut next.undo, &mut next.redo)?;
+            } else {
+                UndoLog::step(&mut next.present, &mut next.redo, &mut next.undo)?;
+            }
+        }
+        *self = next;
+
+        Ok(())
+    }
+}
+
+impl<R: Reversible> Deref for UndoLog<R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        &self.present
+    }
+}
+
+impl<R: Reversible> Clone for UndoLog<R> {
+    fn clone(&self) -> Self {
+        UndoLog {
+            present: self.present.clone(),
+            undo: self.undo.clone(),
+            redo: self.redo.clone(),
+        }
+    }
+}
+
+impl<R: Reversible> Default for UndoLog<R> {
+    fn default() -> Self {
+        UndoLog {
+            present: R::default(),
+            undo: vec![],
+            redo: vec![],
+        }
+    }
+}
+
+impl<R: Reversible> Reducer for UndoLog<R> {
+    type Action = UndoAction<R::Action>;
+    type Error = R::Error;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            UndoAction::Undo => UndoLog::step(&mut self.present, &mut self.undo, &mut self.redo)?,
+            UndoAction::Redo => UndoLog::step(&mut self.present, &mut self.redo, &mut self.undo)?,
+            UndoAction::Jump(steps) => self.jump(steps)?,
+            UndoAction::ClearHistory => {
+                self.undo.clear();
+                self.redo.clear();
+            },
+            UndoAction::Action(action) => {
+                let inverse = self.present.reduce(action)?;
+                self.undo.push(inverse);
+                self.redo.clear();
+            },
+        }
+
+        Ok(self.clone())
+    }
+}
+
+/// Wraps a `Reversible` reducer and, in debug builds, checks every inverse it
+/// returns: the inverse is applied to a copy of the new state, and the result
+/// has to equal the state from before the action, or it panics. Release
+/// builds skip the check entirely.
+///
+/// Use it as `UndoLog<Verified<MyState>>` while developing.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Verified<R>(pub R);
+
+impl<R> Deref for Verified<R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        &self.0
+    }
+}
+
+impl<R> Reversible for Verified<R>
+    where R: Reversible + PartialEq + Debug,
+          R::Action: Debug
+{
+    type Action = R::Action;
+    type Error = R::Error;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self::Action, Self::Error> {
+        if !cfg!(debug_assertions) {
+            return self.0.reduce(action);
+        }
+
+        let before = self.0.clone();
+        let inverse = self.0.reduce(action.clone())?;
+        let mut restored = self.0.clone();
+        if let Err(e) = restored.reduce(inverse.clone()) {
+            panic!("the inverse {:?} of {:?} failed: {}", inverse, action, e);
+        }
+        assert!(restored == before,
+                "applying {:?} and then its inverse {:?} should restore {:?}, but gave {:?}",
+                action, inverse, before, restored);
+
+        Ok(inverse)
+    }
+}
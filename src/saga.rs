@@ -0,0 +1,563 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{DispatchFunc, Middleware, MiddlewareApi, Reducer, Store};
+
+/// What a saga's blocking calls return. The only way they fail is the saga
+/// being cancelled, or the store it runs against being dropped, so workflows
+/// can just `?` their way out.
+pub type SagaResult<R = ()> = Result<R, Cancelled>;
+
+/// Returned from a saga's blocking calls once it has been cancelled, or once
+/// its store is gone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+/// Decides whether an action is the one a saga is waiting for.
+pub type Pattern<'a, A> = dyn Fn(&A) -> bool + 'a;
+
+/// The outcome of `Saga::race`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Race<A> {
+    /// The pattern at this index matched this action first.
+    Took(usize, A),
+    /// No pattern matched before the timeout.
+    TimedOut,
+}
+
+enum Message<A> {
+    Action(A),
+    Cancel,
+    Timeout,
+}
+
+// workflows only get a weak handle on the store, so a store that's dropped
+// while they wait isn't kept alive by them
+type Api<T> = Weak<dyn MiddlewareApi<T> + Send + Sync>;
+
+// Counts workflows that are running plus messages they haven't received yet.
+// When it hits zero every workflow is blocked waiting for an action, which is
+// what lets tests step them deterministically.
+struct Activity {
+    busy: Mutex<usize>,
+    idle: Condvar,
+}
+
+impl Activity {
+    fn start(&self) {
+        *self.busy.lock().unwrap() += 1;
+    }
+
+    fn stop(&self) {
+        let mut busy = self.busy.lock().unwrap();
+        *busy -= 1;
+        if *busy == 0 {
+            self.idle.notify_all();
+        }
+    }
+
+    fn wait_until_idle(&self) {
+        let mut busy = self.busy.lock().unwrap();
+        while *busy > 0 {
+            busy = self.idle.wait(busy).unwrap();
+        }
+    }
+}
+
+struct Registry<T: Reducer> {
+    tasks: Mutex<Vec<SagaTask<T>>>,
+    activity: Arc<Activity>,
+    // timeouts only fire through `SagaTester::time_out`
+    virtual_time: bool,
+}
+
+impl<T: Reducer> Registry<T> {
+    fn new(virtual_time: bool) -> Registry<T> {
+        Registry {
+            tasks: Mutex::new(vec![]),
+            activity: Arc::new(Activity { busy: Mutex::new(0), idle: Condvar::new() }),
+            virtual_time,
+        }
+    }
+
+    fn broadcast(&self, message: impl Fn() -> Message<T::Action>) {
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.retain(|task| task.deliver(message()));
+    }
+
+    fn forget(&self, task: &SagaTask<T>) {
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.retain(|t| !Arc::ptr_eq(&t.state, &task.state));
+    }
+}
+
+impl<T: Reducer> Drop for Registry<T> {
+    // the store and every handle on the middleware are gone, so nothing can
+    // feed the workflows any more; cancel them instead of leaving their
+    // threads blocked
+    fn drop(&mut self) {
+        if let Ok(tasks) = self.tasks.get_mut() {
+            for task in tasks.drain(..) {
+                task.cancel();
+            }
+        }
+    }
+}
+
+fn spawn<T, F>(registry: &Arc<Registry<T>>, api: Api<T>, workflow: F) -> SagaTask<T>
+    where T: 'static + Reducer,
+          T::Action: Send,
+          F: 'static + Send + FnOnce(&Saga<T>) -> SagaResult
+{
+    let (sender, receiver) = channel();
+    let task = SagaTask::new(sender, false, &registry.activity);
+    registry.activity.start();
+    registry.tasks.lock().unwrap().push(task.clone());
+
+    // the workflow only holds on to the registry weakly too, or it could
+    // never be dropped
+    let saga = Saga {
+        task: task.clone(),
+        receiver,
+        api,
+        registry: Arc::downgrade(registry),
+        virtual_time: registry.virtual_time,
+    };
+    thread::spawn(move || {
+        let _ = workflow(&saga);
+    });
+
+    task
+}
+
+struct TaskState<T: Reducer> {
+    sender: Sender<Message<T::Action>>,
+    cancelled: AtomicBool,
+    finished: Mutex<bool>,
+    done: Condvar,
+    children: Mutex<Vec<SagaTask<T>>>,
+    activity: Arc<Activity>,
+}
+
+/// A handle on a running workflow, returned by `SagaMiddleware::run` and
+/// `Saga::fork`.
+pub struct SagaTask<T: Reducer> {
+    state: Arc<TaskState<T>>,
+}
+
+impl<T: Reducer> Clone for SagaTask<T> {
+    fn clone(&self) -> Self {
+        SagaTask {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<T: Reducer> SagaTask<T> {
+    fn new(sender: Sender<Message<T::Action>>, finished: bool, activity: &Arc<Activity>) -> SagaTask<T> {
+        SagaTask {
+            state: Arc::new(TaskState {
+                sender,
+                cancelled: AtomicBool::new(finished),
+                finished: Mutex::new(finished),
+                done: Condvar::new(),
+                children: Mutex::new(vec![]),
+                activity: activity.clone(),
+            }),
+        }
+    }
+
+    // a task that's already cancelled and finished
+    fn never_run(activity: &Arc<Activity>) -> SagaTask<T> {
+        SagaTask::new(channel().0, true, activity)
+    }
+
+    /// Cancels the workflow and every workflow it forked. The workflow's
+    /// current (or next) blocking call returns `Err(Cancelled)`.
+    pub fn cancel(&self) {
+        if !self.state.cancelled.swap(true, Ordering::SeqCst) {
+            self.deliver(Message::Cancel);
+            for child in self.state.children.lock().unwrap().iter() {
+                child.cancel();
+            }
+        }
+    }
+
+    /// Returns whether or not `cancel` has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Returns whether or not the workflow is still running.
+    pub fn is_running(&self) -> bool {
+        !*self.state.finished.lock().unwrap()
+    }
+
+    /// Blocks until the workflow has returned.
+    pub fn join(&self) {
+        let mut finished = self.state.finished.lock().unwrap();
+        while !*finished {
+            finished = self.state.done.wait(finished).unwrap();
+        }
+    }
+
+    // returns false once the workflow has finished and can be forgotten
+    fn deliver(&self, message: Message<T::Action>) -> bool {
+        let finished = self.state.finished.lock().unwrap();
+        if *finished {
+            return false;
+        }
+        self.state.activity.start();
+        let _ = self.state.sender.send(message);
+
+        true
+    }
+}
+
+/// The handle a workflow is run with. Its methods are the saga effects: they
+/// block the workflow's thread until the effect is done.
+///
+/// Each workflow has its own queue of actions, fed from the dispatch path, so
+/// unlike redux-saga an action dispatched while the workflow was busy is not
+/// missed; the next `take` will see it.
+pub struct Saga<T: Reducer> {
+    task: SagaTask<T>,
+    receiver: Receiver<Message<T::Action>>,
+    api: Api<T>,
+    registry: Weak<Registry<T>>,
+    virtual_time: bool,
+}
+
+impl<T: 'static + Reducer> Saga<T> where T::Action: Send {
+    /// Waits for the next action matching `pattern` and returns it.
+    pub fn take<P>(&self, pattern: P) -> SagaResult<T::Action>
+        where P: Fn(&T::Action) -> bool
+    {
+        match self.race(&[&pattern], None)? {
+            Race::Took(_, action) => Ok(action),
+            Race::TimedOut => unreachable!("a race without a timeout can't time out"),
+        }
+    }
+
+    /// Waits for the first action matching any of `patterns`, or for
+    /// `timeout` to pass.
+    pub fn race(&self, patterns: &[&Pattern<'_, T::Action>], timeout: Option<Duration>) -> SagaResult<Race<T::Action>> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if self.task.is_cancelled() {
+                return Err(Cancelled);
+            }
+
+            match self.receive(deadline) {
+                Some(Message::Action(action)) => {
+                    if let Some(index) = patterns.iter().position(|pattern| pattern(&action)) {
+                        return Ok(Race::Took(index, action));
+                    }
+                },
+                Some(Message::Cancel) => return Err(Cancelled),
+                Some(Message::Timeout) => {
+                    if timeout.is_some() {
+                        return Ok(Race::TimedOut);
+                    }
+                },
+                None => return Ok(Race::TimedOut),
+            }
+        }
+    }
+
+    /// Dispatches an action to the store.
+    pub fn put(&self, action: T::Action) -> Result<T::Action, String> {
+        match self.api.upgrade() {
+            Some(api) => api.dispatch(action),
+            None => Err(String::from("Can't put, the store is gone.")),
+        }
+    }
+
+    /// Runs `selector` against the current state.
+    pub fn select<R, F>(&self, selector: F) -> SagaResult<R>
+        where F: FnOnce(&T) -> R
+    {
+        match self.api.upgrade() {
+            Some(api) => Ok(selector(&api.get_state())),
+            None => Err(Cancelled),
+        }
+    }
+
+    /// Starts another workflow alongside this one. Forked workflows are
+    /// cancelled along with their parent.
+    pub fn fork<F>(&self, workflow: F) -> SagaTask<T>
+        where F: 'static + Send + FnOnce(&Saga<T>) -> SagaResult
+    {
+        // without a registry the middleware is gone and this workflow has
+        // been cancelled, so the child never starts
+        let registry = match self.registry.upgrade() {
+            Some(registry) => registry,
+            None => return SagaTask::never_run(&self.task.state.activity),
+        };
+        let child = spawn(&registry, self.api.clone(), workflow);
+        let mut children = self.task.state.children.lock().unwrap();
+        children.retain(|child| child.is_running());
+        children.push(child.clone());
+
+        child
+    }
+
+    /// Cancels a workflow, usually one forked earlier.
+    pub fn cancel(&self, task: &SagaTask<T>) {
+        task.cancel();
+    }
+
+    /// Returns whether or not this workflow has been cancelled, for workflows
+    /// that do long stretches of work between blocking calls.
+    pub fn is_cancelled(&self) -> bool {
+        self.task.is_cancelled()
+    }
+
+    fn receive(&self, deadline: Option<Instant>) -> Option<Message<T::Action>> {
+        let activity = &self.task.state.activity;
+        activity.stop();
+        let message = match deadline {
+            Some(deadline) if !self.virtual_time => {
+                self.receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())).ok()
+            },
+            // the task holds on to its own sender, so this never disconnects;
+            // the registry cancels it when the middleware is dropped instead
+            _ => self.receiver.recv().ok(),
+        };
+        activity.start();
+        if message.is_some() {
+            activity.stop();
+        }
+
+        message
+    }
+}
+
+impl<T: Reducer> Drop for Saga<T> {
+    // runs when the workflow returns or panics
+    fn drop(&mut self) {
+        if let Some(registry) = self.registry.upgrade() {
+            registry.forget(&self.task);
+        }
+        let state = &self.task.state;
+        {
+            let mut finished = state.finished.lock().unwrap();
+            *finished = true;
+            state.done.notify_all();
+        }
+        while self.receiver.try_recv().is_ok() {
+            state.activity.stop();
+        }
+        state.activity.stop();
+    }
+}
+
+/// Runs saga workflows against a `Store`. Every action that makes it through
+/// the reducer is handed to every running workflow.
+///
+/// ## Example
+///
+/// ```
+/// # use redux::{Reducer, Store, SagaMiddleware};
+/// # use std::sync::Arc;
+/// #
+/// # #[derive(Clone, Default)]
+/// # struct Auth { user: Option<String>, profile: Option<String> }
+/// # impl Reducer for Auth {
+/// #     type Action = AuthAction;
+/// #     type Error = String;
+/// #
+/// #     fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+/// #         match action {
+/// #             AuthAction::Login(user) => self.user = Some(user),
+/// #             AuthAction::Profile(profile) => self.profile = Some(profile),
+/// #         }
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+/// #[derive(Clone)]
+/// enum AuthAction {
+///     Login(String),
+///     Profile(String),
+/// }
+///
+/// let sagas = SagaMiddleware::new();
+/// let store : Arc<Store<Auth>> = Arc::new(Store::new(vec![Box::new(sagas.clone())]));
+///
+/// sagas.run(&store, |saga| {
+///     saga.take(|action| matches!(action, AuthAction::Login(_)))?;
+///     let user = saga.select(|state: &Auth| state.user.clone())?.unwrap();
+///     let _ = saga.put(AuthAction::Profile(format!("{}'s profile", user)));
+///     Ok(())
+/// });
+///
+/// let _ = store.dispatch(AuthAction::Login(String::from("jared")));
+/// sagas.wait_until_idle();
+/// assert_eq!(Some(String::from("jared's profile")), store.get_state().profile);
+/// ```
+pub struct SagaMiddleware<T: Reducer> {
+    registry: Arc<Registry<T>>,
+}
+
+impl<T: Reducer> Clone for SagaMiddleware<T> {
+    fn clone(&self) -> Self {
+        SagaMiddleware {
+            registry: self.registry.clone(),
+        }
+    }
+}
+
+impl<T: Reducer> Default for SagaMiddleware<T> {
+    fn default() -> Self {
+        SagaMiddleware::new()
+    }
+}
+
+impl<T: Reducer> SagaMiddleware<T> {
+    pub fn new() -> SagaMiddleware<T> {
+        SagaMiddleware {
+            registry: Arc::new(Registry::new(false)),
+        }
+    }
+
+    /// Blocks until every workflow is waiting for an action (or a timeout),
+    /// which is handy in tests.
+    pub fn wait_until_idle(&self) {
+        self.registry.activity.wait_until_idle();
+    }
+}
+
+impl<T: 'static + Reducer + Send> SagaMiddleware<T> where T::Action: Send {
+    /// Starts a workflow on its own thread. `store` should be the store this
+    /// middleware was given to; it's what `put` and `select` talk to.
+    ///
+    /// The workflow doesn't keep the store alive. Once the store and every
+    /// clone of this middleware are dropped, it's cancelled.
+    pub fn run<F>(&self, store: &Arc<Store<T>>, workflow: F) -> SagaTask<T>
+        where F: 'static + Send + FnOnce(&Saga<T>) -> SagaResult
+    {
+        let api : Arc<dyn MiddlewareApi<T> + Send + Sync> = store.clone();
+        spawn(&self.registry, Arc::downgrade(&api), workflow)
+    }
+}
+
+impl<T: 'static + Reducer> Middleware<T> for SagaMiddleware<T> where T::Action: Send {
+    fn dispatch(&self, store: &Store<T>, action: T::Action, next: &DispatchFunc<T>) -> Result<T, String> {
+        let result = next(store, action.clone());
+        if result.is_ok() {
+            self.registry.broadcast(|| Message::Action(action.clone()));
+        }
+
+        result
+    }
+}
+
+struct ScriptedApi<T: Reducer> {
+    state: Mutex<T>,
+    puts: Mutex<Vec<T::Action>>,
+}
+
+impl<T: Reducer> MiddlewareApi<T> for ScriptedApi<T> {
+    fn dispatch(&self, action: T::Action) -> Result<T::Action, String> {
+        self.puts.lock().unwrap().push(action.clone());
+        Ok(action)
+    }
+
+    fn get_state(&self) -> T {
+        self.state.lock().unwrap().clone()
+    }
+}
+
+/// Steps workflows through a script of actions without a `Store`. Nothing is
+/// reduced: `put`s are recorded, `select` reads a state you set, and timeouts
+/// only fire when you call `time_out`. Every method waits for the workflows to
+/// block again before returning, so tests are deterministic.
+///
+/// ```
+/// # use redux::{Reducer, SagaTester};
+/// #
+/// # #[derive(Clone, Default)]
+/// # struct Counter {}
+/// # impl Reducer for Counter {
+/// #     type Action = usize;
+/// #     type Error = String;
+/// #
+/// #     fn reduce(&mut self, _: Self::Action) -> Result<Self, Self::Error> {
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+/// let tester : SagaTester<Counter> = SagaTester::new(Counter::default());
+/// tester.run(|saga| {
+///     loop {
+///         let n = saga.take(|n| n % 2 == 0)?;
+///         let _ = saga.put(n + 1);
+///     }
+/// });
+///
+/// assert_eq!(Vec::<usize>::new(), tester.step(1));
+/// assert_eq!(vec![3], tester.step(2));
+/// ```
+pub struct SagaTester<T: Reducer> {
+    registry: Arc<Registry<T>>,
+    api: Arc<ScriptedApi<T>>,
+}
+
+impl<T> SagaTester<T>
+    where T: 'static + Reducer + Send,
+          T::Action: Send
+{
+    /// Builds a tester whose `select`s see `state`.
+    pub fn new(state: T) -> SagaTester<T> {
+        SagaTester {
+            registry: Arc::new(Registry::new(true)),
+            api: Arc::new(ScriptedApi {
+                state: Mutex::new(state),
+                puts: Mutex::new(vec![]),
+            }),
+        }
+    }
+
+    /// Starts a workflow and waits for it to block.
+    pub fn run<F>(&self, workflow: F) -> SagaTask<T>
+        where F: 'static + Send + FnOnce(&Saga<T>) -> SagaResult
+    {
+        let api : Arc<dyn MiddlewareApi<T> + Send + Sync> = self.api.clone();
+        let task = spawn(&self.registry, Arc::downgrade(&api), workflow);
+        self.registry.activity.wait_until_idle();
+
+        task
+    }
+
+    /// Feeds `action` to every workflow, as if it had been dispatched, and
+    /// returns the actions they `put` in response.
+    pub fn step(&self, action: T::Action) -> Vec<T::Action> {
+        self.registry.broadcast(|| Message::Action(action.clone()));
+        self.settle()
+    }
+
+    /// Times out every pending `race`, and returns the actions put in
+    /// response.
+    pub fn time_out(&self) -> Vec<T::Action> {
+        self.registry.broadcast(|| Message::Timeout);
+        self.settle()
+    }
+
+    /// Changes the state `select` sees from now on.
+    pub fn set_state(&self, state: T) {
+        *self.api.state.lock().unwrap() = state;
+    }
+
+    /// Returns the actions put since the last step, like the ones put by a
+    /// workflow before its first `take`.
+    pub fn puts(&self) -> Vec<T::Action> {
+        std::mem::take(&mut *self.api.puts.lock().unwrap())
+    }
+
+    fn settle(&self) -> Vec<T::Action> {
+        self.registry.activity.wait_until_idle();
+        self.puts()
+    }
+}
@@ -0,0 +1,306 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::event_log::{encode, scan};
+use crate::migrations::{stamp, unstamp};
+use crate::{ActionLog, DispatchFunc, LogError, Middleware, Migrations, Reducer, Store, SyncPolicy};
+
+/// How often a `SnapshotMiddleware` takes snapshots and how many it keeps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SnapshotOptions {
+    pub sync: SyncPolicy,
+    /// Take a snapshot after this many actions.
+    pub every_actions: Option<usize>,
+    /// Take a snapshot with the first action this long after the last one.
+    pub every: Option<Duration>,
+    /// How many snapshots to keep, newest first, so there's something to
+    /// fall back to if the newest is corrupt. At least one is always kept.
+    pub keep: usize,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        SnapshotOptions {
+            sync: SyncPolicy::EveryAction,
+            every_actions: Some(1000),
+            every: None,
+            keep: 2,
+        }
+    }
+}
+
+fn segment_path(dir: &Path, sequence: u64) -> PathBuf {
+    dir.join(format!("{:020}.log", sequence))
+}
+
+fn snapshot_path(dir: &Path, sequence: u64) -> PathBuf {
+    dir.join(format!("{:020}.snapshot", sequence))
+}
+
+// the sequence numbers of the files in `dir` with `extension`, oldest first
+fn list(dir: &Path, extension: &str) -> io::Result<Vec<u64>> {
+    let mut sequences = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|found| found == extension) {
+            if let Some(sequence) = path.file_stem().and_then(|stem| stem.to_str()?.parse().ok()) {
+                sequences.push(sequence);
+            }
+        }
+    }
+    sequences.sort_unstable();
+
+    Ok(sequences)
+}
+
+// written to a temporary file first so a crash never leaves half a snapshot
+fn write_snapshot<T: Serialize>(dir: &Path, sequence: u64, version: u32, state: &T) -> Result<(), LogError> {
+    let payload = serde_json::to_value(state)
+        .and_then(|state| serde_json::to_vec(&stamp(version, "state", state)))
+        .map_err(|e| LogError::Serde { offset: 0, message: e.to_string() })?;
+    let temporary = dir.join(format!("{:020}.snapshot.tmp", sequence));
+    let mut file = File::create(&temporary)?;
+    file.write_all(&encode(&payload))?;
+    file.sync_all()?;
+    fs::rename(temporary, snapshot_path(dir, sequence))?;
+
+    Ok(())
+}
+
+// `None` if the snapshot doesn't read back, and an older one should be used
+fn read_snapshot<T: DeserializeOwned>(dir: &Path, sequence: u64, migrations: &Migrations) -> Option<Result<T, LogError>> {
+    let bytes = fs::read(snapshot_path(dir, sequence)).ok()?;
+    let saved : Value = match scan(&bytes) {
+        Ok((ref records, len)) if records.len() == 1 && len == bytes.len() => serde_json::from_slice(records[0].1).ok()?,
+        _ => return None,
+    };
+    let (version, state) = unstamp(saved, "state");
+    match migrations.migrate(version, state) {
+        Ok(state) => serde_json::from_value(state).ok().map(Ok),
+        Err(e) => Some(Err(LogError::Migration { sequence, message: e.to_string() })),
+    }
+}
+
+// drops the snapshots past `keep`, and the segments only they needed
+fn compact(dir: &Path, keep: usize) -> io::Result<()> {
+    let snapshots = list(dir, "snapshot")?;
+    if snapshots.len() <= keep {
+        return Ok(());
+    }
+
+    let oldest = snapshots[snapshots.len() - keep];
+    for sequence in snapshots.into_iter().filter(|sequence| *sequence < oldest) {
+        fs::remove_file(snapshot_path(dir, sequence))?;
+    }
+    for start in list(dir, "log")?.into_iter().filter(|start| *start < oldest) {
+        fs::remove_file(segment_path(dir, start))?;
+    }
+
+    Ok(())
+}
+
+struct Snapshotter<T: Reducer> {
+    dir: PathBuf,
+    options: SnapshotOptions,
+    // the schema versions snapshots and actions are saved at
+    state_version: u32,
+    action_version: u32,
+    // the segment actions are appended to
+    log: ActionLog<T::Action>,
+    // how many actions there have been, ever
+    sequence: u64,
+    since_snapshot: usize,
+    last_snapshot: Instant,
+    compaction: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl<T> Snapshotter<T>
+    where T: Reducer + Serialize,
+          T::Action: Serialize + DeserializeOwned
+{
+    fn record(&mut self, action: &T::Action, state: &T) -> Result<(), String> {
+        self.log.append(action).map_err(|e| format!("The action was reduced but not logged: {}", e))?;
+        self.sequence += 1;
+        self.since_snapshot += 1;
+
+        let due = self.options.every_actions.is_some_and(|every| self.since_snapshot >= every)
+            || self.options.every.is_some_and(|every| self.last_snapshot.elapsed() >= every);
+        if due {
+            self.snapshot(state).map_err(|e| format!("The action was logged but the snapshot failed: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    // each snapshot starts a new segment, so the ones before it can go once
+    // it's old enough
+    fn snapshot(&mut self, state: &T) -> Result<(), LogError> {
+        write_snapshot(&self.dir, self.sequence, self.state_version, state)?;
+        self.log.sync()?;
+        self.log = ActionLog::open(segment_path(&self.dir, self.sequence), self.options.sync)?.versioned(self.action_version);
+        self.since_snapshot = 0;
+        self.last_snapshot = Instant::now();
+
+        if let Some(previous) = self.compaction.take() {
+            let _ = previous.join();
+        }
+        let dir = self.dir.clone();
+        let keep = self.options.keep.max(1);
+        self.compaction = Some(thread::spawn(move || compact(&dir, keep)));
+
+        Ok(())
+    }
+}
+
+/// Event-sourced persistence that doesn't replay everything on startup: the
+/// log is split into segments, and every so often a snapshot of the state is
+/// saved and a new segment started. Loading starts from the newest snapshot
+/// that reads back correctly and replays only the segments after it, and
+/// once a snapshot is old enough the segments before it are deleted in the
+/// background.
+///
+/// Everything lives in one directory. `Store::from_snapshots` opens one and
+/// puts the middleware in place; `open` is there when you also want the
+/// middleware itself. Like `PersistenceMiddleware` it's locked from before
+/// the action is reduced until it's logged, so it should usually go first.
+pub struct SnapshotMiddleware<T: Reducer> {
+    inner: Arc<Mutex<Snapshotter<T>>>,
+}
+
+impl<T: Reducer> Clone for SnapshotMiddleware<T> {
+    fn clone(&self) -> Self {
+        SnapshotMiddleware { inner: self.inner.clone() }
+    }
+}
+
+impl<T> SnapshotMiddleware<T>
+    where T: Reducer + Serialize + DeserializeOwned,
+          T::Action: Serialize + DeserializeOwned
+{
+    /// Loads the state saved in `dir`, creating it if it's missing, and
+    /// returns it with the middleware that carries on saving it.
+    pub fn open<P: AsRef<Path>>(dir: P, options: SnapshotOptions) -> Result<(T, SnapshotMiddleware<T>), LogError> {
+        SnapshotMiddleware::open_versioned(dir, options, &Migrations::default(), &Migrations::default())
+    }
+
+    /// Like `open`, for state and actions whose serialized forms have
+    /// changed: snapshots are saved at the current version of `migrations`
+    /// and actions at the current version of `upcasters`, and older ones are
+    /// brought up to date as they're loaded.
+    pub fn open_versioned<P: AsRef<Path>>(dir: P, options: SnapshotOptions, upcasters: &Migrations, migrations: &Migrations) -> Result<(T, SnapshotMiddleware<T>), LogError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut snapshots = list(&dir, "snapshot")?;
+        let (mut state, base) = loop {
+            match snapshots.pop() {
+                Some(sequence) => {
+                    if let Some(state) = read_snapshot(&dir, sequence, migrations) {
+                        break (state?, sequence);
+                    }
+                },
+                None => break (T::default(), 0),
+            }
+        };
+
+        let mut sequence = base;
+        let mut current = base;
+        for start in list(&dir, "log")?.into_iter().filter(|start| *start >= base) {
+            if start != sequence {
+                return Err(LogError::Missing { sequence });
+            }
+            for (offset, action) in ActionLog::<T::Action>::read_upcasting(segment_path(&dir, start), upcasters)? {
+                state.reduce(action).map_err(|e| LogError::Replay { offset, message: e.to_string() })?;
+                sequence += 1;
+            }
+            current = start;
+        }
+
+        let log = ActionLog::open(segment_path(&dir, current), options.sync)?.versioned(upcasters.current());
+        let middleware = SnapshotMiddleware {
+            inner: Arc::new(Mutex::new(Snapshotter {
+                dir,
+                options,
+                state_version: migrations.current(),
+                action_version: upcasters.current(),
+                log,
+                sequence,
+                since_snapshot: (sequence - base) as usize,
+                last_snapshot: Instant::now(),
+                compaction: None,
+            })),
+        };
+
+        Ok((state, middleware))
+    }
+
+    /// Blocks until the latest compaction is done, which is mostly useful in
+    /// tests.
+    pub fn wait_for_compaction(&self) -> io::Result<()> {
+        let compaction = self.inner.lock().unwrap().compaction.take();
+        match compaction {
+            Some(compaction) => compaction.join().unwrap_or(Ok(())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T> Middleware<T> for SnapshotMiddleware<T>
+    where T: Reducer + Serialize,
+          T::Action: Serialize + DeserializeOwned
+{
+    fn dispatch(&self, store: &Store<T>, action: T::Action, next: &DispatchFunc<T>) -> Result<T, String> {
+        let mut inner = self.inner.lock().unwrap();
+        let state = next(store, action.clone())?;
+        inner.record(&action, &state)?;
+
+        Ok(state)
+    }
+}
+
+impl<T> Store<T>
+    where T: 'static + Reducer + Serialize + DeserializeOwned,
+          T::Action: Serialize + DeserializeOwned
+{
+    /// Loads a store's state from the snapshots and log segments in `dir`,
+    /// with a `SnapshotMiddleware` in front of `middlewares` to keep saving
+    /// it.
+    ///
+    /// ```no_run
+    /// # use redux::{Reducer, SnapshotOptions, Store};
+    /// # #[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+    /// # struct Counter { count: usize }
+    /// # impl Reducer for Counter {
+    /// #     type Action = usize;
+    /// #     type Error = String;
+    /// #     fn reduce(&mut self, n: usize) -> Result<Self, String> {
+    /// #         self.count += n;
+    /// #         Ok(self.clone())
+    /// #     }
+    /// # }
+    /// let options = SnapshotOptions { every_actions: Some(10_000), ..SnapshotOptions::default() };
+    /// let store : Store<Counter> = Store::from_snapshots("data/counter", options, vec![]).unwrap();
+    /// ```
+    pub fn from_snapshots<P: AsRef<Path>>(dir: P, options: SnapshotOptions, middlewares: Vec<Box<dyn Middleware<T>>>) -> Result<Store<T>, LogError> {
+        Store::from_versioned_snapshots(dir, options, &Migrations::default(), &Migrations::default(), middlewares)
+    }
+
+    /// Like `from_snapshots`, with `upcasters` for the logged actions and
+    /// `migrations` for the snapshots, as for `Store::from_versioned_log` and
+    /// `Persistor::with_migrations`. A snapshot that can't be migrated is an
+    /// error rather than a reason to fall back to an older one.
+    pub fn from_versioned_snapshots<P: AsRef<Path>>(dir: P, options: SnapshotOptions, upcasters: &Migrations, migrations: &Migrations, mut middlewares: Vec<Box<dyn Middleware<T>>>) -> Result<Store<T>, LogError> {
+        let (state, middleware) = SnapshotMiddleware::open_versioned(dir, options, upcasters, migrations)?;
+        middlewares.insert(0, Box::new(middleware));
+
+        Ok(Store::with_state(state, middlewares))
+    }
+}
@@ -0,0 +1,60 @@
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::stream::Stream;
+
+/// A `Stream` of values published by a `Store`, returned from `Store::changes`
+/// and `Store::actions`.
+///
+/// The stream never ends on its own; it lives as long as you hold on to it.
+/// Dropping it unregisters it from the store on the next dispatch.
+pub struct StoreStream<I> {
+    receiver: UnboundedReceiver<I>,
+}
+
+impl<I> Stream for StoreStream<I> {
+    type Item = I;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<I>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+/// Fans a value out to every open `StoreStream`, forgetting the ones that have
+/// been dropped.
+pub(crate) struct Broadcast<I: Clone> {
+    senders: Mutex<Vec<UnboundedSender<I>>>,
+}
+
+impl<I: Clone> Broadcast<I> {
+    pub(crate) fn new() -> Broadcast<I> {
+        Broadcast {
+            senders: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn listen(&self) -> StoreStream<I> {
+        let (sender, receiver) = unbounded();
+        self.senders.lock().unwrap().push(sender);
+        StoreStream { receiver }
+    }
+
+    pub(crate) fn send(&self, item: &I) {
+        let mut senders = self.senders.lock().unwrap();
+        senders.retain(|sender| sender.unbounded_send(item.clone()).is_ok());
+    }
+}
+
+#[test]
+fn dropped_streams_are_forgotten() {
+    let broadcast: Broadcast<usize> = Broadcast::new();
+    let first = broadcast.listen();
+    let _second = broadcast.listen();
+    assert_eq!(2, broadcast.senders.lock().unwrap().len());
+
+    drop(first);
+    broadcast.send(&1);
+    assert_eq!(1, broadcast.senders.lock().unwrap().len());
+}
@@ -0,0 +1,546 @@
+//! Helpers for testing middleware, thunks and subscribers without a real
+//! reducer behind them, and for testing reducers themselves.
+
+use std::fmt::{self, Debug, Write};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::{DispatchFunc, Middleware, MiddlewareApi, Reducer, Store};
+#[cfg(feature = "fixtures")]
+pub use crate::fixtures::{run_fixture, FixtureFailure, Fixtures};
+#[cfg(feature = "snapshot-testing")]
+pub use crate::golden::Golden;
+#[cfg(feature = "property-testing")]
+pub use crate::properties::{Broken, Invariants};
+
+struct Recording<T: Reducer> {
+    actions: Mutex<Vec<T::Action>>,
+    reduce: AtomicBool,
+}
+
+// the innermost middleware, so it sees exactly what would reach the reducer
+struct Recorder<T: Reducer> {
+    recording: Arc<Recording<T>>,
+}
+
+impl<T: 'static + Reducer> Middleware<T> for Recorder<T> where T::Action: Send {
+    fn dispatch(&self, store: &Store<T>, action: T::Action, next: &DispatchFunc<T>) -> Result<T, String> {
+        self.recording.actions.lock().unwrap().push(action.clone());
+        if self.recording.reduce.load(Ordering::SeqCst) {
+            next(store, action)
+        } else {
+            Ok(store.get_state())
+        }
+    }
+}
+
+/// A store for tests, like redux-mock-store. It records every action that
+/// makes it through its middleware, and by default never reduces them, so
+/// `get_state` returns whatever state was scripted with `new` or `set_state`.
+///
+/// It derefs to the `Store` underneath, which is what a `Middleware` is
+/// handed, so a middleware can also be tested on its own by calling its
+/// `dispatch` with the mock and `next()`.
+///
+/// ```
+/// # #[macro_use] extern crate redux;
+/// # use redux::{MiddlewareApi, Reducer, Thunk, ThunkAction, ThunkMiddleware};
+/// # use redux::testing::MockStore;
+/// #[derive(Clone, Debug)]
+/// enum CounterAction {
+///     Add(usize),
+///     Thunk(Thunk<Counter>),
+/// }
+/// # impl ThunkAction<Counter> for CounterAction {
+/// #     fn into_thunk(self) -> Result<Thunk<Counter>, Self> {
+/// #         match self {
+/// #             CounterAction::Thunk(thunk) => Ok(thunk),
+/// #             action => Err(action),
+/// #         }
+/// #     }
+/// # }
+/// # #[derive(Clone, Default)]
+/// # struct Counter { count: usize }
+/// # impl Reducer for Counter {
+/// #     type Action = CounterAction;
+/// #     type Error = String;
+/// #     fn reduce(&mut self, _: CounterAction) -> Result<Self, String> {
+/// #         unreachable!()
+/// #     }
+/// # }
+/// # fn main() {
+/// let store = MockStore::new(Counter { count: 7 }, vec![Box::new(ThunkMiddleware::new())]);
+/// let round_up = Thunk::new(|api: &dyn MiddlewareApi<Counter>, _: &()| {
+///     api.dispatch(CounterAction::Add(10 - api.get_state().count % 10))?;
+///     Ok(())
+/// });
+/// let _ = store.dispatch(CounterAction::Thunk(round_up));
+///
+/// assert_dispatched!(store, CounterAction::Add(3));
+/// assert_not_dispatched!(store, CounterAction::Add(n) if *n > 3);
+/// assert_eq!(1, store.actions().len());
+/// # }
+/// ```
+pub struct MockStore<T: Reducer> {
+    store: Store<T>,
+    recording: Arc<Recording<T>>,
+}
+
+impl<T: 'static + Reducer> MockStore<T> where T::Action: Send {
+    /// A mock starting from `state`, with `middlewares` in front of the
+    /// recorder in the usual order.
+    pub fn new(state: T, mut middlewares: Vec<Box<dyn Middleware<T>>>) -> MockStore<T> {
+        let recording = Arc::new(Recording {
+            actions: Mutex::new(vec![]),
+            reduce: AtomicBool::new(false),
+        });
+        middlewares.insert(0, Box::new(Recorder { recording: recording.clone() }));
+
+        MockStore {
+            store: Store::with_state(state, middlewares),
+            recording,
+        }
+    }
+
+    /// Passes recorded actions on to the reducer after all, for when the
+    /// code under test depends on the state they lead to.
+    pub fn reducing(self) -> MockStore<T> {
+        self.recording.reduce.store(true, Ordering::SeqCst);
+        self
+    }
+
+    /// Replaces the state `get_state` returns.
+    pub fn set_state(&self, state: T) {
+        self.store.internal_store.lock().unwrap().data = state;
+    }
+
+    /// Every action recorded so far, oldest first.
+    pub fn actions(&self) -> Vec<T::Action> {
+        self.recording.actions.lock().unwrap().clone()
+    }
+
+    pub fn clear_actions(&self) {
+        self.recording.actions.lock().unwrap().clear();
+    }
+
+    /// A `next` for calling a middleware's `dispatch` directly. Actions
+    /// passed to it are recorded, and reduced only if the mock is
+    /// `reducing`.
+    pub fn next(&self) -> DispatchFunc<T> {
+        let recorder = Recorder { recording: self.recording.clone() };
+        let reduce : DispatchFunc<T> = Box::new(|store, action| store.internal_store.lock().unwrap().dispatch(action));
+        Box::new(move |store, action| recorder.dispatch(store, action, &reduce))
+    }
+}
+
+impl<T: Reducer> Deref for MockStore<T> {
+    type Target = Store<T>;
+
+    fn deref(&self) -> &Store<T> {
+        &self.store
+    }
+}
+
+impl<T: 'static + Reducer> MiddlewareApi<T> for MockStore<T> {
+    fn dispatch(&self, action: T::Action) -> Result<T::Action, String> {
+        self.store.dispatch(action)
+    }
+
+    fn get_state(&self) -> T {
+        self.store.get_state()
+    }
+}
+
+/// Starts a reducer test from `state`, given/when/then style:
+///
+/// ```
+/// # use redux::Reducer;
+/// # use redux::testing::given;
+/// # #[derive(Clone, Default, Debug, PartialEq)]
+/// # struct Counter { count: usize }
+/// # impl Reducer for Counter {
+/// #     type Action = usize;
+/// #     type Error = String;
+/// #     fn reduce(&mut self, n: usize) -> Result<Self, String> {
+/// #         if n == 0 {
+/// #             return Err(String::from("nothing to add"));
+/// #         }
+/// #         self.count += n;
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+/// given(Counter { count: 1 })
+///     .when(vec![2, 3])
+///     .then(Counter { count: 6 })
+///     .when(vec![0])
+///     .then_error("nothing to add");
+/// ```
+///
+/// When an expectation doesn't hold, the panic says after which action, and
+/// shows how the state differs from the expected one line by line.
+pub fn given<R: Reducer>(state: R) -> Scenario<R> {
+    Scenario {
+        state,
+        reduced: 0,
+        last: None,
+        pending: vec![],
+    }
+}
+
+/// A reducer test under way, started with `given`. Actions are numbered from
+/// 0 across the whole scenario.
+pub struct Scenario<R: Reducer> {
+    state: R,
+    // how many actions have run
+    reduced: usize,
+    // the last action that ran, for messages
+    last: Option<String>,
+    pending: Vec<R::Action>,
+}
+
+impl<R> Scenario<R> where R: Reducer + Debug + PartialEq, R::Action: Debug {
+    /// Queues actions to run at the next `then`.
+    pub fn when<I: IntoIterator<Item = R::Action>>(mut self, actions: I) -> Scenario<R> {
+        self.pending.extend(actions);
+        self
+    }
+
+    /// Runs the queued actions, panicking if any of them fails or the state
+    /// they leave isn't `expected`.
+    pub fn then(mut self, expected: R) -> Scenario<R> {
+        if let Err(e) = self.expect_state(&expected) {
+            panic!("{}", e);
+        }
+
+        self
+    }
+
+    /// Runs the queued actions until one fails, panicking unless it's with
+    /// `message`. Any actions after it are dropped, and the state is left as
+    /// the reducer left it.
+    pub fn then_error(mut self, message: &str) -> Scenario<R> {
+        if let Err(e) = self.expect_error(message) {
+            panic!("{}", e);
+        }
+
+        self
+    }
+
+    // `then`, describing what went wrong instead of panicking
+    pub(crate) fn expect_state(&mut self, expected: &R) -> Result<(), String> {
+        for action in std::mem::take(&mut self.pending) {
+            if let Err(e) = self.reduce(action) {
+                return Err(format!("{} failed: {}", self.position(), e));
+            }
+        }
+        if self.state != *expected {
+            return Err(format!("the state {} isn't the expected one (- expected, + actual):\n{}", self.after(), diff(&format!("{:#?}", expected), &format!("{:#?}", self.state))));
+        }
+
+        Ok(())
+    }
+
+    // `then_error`, describing what went wrong instead of panicking
+    pub(crate) fn expect_error(&mut self, message: &str) -> Result<(), String> {
+        for action in std::mem::take(&mut self.pending) {
+            match self.reduce(action) {
+                Ok(()) => continue,
+                Err(e) if e == message => return Ok(()),
+                Err(e) => return Err(format!("{} failed with {:?}, not the expected {:?}", self.position(), e, message)),
+            }
+        }
+
+        Err(format!("expected an action to fail with {:?}, but every one succeeded {}, leaving {:#?}", message, self.after(), self.state))
+    }
+
+    /// Runs the queued actions, panicking if any fails, and hands the state
+    /// to `check` for anything `then` can't express.
+    pub fn then_state<F: FnOnce(&R)>(mut self, check: F) -> Scenario<R> {
+        for action in std::mem::take(&mut self.pending) {
+            if let Err(e) = self.reduce(action) {
+                panic!("{} failed: {}", self.position(), e);
+            }
+        }
+        check(&self.state);
+
+        self
+    }
+
+    /// The state the scenario has reached.
+    pub fn state(&self) -> &R {
+        &self.state
+    }
+
+    fn reduce(&mut self, action: R::Action) -> Result<(), String> {
+        self.last = Some(format!("{:?}", action));
+        self.reduced += 1;
+        self.state.reduce(action).map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    // the action that just ran
+    fn position(&self) -> String {
+        format!("action #{} ({})", self.reduced - 1, self.last.as_deref().unwrap_or_default())
+    }
+
+    fn after(&self) -> String {
+        match self.reduced {
+            0 => String::from("before any action"),
+            _ => format!("after {}", self.position()),
+        }
+    }
+}
+
+/// Replays actions through a reducer in several ways and checks they all
+/// end up in the same states, to catch reducers that read the clock, random
+/// numbers or anything else outside the state and action. Time travel,
+/// replays and persistence all assume they don't.
+///
+/// Besides replaying the actions from the start a few times, it reduces each
+/// action on a fresh clone of the state, on two clones side by side, and
+/// resumes from snapshots of the state partway through. After every action
+/// the state is compared with `PartialEq`, and errors by their message.
+///
+/// ```
+/// # use redux::Reducer;
+/// # use redux::testing::Determinism;
+/// # #[derive(Clone, Default, Debug, PartialEq)]
+/// # struct Counter { count: usize }
+/// # impl Reducer for Counter {
+/// #     type Action = usize;
+/// #     type Error = String;
+/// #     fn reduce(&mut self, n: usize) -> Result<Self, String> {
+/// #         self.count += n;
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+/// Determinism::new(Counter::default()).check(&[1, 2, 3]).unwrap();
+/// ```
+pub struct Determinism<T: Reducer> {
+    initial: T,
+    runs: usize,
+}
+
+/// The first action whose outcome wasn't the same in every replay.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence<T: Reducer> {
+    /// How the actions were replayed when the outcome changed.
+    pub replay: String,
+    pub index: usize,
+    pub action: T::Action,
+    /// The state and error after the action the first time through.
+    pub expected: (T, Option<String>),
+    /// The state and error after it in this replay.
+    pub actual: (T, Option<String>),
+}
+
+impl<T> fmt::Display for Divergence<T> where T: Reducer + Debug, T::Action: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "action #{} ({:?}) reduced differently {} (- first run, + this one):", self.index, self.action, self.replay)?;
+        if self.expected.1 != self.actual.1 {
+            writeln!(f, "- error: {:?}", self.expected.1)?;
+            writeln!(f, "+ error: {:?}", self.actual.1)?;
+        }
+        write!(f, "{}", diff(&format!("{:#?}", self.expected.0), &format!("{:#?}", self.actual.0)))
+    }
+}
+
+// the state after an action, and its error if it failed
+type Outcome<T> = (T, Option<String>);
+
+fn step<T: Reducer>(state: &mut T, action: &T::Action) -> Option<String> {
+    state.reduce(action.clone()).err().map(|e| e.to_string())
+}
+
+impl<T> Determinism<T> where T: Reducer + Debug + PartialEq, T::Action: Debug {
+    /// Replays from `initial`, from the start 3 times by default.
+    pub fn new(initial: T) -> Determinism<T> {
+        Determinism {
+            initial,
+            runs: 3,
+        }
+    }
+
+    /// How many times the actions are replayed from the start.
+    pub fn runs(mut self, runs: usize) -> Determinism<T> {
+        self.runs = runs.max(1);
+        self
+    }
+
+    /// Replays `actions` every way there is, returning the earliest action
+    /// that didn't come out the same.
+    pub fn check(&self, actions: &[T::Action]) -> Result<(), Divergence<T>> {
+        let expected = self.replay(self.initial.clone(), actions);
+        let mut found : Option<Divergence<T>> = None;
+        let mut compare = |replay: String, offset: usize, outcomes: Vec<Outcome<T>>| {
+            let first = outcomes.into_iter().enumerate().find(|(index, outcome)| *outcome != expected[offset + index]);
+            if let Some((index, actual)) = first {
+                let index = offset + index;
+                if found.as_ref().is_none_or(|found| index < found.index) {
+                    found = Some(Divergence {
+                        replay,
+                        index,
+                        action: actions[index].clone(),
+                        expected: expected[index].clone(),
+                        actual,
+                    });
+                }
+            }
+        };
+
+        for run in 1..self.runs {
+            compare(format!("on replay #{}", run), 0, self.replay(self.initial.clone(), actions));
+        }
+        compare(String::from("reducing a fresh clone each time"), 0, self.cloning(actions));
+        let (twins, twin) = self.twins(actions);
+        compare(String::from("on two clones side by side"), 0, twins);
+        if let Some(twin) = twin {
+            compare(String::from("on the other of two clones side by side"), 0, twin);
+        }
+
+        // at most 8 snapshots, so long replays don't take quadratic time
+        let every = actions.len().div_ceil(8).max(1);
+        for start in (every..actions.len()).step_by(every) {
+            let snapshot = expected[start - 1].0.clone();
+            compare(format!("resuming from a snapshot after action #{}", start - 1), start, self.replay(snapshot, &actions[start..]));
+        }
+
+        match found {
+            Some(divergence) => Err(divergence),
+            None => Ok(()),
+        }
+    }
+
+    fn replay(&self, mut state: T, actions: &[T::Action]) -> Vec<Outcome<T>> {
+        actions.iter()
+            .map(|action| {
+                let error = step(&mut state, action);
+                (state.clone(), error)
+            })
+            .collect()
+    }
+
+    fn cloning(&self, actions: &[T::Action]) -> Vec<Outcome<T>> {
+        let mut state = self.initial.clone();
+        actions.iter()
+            .map(|action| {
+                let mut next = state.clone();
+                let error = step(&mut next, action);
+                state = next;
+                (state.clone(), error)
+            })
+            .collect()
+    }
+
+    // carries on from the first clone, and hands back where the second went
+    // instead if the two ever disagree
+    fn twins(&self, actions: &[T::Action]) -> (Vec<Outcome<T>>, Option<Vec<Outcome<T>>>) {
+        let mut state = self.initial.clone();
+        let mut outcomes = vec![];
+        for action in actions {
+            let mut other = state.clone();
+            let error = step(&mut state, action);
+            let other_error = step(&mut other, action);
+            if (&state, &error) != (&other, &other_error) {
+                let mut twin = outcomes.clone();
+                twin.push((other, other_error));
+                outcomes.push((state, error));
+                return (outcomes, Some(twin));
+            }
+            outcomes.push((state.clone(), error));
+        }
+
+        (outcomes, None)
+    }
+}
+
+// the most cells `diff` builds its table of common lines with; past that the
+// changed lines are shown as a plain before and after
+const MAX_DIFF_CELLS: usize = 1 << 20;
+
+// a line by line diff from `expected` to `actual`
+pub(crate) fn diff(expected: &str, actual: &str) -> String {
+    let old : Vec<&str> = expected.lines().collect();
+    let new : Vec<&str> = actual.lines().collect();
+
+    // states usually differ in a few lines in the middle, so the lines they
+    // start and end with are matched up before building the table
+    let prefix = old.iter().zip(&new).take_while(|(old, new)| old == new).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let mut out = String::new();
+    for line in &old[..prefix] {
+        let _ = writeln!(out, "  {}", line);
+    }
+    diff_changed(&mut out, &old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+    for line in &old[old.len() - suffix..] {
+        let _ = writeln!(out, "  {}", line);
+    }
+
+    out
+}
+
+fn diff_changed(out: &mut String, old: &[&str], new: &[&str]) {
+    if (old.len() + 1).saturating_mul(new.len() + 1) > MAX_DIFF_CELLS {
+        for line in old {
+            let _ = writeln!(out, "- {}", line);
+        }
+        for line in new {
+            let _ = writeln!(out, "+ {}", line);
+        }
+        return;
+    }
+
+    // the longest common subsequence of every pair of suffixes
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            let _ = writeln!(out, "  {}", old[i]);
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            let _ = writeln!(out, "- {}", old[i]);
+            i += 1;
+        } else {
+            let _ = writeln!(out, "+ {}", new[j]);
+            j += 1;
+        }
+    }
+}
+
+/// Panics unless a `MockStore` recorded an action matching the pattern,
+/// listing the actions it did record. The pattern can have an `if` guard,
+/// which sees its bindings by reference.
+#[macro_export]
+macro_rules! assert_dispatched {
+    ($store:expr, $pattern:pat $(if $guard:expr)? $(,)?) => {{
+        let actions = $store.actions();
+        if !actions.iter().any(|action| matches!(action, $pattern $(if $guard)?)) {
+            panic!("expected an action matching `{}` to be dispatched, but got {:?}", stringify!($pattern $(if $guard)?), actions);
+        }
+    }};
+}
+
+/// Panics if a `MockStore` recorded an action matching the pattern.
+#[macro_export]
+macro_rules! assert_not_dispatched {
+    ($store:expr, $pattern:pat $(if $guard:expr)? $(,)?) => {{
+        let actions = $store.actions();
+        if let Some(action) = actions.iter().find(|action| matches!(action, $pattern $(if $guard)?)) {
+            panic!("expected no action matching `{}` to be dispatched, but got {:?}", stringify!($pattern $(if $guard)?), action);
+        }
+    }};
+}
@@ -0,0 +1,179 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{DispatchFunc, Middleware, MiddlewareApi, Reducer, Store};
+
+type ThunkFunc<T, E> = Arc<dyn Fn(&dyn MiddlewareApi<T>, &E) -> Result<(), String> + Send + Sync>;
+
+/// A function of `(dispatch, get_state)` that can be dispatched like any other
+/// action once the store has a `ThunkMiddleware`. `E` is the type of the extra
+/// argument the middleware was built with, `()` by default.
+///
+/// Because `Reducer::Action` has to be `Clone`, a `Thunk` is cheap to clone;
+/// all the clones share the same function.
+pub struct Thunk<T: Reducer, E = ()> {
+    func: ThunkFunc<T, E>,
+}
+
+impl<T: Reducer, E> Thunk<T, E> {
+    pub fn new<F>(func: F) -> Thunk<T, E>
+        where F: 'static + Send + Sync + Fn(&dyn MiddlewareApi<T>, &E) -> Result<(), String>
+    {
+        Thunk {
+            func: Arc::new(func),
+        }
+    }
+
+    fn run(&self, api: &dyn MiddlewareApi<T>, extra: &E) -> Result<(), String> {
+        let func = &self.func;
+        func(api, extra)
+    }
+}
+
+impl<T: Reducer, E> Clone for Thunk<T, E> {
+    fn clone(&self) -> Self {
+        Thunk {
+            func: self.func.clone(),
+        }
+    }
+}
+
+impl<T: Reducer, E> fmt::Debug for Thunk<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Thunk")
+    }
+}
+
+/// Implemented by action types that can carry a `Thunk`, which is usually
+/// done by giving the action enum a variant for it:
+///
+/// ```
+/// # use redux::{Reducer, Thunk, ThunkAction};
+/// #
+/// # #[derive(Clone, Default)]
+/// # struct Counter { count: usize }
+/// # impl Reducer for Counter {
+/// #     type Action = CounterAction;
+/// #     type Error = String;
+/// #
+/// #     fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+/// #         if let CounterAction::Add(n) = action {
+/// #             self.count += n;
+/// #         }
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+/// #[derive(Clone)]
+/// enum CounterAction {
+///     Add(usize),
+///     Thunk(Thunk<Counter>),
+/// }
+///
+/// impl ThunkAction<Counter> for CounterAction {
+///     fn into_thunk(self) -> Result<Thunk<Counter>, Self> {
+///         match self {
+///             CounterAction::Thunk(thunk) => Ok(thunk),
+///             action => Err(action),
+///         }
+///     }
+/// }
+/// ```
+pub trait ThunkAction<T: Reducer, E = ()>: Sized {
+    /// Returns the thunk this action carries, or gives the action back if it's
+    /// a plain one.
+    fn into_thunk(self) -> Result<Thunk<T, E>, Self>;
+}
+
+/// A redux-thunk style middleware. Thunks are run right away with the store as
+/// their `MiddlewareApi` and never reach the reducer; every other action is
+/// passed along unchanged.
+///
+/// A thunk's error becomes the error of the `dispatch` that carried it. Once
+/// the thunk returns, the store carries on as for any other dispatch, so
+/// subscriptions are still called.
+///
+/// ## Example
+///
+/// ```
+/// # use redux::{MiddlewareApi, Reducer, Store, Thunk, ThunkAction, ThunkMiddleware};
+/// #
+/// # #[derive(Clone, Default)]
+/// # struct Counter { count: usize }
+/// # impl Reducer for Counter {
+/// #     type Action = CounterAction;
+/// #     type Error = String;
+/// #
+/// #     fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+/// #         if let CounterAction::Add(n) = action {
+/// #             self.count += n;
+/// #         }
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+/// # #[derive(Clone)]
+/// # enum CounterAction {
+/// #     Add(usize),
+/// #     Thunk(Thunk<Counter, usize>),
+/// # }
+/// # impl ThunkAction<Counter, usize> for CounterAction {
+/// #     fn into_thunk(self) -> Result<Thunk<Counter, usize>, Self> {
+/// #         match self {
+/// #             CounterAction::Thunk(thunk) => Ok(thunk),
+/// #             action => Err(action),
+/// #         }
+/// #     }
+/// # }
+/// let step = 5;
+/// let store : Store<Counter> = Store::new(vec![Box::new(ThunkMiddleware::with_extra_argument(step))]);
+///
+/// let add_until_ten = Thunk::new(|api: &dyn MiddlewareApi<Counter>, step: &usize| {
+///     while api.get_state().count < 10 {
+///         api.dispatch(CounterAction::Add(*step))?;
+///     }
+///     Ok(())
+/// });
+/// let _ = store.dispatch(CounterAction::Thunk(add_until_ten));
+///
+/// assert_eq!(10, store.get_state().count);
+/// ```
+pub struct ThunkMiddleware<E = ()> {
+    extra: E,
+}
+
+impl ThunkMiddleware {
+    pub fn new() -> ThunkMiddleware {
+        ThunkMiddleware::with_extra_argument(())
+    }
+}
+
+impl Default for ThunkMiddleware {
+    fn default() -> Self {
+        ThunkMiddleware::new()
+    }
+}
+
+impl<E> ThunkMiddleware<E> {
+    /// Builds a middleware that hands `extra` to every thunk it runs, for
+    /// injecting things like an API client.
+    pub fn with_extra_argument(extra: E) -> ThunkMiddleware<E> {
+        ThunkMiddleware {
+            extra,
+        }
+    }
+}
+
+impl<T, E> Middleware<T> for ThunkMiddleware<E>
+    where T: 'static + Reducer,
+          T::Action: ThunkAction<T, E>,
+          E: Send + Sync
+{
+    fn dispatch(&self, store: &Store<T>, action: T::Action, next: &DispatchFunc<T>) -> Result<T, String> {
+        match action.into_thunk() {
+            Ok(thunk) => {
+                thunk.run(store, &self.extra)?;
+                Ok(store.get_state())
+            },
+            Err(action) => next(store, action),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::any;
+
+use tracing::{field, Span};
+
+/// Names an action in the spans of a `Store`.
+pub(crate) type KindFunc<A> = fn(&A) -> &'static str;
+
+// until the store's told how, every action is named after its type
+pub(crate) fn type_kind<A>(_: &A) -> &'static str {
+    any::type_name::<A>()
+}
+
+pub(crate) fn dispatch(kind: &str) -> Span {
+    tracing::info_span!(target: "redux", "dispatch", action = kind, outcome = field::Empty, error = field::Empty)
+}
+
+pub(crate) fn middleware(name: &str, kind: &str) -> Span {
+    tracing::debug_span!(target: "redux", "middleware", middleware = name, action = kind, outcome = field::Empty, error = field::Empty)
+}
+
+pub(crate) fn reduce(kind: &str) -> Span {
+    tracing::debug_span!(target: "redux", "reduce", action = kind, outcome = field::Empty, error = field::Empty)
+}
+
+pub(crate) fn subscription(id: usize, kind: &str) -> Span {
+    tracing::debug_span!(target: "redux", "subscription", subscription = id, action = kind)
+}
+
+/// Runs `f` in `span`, recording whether it worked on the span.
+pub(crate) fn traced<T, F>(span: Span, f: F) -> Result<T, String>
+    where F: FnOnce() -> Result<T, String>
+{
+    let result = span.in_scope(f);
+    match result {
+        Ok(_) => {
+            span.record("outcome", "ok");
+        },
+        Err(ref e) => {
+            span.record("outcome", "error");
+            span.record("error", e.as_str());
+        },
+    }
+
+    result
+}
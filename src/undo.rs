@@ -0,0 +1,261 @@
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use crate::Reducer;
+
+/// The actions an `Undoable` reducer accepts: the meta-actions that move
+/// through history, plus the wrapped reducer's own actions.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UndoAction<A> {
+    /// Go back one step.
+    Undo,
+    /// Go forward one step.
+    Redo,
+    /// Go back (negative) or forward (positive) this many steps. Jumping
+    /// further than the history goes does nothing.
+    Jump(isize),
+    /// Forget the past and the future, keeping the present.
+    ClearHistory,
+    /// An action for the wrapped reducer.
+    Action(A),
+}
+
+/// How an `Undoable` records history. Every method has a default, so a config
+/// only has to override what it cares about:
+///
+/// ```
+/// # use redux::{Reducer, UndoConfig};
+/// # #[derive(Clone, Default)]
+/// # struct Editor {}
+/// # impl Reducer for Editor {
+/// #     type Action = EditorAction;
+/// #     type Error = String;
+/// #     fn reduce(&mut self, _: Self::Action) -> Result<Self, Self::Error> {
+/// #         Ok(self.clone())
+/// #     }
+/// # }
+/// # #[derive(Clone)]
+/// # enum EditorAction { Type(char), MoveCursor(usize) }
+/// struct EditorHistory;
+///
+/// impl UndoConfig<Editor> for EditorHistory {
+///     fn limit() -> Option<usize> {
+///         Some(100)
+///     }
+///
+///     // moving the cursor around isn't something to undo
+///     fn filter(action: &EditorAction) -> bool {
+///         !matches!(action, EditorAction::MoveCursor(_))
+///     }
+///
+///     // a run of typing is undone in one go
+///     fn group(action: &EditorAction) -> Option<&'static str> {
+///         match action {
+///             EditorAction::Type(_) => Some("typing"),
+///             _ => None,
+///         }
+///     }
+/// }
+/// ```
+pub trait UndoConfig<R: Reducer> {
+    /// The most past states to keep; the oldest ones are dropped first.
+    /// `None` keeps everything.
+    fn limit() -> Option<usize> {
+        None
+    }
+
+    /// Whether an action gets its own undo step. Filtered out actions still
+    /// change the present, they just can't be undone on their own.
+    fn filter(_action: &R::Action) -> bool {
+        true
+    }
+
+    /// Consecutive actions in the same group share one undo step. `None`
+    /// means the action is a group of its own.
+    fn group(_action: &R::Action) -> Option<&'static str> {
+        None
+    }
+}
+
+/// The `UndoConfig` used when none is given: unlimited history, every action
+/// recorded, no grouping.
+pub struct KeepEverything;
+
+impl<R: Reducer> UndoConfig<R> for KeepEverything {}
+
+/// A higher-order reducer that adds undo and redo to any `Reducer`, keeping
+/// snapshots of the state in `past` and `future` stacks around the `present`.
+///
+/// Since it's a `Reducer` itself it works with a `Store` directly; dispatch
+/// `UndoAction::Action` for the wrapped reducer and the other `UndoAction`s to
+/// move through history:
+///
+/// ```
+/// # use redux::{Reducer, Store, UndoAction, Undoable};
+/// #[derive(Clone, Default)]
+/// struct Counter {
+///     count: usize,
+/// }
+///
+/// impl Reducer for Counter {
+///     type Action = usize;
+///     type Error = String;
+///
+///     fn reduce(&mut self, n: Self::Action) -> Result<Self, Self::Error> {
+///         self.count += n;
+///         Ok(self.clone())
+///     }
+/// }
+///
+/// let store : Store<Undoable<Counter>> = Store::new(vec![]);
+/// let _ = store.dispatch(UndoAction::Action(1));
+/// let _ = store.dispatch(UndoAction::Action(2));
+/// let _ = store.dispatch(UndoAction::Undo);
+/// assert_eq!(1, store.get_state().count);
+/// ```
+///
+/// `C` picks the `UndoConfig`; see there for history limits, filtering and
+/// grouping.
+pub struct Undoable<R: Reducer, C: UndoConfig<R> = KeepEverything> {
+    past: VecDeque<R>,
+    present: R,
+    future: Vec<R>,
+    // the group of the last recorded action
+    group: Option<&'static str>,
+    config: PhantomData<fn() -> C>,
+}
+
+impl<R: Reducer, C: UndoConfig<R>> Undoable<R, C> {
+    /// The current state.
+    pub fn present(&self) -> &R {
+        &self.present
+    }
+
+    /// How many steps `Undo` can go back.
+    pub fn past_len(&self) -> usize {
+        self.past.len()
+    }
+
+    /// How many steps `Redo` can go forward.
+    pub fn future_len(&self) -> usize {
+        self.future.len()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.past.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.future.is_empty()
+    }
+
+    fn undo(&mut self) {
+        if let Some(previous) = self.past.pop_back() {
+            let present = std::mem::replace(&mut self.present, previous);
+            self.future.push(present);
+        }
+        self.group = None;
+    }
+
+    fn redo(&mut self) {
+        if let Some(next) = self.future.pop() {
+            let present = std::mem::replace(&mut self.present, next);
+            self.past.push_back(present);
+        }
+        self.group = None;
+    }
+
+    fn jump(&mut self, steps: isize) {
+        let available = if steps < 0 { self.past.len() } else { self.future.len() };
+        if steps.unsigned_abs() > available {
+            return;
+        }
+        for _ in 0..steps.unsigned_abs() {
+            if steps < 0 {
+                self.undo();
+            } else {
+                self.redo();
+            }
+        }
+    }
+
+    fn record(&mut self, action: R::Action) -> Result<(), R::Error> {
+        // reduce a copy so a failed action leaves everything as it was
+        let mut next = self.present.clone();
+        next.reduce(action.clone())?;
+        let previous = std::mem::replace(&mut self.present, next);
+
+        if !C::filter(&action) {
+            self.group = None;
+            return Ok(());
+        }
+
+        let group = C::group(&action);
+        if group.is_none() || group != self.group {
+            self.past.push_back(previous);
+            if let Some(limit) = C::limit() {
+                while self.past.len() > limit {
+                    self.past.pop_front();
+                }
+            }
+        }
+        self.future.clear();
+        self.group = group;
+
+        Ok(())
+    }
+}
+
+impl<R: Reducer, C: UndoConfig<R>> Deref for Undoable<R, C> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        &self.present
+    }
+}
+
+impl<R: Reducer, C: UndoConfig<R>> Clone for Undoable<R, C> {
+    fn clone(&self) -> Self {
+        Undoable {
+            past: self.past.clone(),
+            present: self.present.clone(),
+            future: self.future.clone(),
+            group: self.group,
+            config: PhantomData,
+        }
+    }
+}
+
+impl<R: Reducer, C: UndoConfig<R>> Default for Undoable<R, C> {
+    fn default() -> Self {
+        Undoable {
+            past: VecDeque::new(),
+            present: R::default(),
+            future: vec![],
+            group: None,
+            config: PhantomData,
+        }
+    }
+}
+
+impl<R: Reducer, C: UndoConfig<R>> Reducer for Undoable<R, C> {
+    type Action = UndoAction<R::Action>;
+    type Error = R::Error;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            UndoAction::Undo => self.undo(),
+            UndoAction::Redo => self.redo(),
+            UndoAction::Jump(steps) => self.jump(steps),
+            UndoAction::ClearHistory => {
+                self.past.clear();
+                self.future.clear();
+                self.group = None;
+            },
+            UndoAction::Action(action) => self.record(action)?,
+        }
+
+        Ok(self.clone())
+    }
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "derive")]
+
+extern crate redux;
+
+use redux::{ActionKind, KindFilter, Reducer, Store};
+
+#[derive(ActionKind, Clone, Debug, PartialEq)]
+#[action(category = "todos")]
+enum TodoAction {
+    NewTodo { name: String },
+    #[action(kind = "todos/complete", category = "writes")]
+    Complete(usize),
+    #[action(category = "ui", category = "noisy")]
+    Tick,
+}
+
+#[derive(ActionKind, Clone)]
+enum Wrapped<A: Clone> {
+    #[action(category = "meta")]
+    Undo,
+    #[allow(dead_code)]
+    Action(A),
+}
+
+#[derive(Clone, Default, Debug, PartialEq)]
+struct TodoList {
+    names: Vec<String>,
+    done: Vec<usize>,
+    ticks: usize,
+}
+
+impl Reducer for TodoList {
+    type Action = TodoAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            TodoAction::NewTodo { name } => self.names.push(name),
+            TodoAction::Complete(index) => self.done.push(index),
+            TodoAction::Tick => self.ticks += 1,
+        }
+
+        Ok(self.clone())
+    }
+}
+
+fn new_todo(name: &str) -> TodoAction {
+    TodoAction::NewTodo { name: String::from(name) }
+}
+
+#[test]
+fn kinds_are_variant_names_unless_renamed() {
+    assert_eq!("NewTodo", new_todo("a").kind());
+    assert_eq!("todos/complete", TodoAction::Complete(0).kind());
+    assert_eq!("Tick", TodoAction::Tick.kind());
+    assert_eq!("Undo", Wrapped::<usize>::Undo.kind());
+    assert_eq!("Action", Wrapped::Action(1).kind());
+}
+
+#[test]
+fn categories_come_from_the_enum_and_the_variant() {
+    assert_eq!(&["todos"], new_todo("a").categories());
+    assert_eq!(&["todos", "writes"], TodoAction::Complete(0).categories());
+    assert_eq!(&["todos", "ui", "noisy"], TodoAction::Tick.categories());
+    assert_eq!(&["meta"], Wrapped::<usize>::Undo.categories());
+    assert!(Wrapped::Action(1).categories().is_empty());
+}
+
+#[test]
+fn filters_match_kinds_and_categories() {
+    assert!(KindFilter::new().matches(&TodoAction::Tick));
+
+    let writes = KindFilter::new().allow(["writes", "NewTodo"]);
+    assert!(writes.matches(&new_todo("a")));
+    assert!(writes.matches(&TodoAction::Complete(0)));
+    assert!(!writes.matches(&TodoAction::Tick));
+
+    let quiet = KindFilter::new().allow(["*"]).deny(["noisy"]);
+    assert!(quiet.matches(&new_todo("a")));
+    assert!(!quiet.matches(&TodoAction::Tick));
+
+    // denying wins
+    let nothing = KindFilter::new().allow(["todos"]).deny(["todos"]);
+    assert!(!nothing.matches(&new_todo("a")));
+}
+
+#[test]
+fn filters_are_read_from_settings() {
+    let filter : KindFilter = " todos/complete, NewTodo ,-noisy".parse().unwrap();
+    assert_eq!(KindFilter::new().allow(["todos/complete", "NewTodo"]).deny(["noisy"]), filter);
+    assert_eq!(KindFilter::new().deny(["ui"]), "-ui".parse().unwrap());
+    assert_eq!(KindFilter::new(), "".parse().unwrap());
+    assert!("NewTodo, -".parse::<KindFilter>().is_err());
+}
+
+#[test]
+fn filtered_out_actions_are_dropped() {
+    let filter : KindFilter = "-ui".parse().unwrap();
+    let store : Store<TodoList> = Store::new(vec![Box::new(filter)]);
+    assert_eq!(TodoAction::Tick, store.dispatch(TodoAction::Tick).unwrap());
+    let _ = store.dispatch(new_todo("a"));
+    let _ = store.dispatch(TodoAction::Complete(0));
+
+    assert_eq!(TodoList { names: vec![String::from("a")], done: vec![0], ticks: 0 }, store.get_state());
+}
@@ -0,0 +1,310 @@
+#![cfg(feature = "async")]
+
+extern crate futures;
+extern crate redux;
+
+use redux::{async_trait, AsyncDispatchFunc, AsyncMiddleware, DispatchFunc, Middleware, Reducer, Store};
+
+use futures::channel::oneshot;
+use futures::executor::{block_on, LocalPool};
+use futures::stream::StreamExt;
+use futures::task::LocalSpawnExt;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Clone, Debug)]
+enum CounterAction {
+    Add(usize),
+    Fail,
+}
+
+#[derive(Clone, Default, Debug)]
+struct Counter {
+    count: usize,
+}
+
+impl Reducer for Counter {
+    type Action = CounterAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            CounterAction::Add(n) => self.count += n,
+            CounterAction::Fail => return Err(String::from("nope")),
+        }
+
+        Ok(self.clone())
+    }
+}
+
+#[test]
+fn changes_yields_state_after_each_dispatch() {
+    let store : Store<Counter> = Store::new(vec![]);
+    let changes = store.changes();
+
+    block_on(async {
+        store.dispatch_async(CounterAction::Add(1)).await.unwrap();
+        store.dispatch_async(CounterAction::Add(2)).await.unwrap();
+    });
+
+    let counts : Vec<usize> = block_on(changes.take(2).map(|state| state.count).collect());
+    assert_eq!(vec![1, 3], counts);
+}
+
+#[test]
+fn actions_skips_failed_dispatches() {
+    let store : Store<Counter> = Store::new(vec![]);
+    let actions = store.actions();
+
+    let _ = store.dispatch(CounterAction::Add(1));
+    assert!(store.dispatch(CounterAction::Fail).is_err());
+    let _ = store.dispatch(CounterAction::Add(2));
+
+    let seen : Vec<usize> = block_on(actions.take(2).map(|action| match action {
+        CounterAction::Add(n) => n,
+        CounterAction::Fail => 0,
+    }).collect());
+    assert_eq!(vec![1, 2], seen);
+}
+
+// answers `Add(0)` itself, without reducing it
+struct AnswersZero;
+
+impl Middleware<Counter> for AnswersZero {
+    fn dispatch(&self, store: &Store<Counter>, action: CounterAction, next: &DispatchFunc<Counter>) -> Result<Counter, String> {
+        match action {
+            CounterAction::Add(0) => Ok(store.get_state()),
+            action => next(store, action),
+        }
+    }
+}
+
+#[test]
+fn actions_includes_dispatches_that_were_never_reduced() {
+    let store : Store<Counter> = Store::new(vec![Box::new(AnswersZero)]);
+    let actions = store.actions();
+    let changes = store.changes();
+
+    let _ = store.dispatch(CounterAction::Add(0));
+    let _ = store.dispatch(CounterAction::Add(2));
+
+    let seen : Vec<usize> = block_on(actions.take(2).map(|action| match action {
+        CounterAction::Add(n) => n,
+        CounterAction::Fail => 99,
+    }).collect());
+    assert_eq!(vec![0, 2], seen);
+    let counts : Vec<usize> = block_on(changes.take(2).map(|state| state.count).collect());
+    assert_eq!(vec![0, 2], counts);
+}
+
+#[test]
+fn streams_only_see_dispatches_after_they_were_created() {
+    let store : Store<Counter> = Store::new(vec![]);
+    let _ = store.dispatch(CounterAction::Add(5));
+
+    let mut changes = store.changes();
+    let _ = store.dispatch(CounterAction::Add(1));
+    assert_eq!(6, block_on(changes.next()).unwrap().count);
+}
+
+#[test]
+fn consume_changes_from_a_spawned_task() {
+    let store : Arc<Store<Counter>> = Arc::new(Store::new(vec![]));
+    let mut pool = LocalPool::new();
+    let spawner = pool.spawner();
+
+    let changes = store.changes();
+    let handle = spawner.spawn_local_with_handle(async move {
+        changes.take(3).fold(0, |sum, state| async move { sum + state.count }).await
+    }).unwrap();
+
+    let s = store.clone();
+    spawner.spawn_local(async move {
+        for n in 1..4 {
+            s.dispatch_async(CounterAction::Add(n)).await.unwrap();
+        }
+    }).unwrap();
+
+    // 1 + 3 + 6
+    assert_eq!(10, pool.run_until(handle));
+}
+
+struct Gate {
+    waiting: Mutex<Option<oneshot::Receiver<()>>>,
+}
+
+impl Gate {
+    fn new() -> (Gate, oneshot::Sender<()>) {
+        let (sender, receiver) = oneshot::channel();
+        (Gate { waiting: Mutex::new(Some(receiver)) }, sender)
+    }
+}
+
+// holds back the first `Add(1)` until the gate is opened
+#[async_trait]
+impl AsyncMiddleware<Counter> for Gate {
+    async fn dispatch(&self, store: &Store<Counter>, action: CounterAction, next: &AsyncDispatchFunc<Counter>) -> Result<Counter, String> {
+        let receiver = match action {
+            CounterAction::Add(1) => self.waiting.lock().unwrap().take(),
+            _ => None,
+        };
+        if let Some(receiver) = receiver {
+            receiver.await.map_err(|_| String::from("gate dropped"))?;
+        }
+
+        next(store, action).await
+    }
+}
+
+#[test]
+fn async_middleware_holds_dispatch_until_it_resolves() {
+    let (gate, open) = Gate::new();
+    let store : Arc<Store<Counter>> = Arc::new(Store::new_async(vec![], vec![Box::new(gate)]));
+    let mut pool = LocalPool::new();
+    let spawner = pool.spawner();
+
+    let s = store.clone();
+    let handle = spawner.spawn_local_with_handle(async move {
+        s.dispatch_async(CounterAction::Add(1)).await
+    }).unwrap();
+
+    pool.run_until_stalled();
+    assert_eq!(0, store.get_state().count);
+
+    open.send(()).unwrap();
+    assert!(pool.run_until(handle).is_ok());
+    assert_eq!(1, store.get_state().count);
+}
+
+#[test]
+fn overlapping_async_dispatches_reduce_in_arrival_order() {
+    let (gate, open) = Gate::new();
+    let store : Arc<Store<Counter>> = Arc::new(Store::new_async(vec![], vec![Box::new(gate)]));
+    let actions = store.actions();
+    let mut pool = LocalPool::new();
+    let spawner = pool.spawner();
+
+    // the first dispatch is held by the gate, so the second one overtakes it
+    for n in 1..3 {
+        let s = store.clone();
+        spawner.spawn_local(async move {
+            s.dispatch_async(CounterAction::Add(n)).await.unwrap();
+        }).unwrap();
+    }
+    pool.run_until_stalled();
+    assert_eq!(2, store.get_state().count);
+
+    open.send(()).unwrap();
+    pool.run_until_stalled();
+    assert_eq!(3, store.get_state().count);
+
+    let order : Vec<usize> = block_on(actions.take(2).map(|action| match action {
+        CounterAction::Add(n) => n,
+        CounterAction::Fail => 0,
+    }).collect());
+    assert_eq!(vec![2, 1], order);
+}
+
+// holds back the first `Add(1)` after it has been reduced, until the gate is
+// opened
+struct GateAfter(Gate);
+
+#[async_trait]
+impl AsyncMiddleware<Counter> for GateAfter {
+    async fn dispatch(&self, store: &Store<Counter>, action: CounterAction, next: &AsyncDispatchFunc<Counter>) -> Result<Counter, String> {
+        let receiver = match action {
+            CounterAction::Add(1) => self.0.waiting.lock().unwrap().take(),
+            _ => None,
+        };
+        let state = next(store, action).await?;
+        if let Some(receiver) = receiver {
+            receiver.await.map_err(|_| String::from("gate dropped"))?;
+        }
+
+        Ok(state)
+    }
+}
+
+#[test]
+fn awaiting_after_next_does_not_hold_back_notifications() {
+    let (gate, open) = Gate::new();
+    let store : Arc<Store<Counter>> = Arc::new(Store::new_async(vec![], vec![Box::new(GateAfter(gate))]));
+    let actions = store.actions();
+    let counts = Arc::new(Mutex::new(vec![]));
+    let c = counts.clone();
+    store.subscribe(Box::new(move |store, _| c.lock().unwrap().push(store.get_state().count)));
+    let mut pool = LocalPool::new();
+    let spawner = pool.spawner();
+
+    for n in 1..3 {
+        let s = store.clone();
+        spawner.spawn_local(async move {
+            s.dispatch_async(CounterAction::Add(n)).await.unwrap();
+        }).unwrap();
+    }
+    pool.run_until_stalled();
+
+    // both were reduced and notified in order, while the first is still held
+    assert_eq!(vec![1, 3], *counts.lock().unwrap());
+    let order : Vec<usize> = block_on(actions.take(2).map(|action| match action {
+        CounterAction::Add(n) => n,
+        CounterAction::Fail => 0,
+    }).collect());
+    assert_eq!(vec![1, 2], order);
+
+    open.send(()).unwrap();
+    pool.run_until_stalled();
+    assert_eq!(vec![1, 3], *counts.lock().unwrap());
+}
+
+#[test]
+fn async_dispatches_can_be_awaited_on_another_thread() {
+    let (gate, open) = Gate::new();
+    let store : Arc<Store<Counter>> = Arc::new(Store::new_async(vec![], vec![Box::new(gate)]));
+
+    // made here, but polled on the other thread
+    let s = store.clone();
+    let dispatch = async move { s.dispatch_async(CounterAction::Add(1)).await };
+    let handle = thread::spawn(move || block_on(dispatch));
+
+    open.send(()).unwrap();
+    assert!(handle.join().unwrap().is_ok());
+    assert_eq!(1, store.get_state().count);
+}
+
+struct Recorder {
+    name: &'static str,
+    calls: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Middleware<Counter> for Recorder {
+    fn dispatch(&self, store: &Store<Counter>, action: CounterAction, next: &DispatchFunc<Counter>) -> Result<Counter, String> {
+        self.calls.lock().unwrap().push(self.name);
+        next(store, action)
+    }
+}
+
+#[async_trait]
+impl AsyncMiddleware<Counter> for Recorder {
+    async fn dispatch(&self, store: &Store<Counter>, action: CounterAction, next: &AsyncDispatchFunc<Counter>) -> Result<Counter, String> {
+        self.calls.lock().unwrap().push(self.name);
+        next(store, action).await
+    }
+}
+
+#[test]
+fn async_middlewares_wrap_sync_middlewares() {
+    let calls = Arc::new(Mutex::new(vec![]));
+    let sync = Box::new(Recorder { name: "sync", calls: calls.clone() });
+    let first = Box::new(Recorder { name: "first async", calls: calls.clone() });
+    let second = Box::new(Recorder { name: "second async", calls: calls.clone() });
+    let store : Store<Counter> = Store::new_async(vec![sync], vec![first, second]);
+
+    block_on(store.dispatch_async(CounterAction::Add(1))).unwrap();
+    assert_eq!(vec!["second async", "first async", "sync"], *calls.lock().unwrap());
+
+    // the synchronous dispatch skips the async middlewares entirely
+    calls.lock().unwrap().clear();
+    store.dispatch(CounterAction::Add(1)).unwrap();
+    assert_eq!(vec!["sync"], *calls.lock().unwrap());
+}
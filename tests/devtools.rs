@@ -0,0 +1,293 @@
+#![cfg(feature = "devtools")]
+extern crate redux;
+
+use redux::{DevtoolsServer, DevtoolsSession, History, HistoryAction, RecordEverything, Reducer, Store};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tungstenite::{Message, WebSocket};
+
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum CounterAction {
+    Increment,
+    Add(usize),
+}
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+struct Counter {
+    count: usize,
+}
+
+impl Reducer for Counter {
+    type Action = CounterAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            CounterAction::Increment => self.count += 1,
+            CounterAction::Add(n) => self.count += n,
+        }
+
+        Ok(self.clone())
+    }
+}
+
+type Monitor = WebSocket<tungstenite::stream::MaybeTlsStream<TcpStream>>;
+
+fn connect(devtools: &DevtoolsServer<Counter>) -> Monitor {
+    let (monitor, _) = tungstenite::connect(format!("ws://{}/socketcluster/", devtools.local_addr())).unwrap();
+    if let tungstenite::stream::MaybeTlsStream::Plain(ref stream) = monitor.get_ref() {
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    }
+    monitor
+}
+
+// the next frame that isn't a ping, which is answered
+fn frame(monitor: &mut Monitor) -> Value {
+    loop {
+        match monitor.read().unwrap() {
+            Message::Text(ping) if ping == "#1" => monitor.send(Message::Text(String::from("#2"))).unwrap(),
+            Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+            _ => {},
+        }
+    }
+}
+
+fn emit(monitor: &mut Monitor, event: &str, data: Value, cid: u64) -> Value {
+    monitor.send(Message::Text(json!({ "event": event, "data": data, "cid": cid }).to_string())).unwrap();
+    let answer = frame(monitor);
+    assert_eq!(json!(cid), answer["rid"], "{}", answer);
+    answer
+}
+
+// logs in and subscribes the way the remote monitor does
+fn served_store() -> (Arc<Store<History<Counter>>>, DevtoolsServer<Counter>, Monitor) {
+    let devtools = DevtoolsServer::bind("127.0.0.1:0").unwrap();
+    let store = Arc::new(Store::new(vec![Box::new(devtools.clone())]));
+    devtools.attach(&store);
+    let mut monitor = connect(&devtools);
+    emit(&mut monitor, "#handshake", json!({ "authToken": null }), 1);
+    let channel = emit(&mut monitor, "login", json!({}), 2)["data"].clone();
+    emit(&mut monitor, "#subscribe", json!({ "channel": channel }), 3);
+    (store, devtools, monitor)
+}
+
+// the next message the app published on the `log` channel
+fn receive(monitor: &mut Monitor) -> Value {
+    loop {
+        let frame = frame(monitor);
+        if frame["event"] == "#publish" {
+            assert_eq!("log", frame["data"]["channel"]);
+            return frame["data"]["data"].clone();
+        }
+    }
+}
+
+// the remote protocol nests JSON in strings
+fn payload(message: &Value) -> Value {
+    serde_json::from_str(message["payload"].as_str().unwrap()).unwrap()
+}
+
+fn send(monitor: &mut Monitor, message: Value) {
+    monitor.send(Message::Text(json!({ "event": "respond", "data": message }).to_string())).unwrap();
+}
+
+#[test]
+fn monitors_log_in_like_on_remotedev_server() {
+    let devtools : DevtoolsServer<Counter> = DevtoolsServer::bind("127.0.0.1:0").unwrap();
+    let mut monitor = connect(&devtools);
+
+    let handshake = emit(&mut monitor, "#handshake", json!({ "authToken": null }), 1);
+    assert!(handshake["data"]["id"].is_string(), "{}", handshake);
+    assert!(handshake["data"]["pingTimeout"].as_u64().unwrap() > 0, "{}", handshake);
+    assert_eq!("log", emit(&mut monitor, "login", json!({}), 2)["data"]);
+    assert!(emit(&mut monitor, "login", json!("master"), 3)["error"].is_string());
+
+    monitor.send(Message::Text(String::from("#1"))).unwrap();
+    assert_eq!(Message::Text(String::from("#2")), monitor.read().unwrap());
+}
+
+#[test]
+fn monitors_get_the_state_then_actions() {
+    let (store, devtools, mut monitor) = served_store();
+    let start = receive(&mut monitor);
+    assert_eq!("START", start["type"]);
+    assert_eq!(json!(format!("redux-{}", devtools.local_addr().port())), start["id"]);
+    let hello = receive(&mut monitor);
+    assert_eq!("STATE", hello["type"]);
+    assert_eq!(json!([0]), payload(&hello)["stagedActionIds"]);
+
+    let _ = store.dispatch(HistoryAction::Perform(CounterAction::Add(2)));
+    let message = receive(&mut monitor);
+    assert_eq!("ACTION", message["type"]);
+    assert_eq!(start["id"], message["id"]);
+    assert_eq!(json!({ "count": 2 }), payload(&message));
+    let action : Value = serde_json::from_str(message["action"].as_str().unwrap()).unwrap();
+    assert_eq!(json!({ "Add": 2 }), action["action"]);
+
+    send(&mut monitor, json!({ "type": "UPDATE" }));
+    assert_eq!(json!([0, 1]), payload(&receive(&mut monitor))["stagedActionIds"]);
+}
+
+#[test]
+fn monitors_travel_through_time() {
+    let (store, _devtools, mut monitor) = served_store();
+    let app = receive(&mut monitor)["id"].as_str().unwrap().to_string();
+    receive(&mut monitor);
+    for _ in 0..3 {
+        let _ = store.dispatch(HistoryAction::Perform(CounterAction::Increment));
+        receive(&mut monitor);
+    }
+
+    send(&mut monitor, json!({ "type": "DISPATCH", "action": { "type": "JUMP_TO_STATE", "index": 1 } }));
+    let lifted = payload(&receive(&mut monitor));
+    assert_eq!(1, lifted["currentStateIndex"]);
+    assert_eq!(1, store.get_state().count);
+
+    // sent to the app's own channel this time, the way the extension words it
+    let toggle = json!({ "type": "DISPATCH", "payload": { "type": "TOGGLE_ACTION", "id": 1 } });
+    emit(&mut monitor, "#publish", json!({ "channel": format!("sc-{}", app), "data": toggle }), 4);
+    let lifted = payload(&receive(&mut monitor));
+    assert_eq!(json!([1]), lifted["skippedActionIds"]);
+    assert_eq!(json!({ "count": 2 }), lifted["computedStates"][3]["state"]);
+    assert_eq!(0, store.get_state().count);
+    assert_eq!(2, store.get_state().latest().count);
+}
+
+#[test]
+fn action_ids_survive_trimming() {
+    let (store, _devtools, mut monitor) = served_store();
+    receive(&mut monitor);
+    receive(&mut monitor);
+    for n in 0..60 {
+        let _ = store.dispatch(HistoryAction::Perform(CounterAction::Add(n)));
+        assert_eq!(n + 2, receive(&mut monitor)["nextActionId"].as_u64().unwrap() as usize);
+    }
+
+    // the first 10 were committed, so id 55 is the 45th still recorded
+    send(&mut monitor, json!({ "type": "DISPATCH", "action": { "type": "TOGGLE_ACTION", "id": 55 } }));
+    let lifted = payload(&receive(&mut monitor));
+    assert_eq!(json!([55]), lifted["skippedActionIds"]);
+    assert_eq!(json!(11), lifted["stagedActionIds"][1]);
+    assert_eq!(json!(61), lifted["nextActionId"]);
+    assert_eq!(json!({ "Add": 54 }), lifted["actionsById"]["55"]["action"]);
+    assert!(store.get_state().actions().nth(44).unwrap().skipped);
+
+    // a trimmed one is gone
+    send(&mut monitor, json!({ "type": "DISPATCH", "action": { "type": "TOGGLE_ACTION", "id": 3 } }));
+    send(&mut monitor, json!({ "type": "UPDATE" }));
+    assert_eq!(json!([55]), payload(&receive(&mut monitor))["skippedActionIds"]);
+}
+
+#[test]
+fn monitors_import_lifted_states() {
+    let (store, _devtools, mut monitor) = served_store();
+    receive(&mut monitor);
+    receive(&mut monitor);
+
+    let lifted = json!({
+        "actionsById": {
+            "0": { "type": "PERFORM_ACTION", "action": { "type": "@@INIT" } },
+            "1": { "type": "PERFORM_ACTION", "action": { "Add": 5 } },
+            "2": { "type": "PERFORM_ACTION", "action": "Increment" },
+            "3": { "type": "PERFORM_ACTION", "action": { "Add": 10 } },
+        },
+        "stagedActionIds": [0, 1, 2, 3],
+        "skippedActionIds": [3],
+        "currentStateIndex": 3,
+    });
+    send(&mut monitor, json!({ "type": "DISPATCH", "action": { "type": "IMPORT_STATE", "nextLiftedState": lifted } }));
+    let imported = payload(&receive(&mut monitor));
+    assert_eq!(json!([0, 1, 2, 3]), imported["stagedActionIds"]);
+
+    let state = store.get_state();
+    assert_eq!(6, state.count);
+    assert_eq!(3, state.len());
+    assert!(state.actions().nth(2).unwrap().skipped);
+}
+
+#[test]
+fn monitors_can_dispatch_actions() {
+    let (store, _devtools, mut monitor) = served_store();
+    receive(&mut monitor);
+    receive(&mut monitor);
+
+    send(&mut monitor, json!({ "type": "ACTION", "action": "{\"Add\":3}" }));
+    assert_eq!("ACTION", receive(&mut monitor)["type"]);
+    assert_eq!(3, store.get_state().count);
+}
+
+fn recorded_session() -> DevtoolsSession<Counter> {
+    let store : Store<History<Counter>> = Store::with_state(History::new(Counter { count: 10 }), vec![]);
+    let _ = store.dispatch(HistoryAction::Perform(CounterAction::Add(5)));
+    let _ = store.dispatch(HistoryAction::Perform(CounterAction::Increment));
+    let _ = store.dispatch(HistoryAction::Perform(CounterAction::Add(100)));
+    let _ = store.dispatch(HistoryAction::Toggle(2));
+    let _ = store.dispatch(HistoryAction::JumpTo(1));
+    DevtoolsSession::from_history(&store.get_state())
+}
+
+#[test]
+fn exported_sessions_replay_into_a_fresh_store() {
+    let session = recorded_session();
+    let json = session.to_json().unwrap();
+    let imported = DevtoolsSession::from_json(&json).unwrap();
+    assert_eq!(session, imported);
+
+    let store : Store<History<Counter>> = imported.replay(vec![]).unwrap();
+    let state = store.get_state();
+    assert_eq!(15, state.count);
+    assert_eq!(16, state.latest().count);
+    assert_eq!(10, state.base().count);
+}
+
+#[test]
+fn long_sessions_round_trip_whole() {
+    let store : Store<History<Counter, RecordEverything>> = Store::new(vec![]);
+    for n in 0..60 {
+        let _ = store.dispatch(HistoryAction::Perform(CounterAction::Add(n)));
+    }
+    let _ = store.dispatch(HistoryAction::Toggle(3));
+    let session = DevtoolsSession::from_history(&store.get_state());
+    assert_eq!(60, session.actions.len());
+
+    let json = session.to_json().unwrap();
+    let lifted : Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(json!({ "count": 0 }), lifted["committedState"]);
+    assert_eq!(61, lifted["stagedActionIds"].as_array().unwrap().len());
+    assert_eq!(DevtoolsSession::from_json(&json).unwrap(), session);
+}
+
+#[test]
+fn replay_checks_the_final_state() {
+    let mut session = recorded_session();
+    session.computed_states.last_mut().unwrap().count = 116;
+    let json = session.to_json().unwrap();
+
+    let replayed : Result<Store<History<Counter>>, String> = DevtoolsSession::from_json(&json).unwrap().replay(vec![]);
+    let error = replayed.err().unwrap();
+    assert!(error.contains("count: 16"), "{}", error);
+}
+
+#[test]
+fn sessions_import_the_payload_format() {
+    let lifted = json!({
+        "actionsById": {
+            "0": { "type": "PERFORM_ACTION", "action": { "type": "@@INIT" } },
+            "1": { "type": "PERFORM_ACTION", "action": "Increment" },
+        },
+        "stagedActionIds": [0, 1],
+    });
+    let exported = json!({ "payload": lifted.to_string(), "preloadedState": "{\"count\":4}" });
+
+    let session : DevtoolsSession<Counter> = DevtoolsSession::from_json(&exported.to_string()).unwrap();
+    assert_eq!(Counter { count: 4 }, session.committed_state);
+    assert!(session.computed_states.is_empty());
+    let store : Store<History<Counter>> = session.replay(vec![]).unwrap();
+    assert_eq!(5, store.get_state().count);
+
+    assert!(DevtoolsSession::<Counter>::from_json("{\"hello\":1}").is_err());
+}
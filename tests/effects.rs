@@ -0,0 +1,112 @@
+extern crate redux;
+
+use redux::{EffectReducer, Effects, MiddlewareApi, Store};
+
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug)]
+enum SessionAction {
+    Login(String),
+    ProfileLoaded(String),
+    Logout,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum SessionEffect {
+    LoadProfile(String),
+    ClearCache,
+}
+
+#[derive(Clone, Default)]
+struct Session {
+    user: Option<String>,
+    profile: Option<String>,
+}
+
+impl EffectReducer for Session {
+    type Action = SessionAction;
+    type Error = String;
+    type Effect = SessionEffect;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Vec<Self::Effect>, Self::Error> {
+        match action {
+            SessionAction::Login(user) => {
+                self.user = Some(user.clone());
+                Ok(vec![SessionEffect::LoadProfile(user)])
+            },
+            SessionAction::ProfileLoaded(profile) => {
+                self.profile = Some(profile);
+                Ok(vec![])
+            },
+            SessionAction::Logout => {
+                if self.user.is_none() {
+                    return Err(String::from("not logged in"));
+                }
+                *self = Session::default();
+                Ok(vec![SessionEffect::ClearCache])
+            },
+        }
+    }
+}
+
+type Api<'a> = &'a dyn MiddlewareApi<Effects<Session>>;
+
+#[test]
+fn reducer_effects_can_be_asserted_without_running() {
+    let mut session = Session::default();
+    let effects = session.reduce(SessionAction::Login(String::from("jared"))).unwrap();
+    assert_eq!(vec![SessionEffect::LoadProfile(String::from("jared"))], effects);
+
+    // a store without a runner just collects them
+    let store : Store<Effects<Session>> = Store::new(vec![]);
+    let _ = store.dispatch(SessionAction::Login(String::from("jared")));
+    let _ = store.dispatch(SessionAction::Logout);
+    let expected = [SessionEffect::LoadProfile(String::from("jared")), SessionEffect::ClearCache];
+    assert_eq!(&expected, store.get_state().effects());
+}
+
+#[test]
+fn runner_dispatches_follow_up_actions() {
+    let store = Store::with_effects(vec![], |effect: SessionEffect, api: Api| {
+        if let SessionEffect::LoadProfile(user) = effect {
+            // the reduce has committed, so the runner sees the logged in user
+            assert_eq!(Some(user.clone()), api.get_state().user);
+            let _ = api.dispatch(SessionAction::ProfileLoaded(format!("{}'s profile", user)));
+        }
+    });
+
+    let _ = store.dispatch(SessionAction::Login(String::from("jared")));
+    let state = store.get_state();
+    assert_eq!(Some(String::from("jared's profile")), state.profile);
+    assert_eq!(0, state.effects().len());
+}
+
+#[test]
+fn effects_run_once_and_before_subscriptions() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let runner_log = log.clone();
+    let store = Store::with_effects(vec![], move |effect: SessionEffect, _: Api| {
+        runner_log.lock().unwrap().push(format!("{:?}", effect));
+    });
+    let subscription_log = log.clone();
+    store.subscribe(Box::new(move |_, _| {
+        subscription_log.lock().unwrap().push(String::from("subscription"));
+    }));
+
+    let _ = store.dispatch(SessionAction::Login(String::from("jared")));
+    let _ = store.dispatch(SessionAction::ProfileLoaded(String::from("profile")));
+    let expected = vec!["LoadProfile(\"jared\")", "subscription", "subscription"];
+    assert_eq!(expected, *log.lock().unwrap());
+}
+
+#[test]
+fn failed_reduces_cause_no_effects() {
+    let ran = Arc::new(Mutex::new(0));
+    let counter = ran.clone();
+    let store = Store::with_effects(vec![], move |_: SessionEffect, _: Api| {
+        *counter.lock().unwrap() += 1;
+    });
+
+    assert!(store.dispatch(SessionAction::Logout).is_err());
+    assert_eq!(0, *ran.lock().unwrap());
+}
@@ -0,0 +1,248 @@
+#![cfg(feature = "persistence")]
+extern crate redux;
+
+use redux::{ActionLog, LogError, LogOptions, Migrations, Reducer, Store, SyncPolicy};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum TodoAction {
+    NewTodo { name: String },
+    Complete(usize),
+}
+
+#[derive(Clone, Default, Debug, PartialEq)]
+struct Todos {
+    names: Vec<String>,
+    done: Vec<usize>,
+}
+
+impl Reducer for Todos {
+    type Action = TodoAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            TodoAction::NewTodo { name } => self.names.push(name),
+            TodoAction::Complete(index) => {
+                if index >= self.names.len() {
+                    return Err(format!("no todo {}", index));
+                }
+                self.done.push(index);
+            },
+        }
+
+        Ok(self.clone())
+    }
+}
+
+fn new_todo(name: &str) -> TodoAction {
+    TodoAction::NewTodo { name: String::from(name) }
+}
+
+fn open(path: &Path, options: LogOptions) -> Store<Todos> {
+    Store::from_log(path, options, vec![]).unwrap()
+}
+
+#[test]
+fn state_is_rebuilt_from_the_log() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("todos.log");
+    {
+        let store = open(&path, LogOptions::default());
+        let _ = store.dispatch(new_todo("write tests"));
+        let _ = store.dispatch(new_todo("ship it"));
+        assert!(store.dispatch(TodoAction::Complete(7)).is_err());
+        let _ = store.dispatch(TodoAction::Complete(0));
+    }
+
+    let store = open(&path, LogOptions { sync: SyncPolicy::Batched(10), read_only: false });
+    let state = store.get_state();
+    assert_eq!(vec![String::from("write tests"), String::from("ship it")], state.names);
+    assert_eq!(vec![0], state.done);
+
+    let _ = store.dispatch(TodoAction::Complete(1));
+    drop(store);
+    assert_eq!(4, ActionLog::<TodoAction>::read(&path).unwrap().len());
+}
+
+#[test]
+fn a_torn_last_record_is_dropped() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("todos.log");
+    let _ = open(&path, LogOptions::default()).dispatch(new_todo("survives"));
+    let whole = fs::metadata(&path).unwrap().len();
+
+    // a record that claims 100 bytes but only got 3 of them written
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&[100, 0, 0, 0, 1, 2, 3, 4, b'{', b'"', b'N']).unwrap();
+    drop(file);
+
+    let read_only = open(&path, LogOptions { sync: SyncPolicy::Never, read_only: true });
+    assert_eq!(1, read_only.get_state().names.len());
+    let _ = read_only.dispatch(new_todo("not logged"));
+    assert_eq!(whole + 11, fs::metadata(&path).unwrap().len());
+
+    let store = open(&path, LogOptions::default());
+    assert_eq!(vec![String::from("survives")], store.get_state().names);
+    assert_eq!(whole, fs::metadata(&path).unwrap().len());
+    let _ = store.dispatch(new_todo("after the tear"));
+    drop(store);
+    assert_eq!(2, open(&path, LogOptions::default()).get_state().names.len());
+}
+
+#[test]
+fn a_bad_checksum_before_the_end_is_corruption() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("todos.log");
+    let mut log = ActionLog::open(&path, SyncPolicy::Never).unwrap();
+    log.append(&new_todo("first")).unwrap();
+    let second = log.append(&new_todo("second")).unwrap();
+    log.append(&new_todo("third")).unwrap();
+    drop(log);
+
+    let mut bytes = fs::read(&path).unwrap();
+    bytes[second as usize + 10] ^= 0xff;
+    fs::write(&path, bytes).unwrap();
+
+    match Store::<Todos>::from_log(&path, LogOptions::default(), vec![]) {
+        Err(LogError::Corrupt { offset }) => assert_eq!(second, offset),
+        other => panic!("expected corruption, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn a_length_past_the_end_before_other_records_is_corruption() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("todos.log");
+    let mut log = ActionLog::open(&path, SyncPolicy::Never).unwrap();
+    log.append(&new_todo("first")).unwrap();
+    log.append(&new_todo("second")).unwrap();
+    drop(log);
+
+    // the first record now claims to run past the end of the file
+    let mut bytes = fs::read(&path).unwrap();
+    bytes[..4].copy_from_slice(&1000u32.to_le_bytes());
+    fs::write(&path, &bytes).unwrap();
+
+    match ActionLog::<TodoAction>::open(&path, SyncPolicy::Never) {
+        Err(LogError::Corrupt { offset }) => assert_eq!(0, offset),
+        other => panic!("expected corruption, got {:?}", other.err()),
+    }
+    assert!(Store::<Todos>::from_log(&path, LogOptions::default(), vec![]).is_err());
+    assert_eq!(bytes, fs::read(&path).unwrap());
+}
+
+#[test]
+fn a_zero_filled_tail_is_dropped() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("todos.log");
+    let _ = open(&path, LogOptions::default()).dispatch(new_todo("survives"));
+    let whole = fs::metadata(&path).unwrap().len();
+
+    // what a crash can leave after the filesystem grew the file
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&[0; 16]).unwrap();
+    drop(file);
+
+    let store = open(&path, LogOptions::default());
+    assert_eq!(vec![String::from("survives")], store.get_state().names);
+    assert_eq!(whole, fs::metadata(&path).unwrap().len());
+}
+
+#[test]
+fn an_empty_record_before_the_end_is_corruption() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("todos.log");
+    let mut log = ActionLog::open(&path, SyncPolicy::Never).unwrap();
+    log.append(&new_todo("first")).unwrap();
+    drop(log);
+
+    // an empty record, whose checksum of 0 is right, in front of it
+    let mut bytes = vec![0; 8];
+    bytes.extend(fs::read(&path).unwrap());
+    fs::write(&path, bytes).unwrap();
+
+    match ActionLog::<TodoAction>::read(&path) {
+        Err(LogError::Corrupt { offset }) => assert_eq!(0, offset),
+        other => panic!("expected corruption, got {:?}", other),
+    }
+}
+
+#[test]
+fn actions_that_no_longer_reduce_fail_the_replay() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("todos.log");
+    let mut log = ActionLog::open(&path, SyncPolicy::EveryAction).unwrap();
+    log.append(&new_todo("only")).unwrap();
+    let bad = log.append(&TodoAction::Complete(3)).unwrap();
+    drop(log);
+
+    match Store::<Todos>::from_log(&path, LogOptions::default(), vec![]) {
+        Err(LogError::Replay { offset, message }) => {
+            assert_eq!(bad, offset);
+            assert_eq!("no todo 3", message);
+        },
+        other => panic!("expected a replay error, got {:?}", other.err()),
+    }
+}
+
+// version 0 of `NewTodo` called its name `text`
+fn upcasters() -> Migrations {
+    Migrations::new(1).add(0, |mut action| {
+        if let Some(new_todo) = action.get_mut("NewTodo") {
+            match new_todo.as_object_mut().and_then(|fields| fields.remove("text")) {
+                Some(text) => new_todo["name"] = text,
+                None => return Err(String::from("no text")),
+            }
+        }
+        Ok(action)
+    })
+}
+
+#[test]
+fn old_actions_are_upcast_when_replayed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("todos.log");
+    let mut old = ActionLog::<Value>::open(&path, SyncPolicy::EveryAction).unwrap();
+    old.append(&json!({ "NewTodo": { "text": "from version 0" } })).unwrap();
+    old.append(&json!({ "Complete": 0 })).unwrap();
+    drop(old);
+
+    let store = Store::<Todos>::from_versioned_log(&path, LogOptions::default(), &upcasters(), vec![]).unwrap();
+    assert_eq!(vec![String::from("from version 0")], store.get_state().names);
+    let _ = store.dispatch(new_todo("from version 1"));
+    drop(store);
+
+    let actions = ActionLog::<TodoAction>::read_upcasting(&path, &upcasters()).unwrap();
+    assert_eq!(vec![new_todo("from version 0"), TodoAction::Complete(0), new_todo("from version 1")],
+               actions.into_iter().map(|(_, action)| action).collect::<Vec<_>>());
+
+    // and an older app can't make sense of the newer action
+    match ActionLog::<Value>::read(&path) {
+        Err(LogError::Upcast { message, .. }) => assert_eq!("The action is from schema version 1, but this is only version 0", message),
+        other => panic!("expected an upcast error, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn actions_that_fail_to_upcast_fail_the_replay() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("todos.log");
+    let mut old = ActionLog::<Value>::open(&path, SyncPolicy::EveryAction).unwrap();
+    old.append(&json!({ "NewTodo": { "text": "fine" } })).unwrap();
+    let bad = old.append(&json!({ "NewTodo": { "label": "not fine" } })).unwrap();
+    drop(old);
+
+    match Store::<Todos>::from_versioned_log(&path, LogOptions::default(), &upcasters(), vec![]) {
+        Err(LogError::Upcast { offset, message }) => {
+            assert_eq!(bad, offset);
+            assert_eq!("The migration from schema version 0 to 1 failed: no text", message);
+        },
+        other => panic!("expected an upcast error, got {:?}", other.err()),
+    }
+}
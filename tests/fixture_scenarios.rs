@@ -0,0 +1,41 @@
+// every fixture under tests/fixtures/todos as a test of its own
+extern crate redux;
+
+use redux::testing::Fixtures;
+use redux::Reducer;
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize)]
+enum TodoAction {
+    NewTodo { name: String },
+    Complete(usize),
+}
+
+#[derive(Clone, Default, Debug, PartialEq, Deserialize)]
+struct TodoList {
+    names: Vec<String>,
+    done: Vec<usize>,
+}
+
+impl Reducer for TodoList {
+    type Action = TodoAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            TodoAction::NewTodo { name } => self.names.push(name),
+            TodoAction::Complete(index) => {
+                if index >= self.names.len() {
+                    return Err(format!("no todo {}", index));
+                }
+                self.done.push(index);
+            },
+        }
+
+        Ok(self.clone())
+    }
+}
+
+fn main() {
+    Fixtures::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/todos")).test_main::<TodoList>();
+}
@@ -0,0 +1,80 @@
+#![cfg(feature = "fixtures")]
+extern crate redux;
+
+use redux::testing::{run_fixture, Fixtures};
+use redux::Reducer;
+use serde::Deserialize;
+
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, Deserialize)]
+enum TodoAction {
+    NewTodo { name: String },
+    Complete(usize),
+}
+
+#[derive(Clone, Default, Debug, PartialEq, Deserialize)]
+struct TodoList {
+    names: Vec<String>,
+    done: Vec<usize>,
+}
+
+impl Reducer for TodoList {
+    type Action = TodoAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            TodoAction::NewTodo { name } => self.names.push(name),
+            TodoAction::Complete(index) => {
+                if index >= self.names.len() {
+                    return Err(format!("no todo {}", index));
+                }
+                self.done.push(index);
+            },
+        }
+
+        Ok(self.clone())
+    }
+}
+
+fn fixtures(dir: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(dir)
+}
+
+#[test]
+fn every_fixture_passes() {
+    let todos = Fixtures::new(fixtures("todos"));
+    assert_eq!(3, todos.files().unwrap().len());
+    todos.assert_all::<TodoList>();
+}
+
+#[test]
+fn every_failure_is_reported() {
+    let failures = Fixtures::new(fixtures("broken")).run::<TodoList>().unwrap();
+    let failed : Vec<String> = failures.iter().map(|failure| failure.path.file_name().unwrap().to_string_lossy().into_owned()).collect();
+    assert_eq!(vec!["unknown_action.yaml", "wrong_error.json", "wrong_state.yaml"], failed);
+
+    assert!(failures[0].message.starts_with("`actions` can't be read: unknown variant `Delete`"), "{}", failures[0]);
+    assert!(failures[1].message.starts_with("expected an action to fail with \"no todo 0\", but every one succeeded after action #0"), "{}", failures[1]);
+    assert!(failures[2].message.starts_with("the state after action #1 (NewTodo { name: \"b\" }) isn't the expected one"), "{}", failures[2]);
+    assert!(failures[2].message.contains("\n-         \"c\",\n+         \"b\",\n"), "{}", failures[2]);
+}
+
+#[test]
+#[should_panic(expected = "3 of 3 fixtures failed")]
+fn assert_all_panics_with_every_failure() {
+    Fixtures::new(fixtures("broken")).assert_all::<TodoList>();
+}
+
+#[test]
+fn a_single_fixture_can_be_run() {
+    assert_eq!(Ok(()), run_fixture::<TodoList>(&fixtures("todos").join("new_todos.yaml")));
+}
+
+#[test]
+fn every_fixture_is_a_test_of_its_own() {
+    let trials = Fixtures::new(fixtures("broken")).trials::<TodoList>().unwrap();
+    let names : Vec<&str> = trials.iter().map(|trial| trial.name()).collect();
+    assert_eq!(vec!["unknown_action.yaml", "wrong_error.json", "wrong_state.yaml"], names);
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "snapshot-testing")]
+extern crate redux;
+
+use redux::testing::Golden;
+use redux::Reducer;
+use serde::Serialize;
+
+use std::fs;
+
+#[derive(Clone, Debug, Serialize)]
+enum TodoAction {
+    NewTodo { name: String },
+    Complete(usize),
+}
+
+#[derive(Clone, Default, Serialize)]
+struct TodoList {
+    names: Vec<String>,
+    done: Vec<usize>,
+}
+
+impl Reducer for TodoList {
+    type Action = TodoAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            TodoAction::NewTodo { name } => self.names.push(name),
+            TodoAction::Complete(index) => {
+                if index >= self.names.len() {
+                    return Err(format!("no todo {}", index));
+                }
+                self.done.push(index);
+            },
+        }
+
+        Ok(self.clone())
+    }
+}
+
+fn script() -> Vec<TodoAction> {
+    vec![
+        TodoAction::NewTodo { name: String::from("write tests") },
+        TodoAction::Complete(3),
+        TodoAction::NewTodo { name: String::from("ship it") },
+        TodoAction::Complete(0),
+    ]
+}
+
+fn snapshot(name: &str) -> String {
+    format!("{}/tests/snapshots/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn final_states_match_their_snapshot() {
+    Golden::new(TodoList::default()).check(snapshot("todos.json"), script());
+}
+
+#[test]
+fn intermediate_states_match_their_snapshot() {
+    Golden::new(TodoList::default()).with_intermediate_states().check(snapshot("todos_steps.json"), script());
+}
+
+#[test]
+#[should_panic(expected = "-     \"write tests\"\n+     \"write tests\",\n+     \"ship it\"\n")]
+fn changes_are_shown_as_a_diff() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("todos.json");
+    Golden::new(TodoList::default()).updating(true).check(&path, script()[..2].to_vec());
+    Golden::new(TodoList::default()).updating(false).check(&path, script());
+}
+
+#[test]
+fn updating_writes_the_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("new").join("todos.json");
+    Golden::new(TodoList::default()).updating(true).check(&path, script());
+    assert_eq!(fs::read_to_string(snapshot("todos.json")).unwrap(), fs::read_to_string(&path).unwrap());
+    Golden::new(TodoList::default()).updating(false).check(&path, script());
+}
+
+#[test]
+#[should_panic(expected = "run with REDUX_UPDATE_SNAPSHOTS=1 to write it")]
+fn missing_snapshots_fail() {
+    let dir = tempfile::tempdir().unwrap();
+    Golden::new(TodoList::default()).updating(false).check(dir.path().join("missing.json"), script());
+}
@@ -0,0 +1,224 @@
+extern crate redux;
+
+use redux::{History, HistoryAction, HistoryConfig, RecordEverything, Reducer, Store};
+
+use std::cell::Cell;
+
+#[derive(Clone, Debug, PartialEq)]
+enum BankAction {
+    Deposit(usize),
+    Withdraw(usize),
+}
+
+#[derive(Clone, Default, Debug, PartialEq)]
+struct Account {
+    balance: usize,
+}
+
+impl Reducer for Account {
+    type Action = BankAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            BankAction::Deposit(amount) => self.balance += amount,
+            BankAction::Withdraw(amount) => {
+                if amount > self.balance {
+                    return Err(String::from("insufficient funds"));
+                }
+                self.balance -= amount;
+            },
+        }
+
+        Ok(self.clone())
+    }
+}
+
+struct Sparse;
+
+impl HistoryConfig for Sparse {
+    fn max_actions() -> Option<usize> {
+        Some(4)
+    }
+
+    fn checkpoint_every() -> usize {
+        3
+    }
+}
+
+thread_local! {
+    static REDUCES : Cell<usize> = const { Cell::new(0) };
+}
+
+// counts its reduces, so a test can tell which states were kept
+#[derive(Clone, Default, Debug, PartialEq)]
+struct Counted {
+    count: usize,
+}
+
+impl Reducer for Counted {
+    type Action = usize;
+    type Error = String;
+
+    fn reduce(&mut self, n: Self::Action) -> Result<Self, Self::Error> {
+        REDUCES.with(|reduces| reduces.set(reduces.get() + 1));
+        self.count += n;
+        Ok(self.clone())
+    }
+}
+
+struct EveryThird;
+
+impl HistoryConfig for EveryThird {
+    fn max_actions() -> Option<usize> {
+        Some(6)
+    }
+
+    fn checkpoint_every() -> usize {
+        3
+    }
+}
+
+fn perform(history: &mut History<Account, Sparse>, action: BankAction) {
+    let _ = history.reduce(HistoryAction::Perform(action));
+}
+
+#[test]
+fn jump_to_shows_earlier_states() {
+    let store : Store<History<Account>> = Store::new(vec![]);
+    for amount in 1..4 {
+        let _ = store.dispatch(HistoryAction::Perform(BankAction::Deposit(amount)));
+    }
+
+    let _ = store.dispatch(HistoryAction::JumpTo(1));
+    assert_eq!(1, store.get_state().balance);
+    assert!(!store.get_state().is_at_latest());
+
+    // new actions are recorded but don't move the view
+    let _ = store.dispatch(HistoryAction::Perform(BankAction::Deposit(10)));
+    let state = store.get_state();
+    assert_eq!(1, state.balance);
+    assert_eq!(16, state.latest().balance);
+    assert_eq!(4, state.len());
+
+    let _ = store.dispatch(HistoryAction::JumpTo(4));
+    assert_eq!(16, store.get_state().balance);
+    let _ = store.dispatch(HistoryAction::JumpTo(9));
+    assert_eq!(16, store.get_state().balance);
+}
+
+#[test]
+fn skipping_recomputes_later_states() {
+    let store : Store<History<Account>> = Store::new(vec![]);
+    let _ = store.dispatch(HistoryAction::Perform(BankAction::Deposit(5)));
+    let _ = store.dispatch(HistoryAction::Perform(BankAction::Withdraw(3)));
+    let _ = store.dispatch(HistoryAction::Perform(BankAction::Deposit(1)));
+
+    let _ = store.dispatch(HistoryAction::Toggle(0));
+    let state = store.get_state();
+    assert_eq!(1, state.balance);
+    let errors : Vec<_> = state.actions().map(|recorded| recorded.error.clone()).collect();
+    assert_eq!(vec![None, Some(String::from("insufficient funds")), None], errors);
+
+    let _ = store.dispatch(HistoryAction::SetSkipped(0, false));
+    let state = store.get_state();
+    assert_eq!(3, state.balance);
+    assert!(state.actions().all(|recorded| recorded.error.is_none() && !recorded.skipped));
+}
+
+#[test]
+fn failed_actions_are_not_recorded() {
+    let store : Store<History<Account>> = Store::new(vec![]);
+    assert!(store.dispatch(HistoryAction::Perform(BankAction::Withdraw(1))).is_err());
+    assert!(store.get_state().is_empty());
+}
+
+#[test]
+fn commit_makes_the_shown_state_the_base() {
+    let store : Store<History<Account>> = Store::new(vec![]);
+    let _ = store.dispatch(HistoryAction::Perform(BankAction::Deposit(5)));
+    let _ = store.dispatch(HistoryAction::Perform(BankAction::Deposit(7)));
+    let _ = store.dispatch(HistoryAction::JumpTo(1));
+    let _ = store.dispatch(HistoryAction::Commit);
+
+    let state = store.get_state();
+    assert_eq!(5, state.base().balance);
+    assert_eq!(5, state.balance);
+    assert!(state.is_empty());
+    assert_eq!(0, state.current());
+}
+
+#[test]
+fn checkpoints_and_limits_give_the_same_states() {
+    let mut history : History<Account, Sparse> = History::new(Account { balance: 100 });
+    for amount in 1..7 {
+        perform(&mut history, BankAction::Deposit(amount));
+    }
+
+    // the first two deposits were committed into the base
+    assert_eq!(4, history.len());
+    assert_eq!(103, history.base().balance);
+    let balances : Vec<_> = (0..5).map(|index| history.state_at(index).unwrap().balance).collect();
+    assert_eq!(vec![103, 106, 110, 115, 121], balances);
+    assert_eq!(None, history.state_at(5));
+
+    let _ = history.reduce(HistoryAction::Toggle(1));
+    let _ = history.reduce(HistoryAction::JumpTo(3));
+    assert_eq!(111, history.balance);
+    assert_eq!(117, history.latest().balance);
+}
+
+#[test]
+fn only_recent_actions_are_kept_by_default() {
+    let store : Store<History<Account>> = Store::new(vec![]);
+    for _ in 0..60 {
+        let _ = store.dispatch(HistoryAction::Perform(BankAction::Deposit(1)));
+    }
+    let state = store.get_state();
+    assert_eq!(50, state.len());
+    assert_eq!(10, state.base().balance);
+    assert_eq!(60, state.balance);
+    assert_eq!(10, state.trimmed());
+    let _ = store.dispatch(HistoryAction::Commit);
+    assert_eq!(0, store.get_state().trimmed());
+
+    let mut everything : History<Account, RecordEverything> = History::default();
+    for _ in 0..60 {
+        let _ = everything.reduce(HistoryAction::Perform(BankAction::Deposit(1)));
+    }
+    assert_eq!(60, everything.len());
+}
+
+#[test]
+fn import_replaces_the_recorded_actions() {
+    let store : Store<History<Account>> = Store::new(vec![]);
+    let _ = store.dispatch(HistoryAction::Perform(BankAction::Deposit(1)));
+    let _ = store.dispatch(HistoryAction::Import {
+        actions: vec![BankAction::Deposit(5), BankAction::Withdraw(9), BankAction::Deposit(2)],
+        skipped: vec![2],
+        current: 3,
+    });
+
+    let state = store.get_state();
+    assert_eq!(5, state.balance);
+    assert_eq!(3, state.len());
+    let errors : Vec<_> = state.actions().map(|recorded| recorded.error.is_some()).collect();
+    assert_eq!(vec![false, true, false], errors);
+}
+
+#[test]
+fn checkpoints_survive_trimming() {
+    let mut history : History<Counted, EveryThird> = History::default();
+    for _ in 0..20 {
+        let _ = history.reduce(HistoryAction::Perform(1));
+    }
+    assert_eq!(14, history.trimmed());
+
+    // the states after the 15th and 18th actions are kept, and the latest one
+    let replayed : Vec<_> = (0..7).map(|index| {
+        REDUCES.with(|reduces| reduces.set(0));
+        assert_eq!(14 + index, history.state_at(index).unwrap().count);
+        REDUCES.with(|reduces| reduces.get())
+    }).collect();
+    assert_eq!(vec![0, 0, 1, 2, 0, 1, 0], replayed);
+}
@@ -3,7 +3,8 @@ extern crate redux;
 use redux::{Reducer, Store, Middleware, DispatchFunc};
 
 use std::collections::HashMap;
-use std::sync::{Mutex, Arc};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Arc, OnceLock};
 use std::{thread, time};
 use std::default::Default;
 
@@ -12,6 +13,7 @@ enum TodoAction {
     NewTodo { name: String }
 }
 
+#[allow(dead_code)]
 #[derive(Clone)]
 struct Todo {
     name: String,
@@ -63,7 +65,7 @@ impl Reducer for TodoStore {
     fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
         match action {
             TodoAction::NewTodo { name } => {
-                let todo = Todo { name: name, id: self.ticket(), };
+                let todo = Todo { name, id: self.ticket(), };
                 self.push(todo);
             },
             // _ => {}
@@ -164,8 +166,8 @@ struct Counter {
 impl Counter {
     fn new(before_count: Arc<Mutex<usize>>, after_count: Arc<Mutex<usize>>) -> Counter {
         Counter {
-            before_count: before_count,
-            after_count: after_count,
+            before_count,
+            after_count,
         }
     }
 }
@@ -242,3 +244,116 @@ fn subscribe_and_cancel_during_subscription_callback() {
     let _ = store.dispatch(action.clone());
     assert_eq!(5, store.get_state().len());
 }
+
+static REDUCING : AtomicBool = AtomicBool::new(false);
+static RELEASED : AtomicBool = AtomicBool::new(false);
+
+// a `true` action holds its reduce open until `RELEASED` is set
+#[derive(Clone, Default)]
+struct Slow {
+    count: usize,
+}
+
+impl Reducer for Slow {
+    type Action = bool;
+    type Error = String;
+
+    fn reduce(&mut self, slow: Self::Action) -> Result<Self, Self::Error> {
+        if slow {
+            REDUCING.store(true, Ordering::SeqCst);
+            while !RELEASED.load(Ordering::SeqCst) {
+                thread::yield_now();
+            }
+        }
+        self.count += 1;
+
+        Ok(self.clone())
+    }
+}
+
+#[test]
+fn dispatches_wait_for_another_threads_reduce() {
+    let store : Arc<Store<Slow>> = Arc::new(Store::new(vec![]));
+    let slow = {
+        let store = store.clone();
+        thread::spawn(move || store.dispatch(true))
+    };
+    while !REDUCING.load(Ordering::SeqCst) {
+        thread::yield_now();
+    }
+    let release = thread::spawn(|| {
+        thread::sleep(time::Duration::from_millis(20));
+        RELEASED.store(true, Ordering::SeqCst);
+    });
+
+    // the other thread is reducing, so this one waits its turn
+    assert!(store.dispatch(false).is_ok());
+    assert!(slow.join().unwrap().is_ok());
+    release.join().unwrap();
+    assert_eq!(2, store.get_state().count);
+}
+
+#[derive(Clone, Default)]
+struct Picky {
+    count: usize,
+}
+
+impl Reducer for Picky {
+    type Action = bool;
+    type Error = String;
+
+    fn reduce(&mut self, ok: Self::Action) -> Result<Self, Self::Error> {
+        if !ok {
+            return Err(String::from("rejected"));
+        }
+        self.count += 1;
+
+        Ok(self.clone())
+    }
+}
+
+// reduces `true` by dispatching `false` into the store it's in
+#[derive(Clone, Default)]
+struct Reentrant {
+    error: Option<String>,
+}
+
+static REENTRANT : OnceLock<Store<Reentrant>> = OnceLock::new();
+
+impl Reducer for Reentrant {
+    type Action = bool;
+    type Error = String;
+
+    fn reduce(&mut self, reenter: Self::Action) -> Result<Self, Self::Error> {
+        if reenter {
+            self.error = REENTRANT.get().unwrap().dispatch(false).err();
+        }
+
+        Ok(self.clone())
+    }
+}
+
+#[test]
+fn dispatch_from_inside_a_reduce_fails() {
+    let store = REENTRANT.get_or_init(|| Store::new(vec![]));
+    store.dispatch(true).unwrap();
+    assert_eq!(Some(String::from("Error during dispatch: Can't dispatch during a reduce.")), store.get_state().error);
+
+    // and the store is still usable afterwards
+    store.dispatch(false).unwrap();
+}
+
+#[test]
+fn dispatch_after_a_reducer_error() {
+    let store : Store<Picky> = Store::new(vec![]);
+    assert!(store.dispatch(false).is_err());
+    assert!(store.dispatch(true).is_ok());
+    assert_eq!(1, store.get_state().count);
+}
+
+#[test]
+fn start_from_a_given_state() {
+    let store : Store<Picky> = Store::with_state(Picky { count: 41 }, vec![]);
+    let _ = store.dispatch(true);
+    assert_eq!(42, store.get_state().count);
+}
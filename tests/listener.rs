@@ -0,0 +1,221 @@
+extern crate redux;
+
+use redux::{Cancelled, DispatchFunc, Listener, ListenerMiddleware, Middleware, MiddlewareApi, Reducer, Store};
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Clone, Debug, PartialEq)]
+enum SearchAction {
+    Type(String),
+    Results(String),
+    Connect,
+    Tick,
+}
+
+#[derive(Clone, Default, Debug)]
+struct Search {
+    query: String,
+    results: Vec<String>,
+    connected: bool,
+    ticks: usize,
+}
+
+impl Reducer for Search {
+    type Action = SearchAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            SearchAction::Type(query) => self.query = query,
+            SearchAction::Results(results) => self.results.push(results),
+            SearchAction::Connect => self.connected = true,
+            SearchAction::Tick => self.ticks += 1,
+        }
+
+        Ok(self.clone())
+    }
+}
+
+fn attached_store() -> (Arc<Store<Search>>, ListenerMiddleware<Search>) {
+    let listeners = ListenerMiddleware::new();
+    let store = Arc::new(Store::new(vec![Box::new(listeners.clone())]));
+    listeners.attach(&store);
+    (store, listeners)
+}
+
+fn is_typing(action: &SearchAction) -> bool {
+    matches!(action, SearchAction::Type(_))
+}
+
+#[test]
+fn action_listeners_dispatch_follow_ups() {
+    let (store, listeners) = attached_store();
+    listeners.add(Listener::on_action(is_typing, |action, api| {
+        if let SearchAction::Type(query) = action {
+            let _ = api.dispatch(SearchAction::Results(format!("results for {}", query)));
+        }
+    }));
+
+    let _ = store.dispatch(SearchAction::Type(String::from("rust")));
+    listeners.wait_for_handlers();
+    assert_eq!(vec![String::from("results for rust")], store.get_state().results);
+}
+
+#[test]
+fn state_listeners_fire_when_the_predicate_flips() {
+    let (store, listeners) = attached_store();
+    let fired = Arc::new(Mutex::new(0));
+    let counter = fired.clone();
+    listeners.add(Listener::on_state(|state: &Search| state.ticks >= 2, move |_, _| {
+        *counter.lock().unwrap() += 1;
+    }));
+
+    for _ in 0..5 {
+        let _ = store.dispatch(SearchAction::Tick);
+    }
+    listeners.wait_for_handlers();
+    assert_eq!(1, *fired.lock().unwrap());
+}
+
+// connects before passing on the first search, from inside the listeners'
+// `next`
+struct ConnectFirst;
+
+impl Middleware<Search> for ConnectFirst {
+    fn dispatch(&self, store: &Store<Search>, action: SearchAction, next: &DispatchFunc<Search>) -> Result<Search, String> {
+        if is_typing(&action) && !store.get_state().connected {
+            store.dispatch(SearchAction::Connect)?;
+        }
+        next(store, action)
+    }
+}
+
+#[test]
+fn state_listeners_compare_with_the_state_the_action_was_reduced_from() {
+    let listeners = ListenerMiddleware::new();
+    let store = Arc::new(Store::new(vec![Box::new(ConnectFirst), Box::new(listeners.clone())]));
+    listeners.attach(&store);
+    let fired = Arc::new(Mutex::new(vec![]));
+    let seen = fired.clone();
+    listeners.add(Listener::on_state(|state: &Search| state.connected, move |action, _| {
+        seen.lock().unwrap().push(action.clone());
+    }));
+
+    // `Connect` connected, `Type` was reduced from a connected state
+    let _ = store.dispatch(SearchAction::Type(String::from("rust")));
+    listeners.wait_for_handlers();
+    assert_eq!(vec![SearchAction::Connect], *fired.lock().unwrap());
+}
+
+#[test]
+fn predicates_can_add_listeners() {
+    let (store, listeners) = attached_store();
+    let fired = Arc::new(Mutex::new(0));
+    let l = listeners.clone();
+    let counter = fired.clone();
+    listeners.add(Listener::on_action(move |action| {
+        if *action == SearchAction::Connect {
+            let counter = counter.clone();
+            l.add(Listener::on_action(|_| true, move |_, _| *counter.lock().unwrap() += 1));
+        }
+        false
+    }, |_, _| {}));
+
+    let _ = store.dispatch(SearchAction::Connect);
+    let _ = store.dispatch(SearchAction::Tick);
+    listeners.wait_for_handlers();
+    assert_eq!(1, *fired.lock().unwrap());
+}
+
+#[test]
+fn condition_waits_for_the_state() {
+    let (store, listeners) = attached_store();
+    let outcome = Arc::new(Mutex::new(vec![]));
+    let seen = outcome.clone();
+    listeners.add(Listener::on_action(is_typing, move |_, api| {
+        let connected = api.condition(|state: &Search| state.connected, Some(Duration::from_secs(5)));
+        seen.lock().unwrap().push(connected);
+        if connected == Ok(true) {
+            let _ = api.dispatch(SearchAction::Results(api.get_state().query));
+        }
+    }));
+
+    let _ = store.dispatch(SearchAction::Type(String::from("rust")));
+    let _ = store.dispatch(SearchAction::Tick);
+    let _ = store.dispatch(SearchAction::Connect);
+    listeners.wait_for_handlers();
+    assert_eq!(vec![Ok(true)], *outcome.lock().unwrap());
+    assert_eq!(vec![String::from("rust")], store.get_state().results);
+}
+
+#[test]
+fn condition_times_out() {
+    let (store, listeners) = attached_store();
+    let outcome = Arc::new(Mutex::new(vec![]));
+    let seen = outcome.clone();
+    listeners.add(Listener::on_action(is_typing, move |_, api| {
+        seen.lock().unwrap().push(api.condition(|state: &Search| state.connected, Some(Duration::from_millis(10))));
+    }));
+
+    let _ = store.dispatch(SearchAction::Type(String::from("rust")));
+    listeners.wait_for_handlers();
+    assert_eq!(vec![Ok(false)], *outcome.lock().unwrap());
+}
+
+#[test]
+fn cancel_active_listeners_debounces() {
+    let (store, listeners) = attached_store();
+    let outcome = Arc::new(Mutex::new(vec![]));
+    let seen = outcome.clone();
+    listeners.add(Listener::on_action(is_typing, move |action, api| {
+        api.cancel_active_listeners();
+        let waited = api.delay(Duration::from_millis(200));
+        seen.lock().unwrap().push(waited);
+        if waited.is_ok() {
+            let _ = api.dispatch(SearchAction::Results(format!("{:?}", action)));
+        }
+    }));
+
+    for query in ["r", "ru", "rust"].iter() {
+        let _ = store.dispatch(SearchAction::Type(String::from(*query)));
+    }
+    listeners.wait_for_handlers();
+
+    let mut outcome = outcome.lock().unwrap().clone();
+    outcome.sort_by_key(|waited| waited.is_ok());
+    assert_eq!(vec![Err(Cancelled), Err(Cancelled), Ok(())], outcome);
+    assert_eq!(vec![String::from("Type(\"rust\")")], store.get_state().results);
+}
+
+#[test]
+fn listeners_are_added_and_removed_at_runtime() {
+    let (store, listeners) = attached_store();
+    let fired = Arc::new(Mutex::new(0));
+    let counter = fired.clone();
+    let id = listeners.add(Listener::on_action(|action| *action == SearchAction::Tick, move |_, _| {
+        *counter.lock().unwrap() += 1;
+    }));
+
+    let _ = store.dispatch(SearchAction::Tick);
+    listeners.wait_for_handlers();
+    assert!(listeners.remove(id, false));
+    assert!(!listeners.remove(id, false));
+    let _ = store.dispatch(SearchAction::Tick);
+    listeners.wait_for_handlers();
+
+    assert_eq!(1, *fired.lock().unwrap());
+}
+
+#[test]
+fn nothing_runs_before_the_store_is_attached() {
+    let listeners = ListenerMiddleware::new();
+    let store : Store<Search> = Store::new(vec![Box::new(listeners.clone())]);
+    listeners.add(Listener::on_action(|_| true, |_, api| {
+        let _ = api.dispatch(SearchAction::Connect);
+    }));
+
+    let _ = store.dispatch(SearchAction::Tick);
+    listeners.wait_for_handlers();
+    assert!(!store.get_state().connected);
+}
@@ -0,0 +1,327 @@
+#![cfg(feature = "logger")]
+
+extern crate redux;
+
+use redux::{ActionKind, DispatchFunc, KindFilter, Listener, ListenerMiddleware, LoggerMiddleware, Middleware, MiddlewareApi, Reducer, StateLogging, Store, Thunk, ThunkAction, ThunkMiddleware};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, Once};
+use std::thread::{self, ThreadId};
+
+// everything logged, by thread, so tests running in parallel don't see each
+// other's lines
+struct Captured {
+    lines: Mutex<Vec<(ThreadId, Level, String)>>,
+}
+
+impl Log for Captured {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target() == "redux"
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.lines.lock().unwrap().push((thread::current().id(), record.level(), record.args().to_string()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURED : Captured = Captured { lines: Mutex::new(vec![]) };
+static INSTALLED : Once = Once::new();
+
+fn captured() -> &'static Captured {
+    INSTALLED.call_once(|| {
+        log::set_logger(&CAPTURED).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+    });
+    &CAPTURED
+}
+
+// takes what this thread logged so far
+fn lines() -> Vec<(Level, String)> {
+    let id = thread::current().id();
+    let mut lines = captured().lines.lock().unwrap();
+    let (mine, others) = lines.drain(..).partition(|(thread, _, _)| *thread == id);
+    *lines = others;
+    mine.into_iter().map(|(_, level, line)| (level, line)).collect()
+}
+
+fn texts() -> Vec<String> {
+    lines().into_iter().map(|(_, line)| line).collect()
+}
+
+#[derive(Clone, Debug)]
+enum TodoAction {
+    NewTodo { name: String },
+    Login { password: String },
+    Complete(usize),
+    Thunk(Thunk<TodoList>),
+}
+
+impl ThunkAction<TodoList> for TodoAction {
+    fn into_thunk(self) -> Result<Thunk<TodoList>, Self> {
+        match self {
+            TodoAction::Thunk(thunk) => Ok(thunk),
+            action => Err(action),
+        }
+    }
+}
+
+impl ActionKind for TodoAction {
+    fn kind(&self) -> &'static str {
+        match self {
+            TodoAction::NewTodo { .. } => "NewTodo",
+            TodoAction::Login { .. } => "Login",
+            TodoAction::Complete(_) => "Complete",
+            TodoAction::Thunk(_) => "Thunk",
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+struct TodoList {
+    names: Vec<String>,
+    password: String,
+}
+
+impl Reducer for TodoList {
+    type Action = TodoAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            TodoAction::NewTodo { name } => self.names.push(name),
+            TodoAction::Login { password } => self.password = password,
+            TodoAction::Complete(index) if index >= self.names.len() => return Err(format!("no todo {}", index)),
+            TodoAction::Complete(_) => {},
+            TodoAction::Thunk(_) => return Err(String::from("a thunk reached the reducer")),
+        }
+
+        Ok(self.clone())
+    }
+}
+
+fn new_todo(name: &str) -> TodoAction {
+    TodoAction::NewTodo { name: String::from(name) }
+}
+
+fn store_with(logger: LoggerMiddleware<TodoList>) -> Store<TodoList> {
+    captured();
+    Store::new(vec![Box::new(ThunkMiddleware::new()), Box::new(logger)])
+}
+
+#[test]
+fn dispatches_are_logged_with_their_timing() {
+    let store = store_with(LoggerMiddleware::new());
+    let _ = store.dispatch(new_todo("a"));
+    let _ = store.dispatch(TodoAction::Complete(3));
+
+    let lines = lines();
+    assert_eq!(4, lines.len(), "{:?}", lines);
+    assert_eq!((Level::Info, String::from("action NewTodo { name: \"a\" }")), lines[0]);
+    assert_eq!(Level::Info, lines[1].0);
+    assert!(lines[1].1.starts_with("done in ") && lines[1].1.ends_with("ms"), "{}", lines[1].1);
+    assert_eq!(Level::Error, lines[3].0);
+    assert!(lines[3].1.starts_with("failed in ") && lines[3].1.ends_with("ms: no todo 3"), "{}", lines[3].1);
+}
+
+#[test]
+fn levels_are_configurable() {
+    let store = store_with(LoggerMiddleware::new().level(Level::Debug).error_level(Level::Warn));
+    let _ = store.dispatch(new_todo("a"));
+    let _ = store.dispatch(TodoAction::Complete(3));
+
+    let levels : Vec<Level> = lines().into_iter().map(|(level, _)| level).collect();
+    assert_eq!(vec![Level::Debug, Level::Debug, Level::Debug, Level::Warn], levels);
+}
+
+#[test]
+fn filtered_actions_are_dispatched_quietly() {
+    let store = store_with(LoggerMiddleware::new().filter(|action| !matches!(action, TodoAction::Complete(_))));
+    let _ = store.dispatch(new_todo("a"));
+    let _ = store.dispatch(TodoAction::Complete(0));
+
+    let texts = texts();
+    assert_eq!(2, texts.len(), "{:?}", texts);
+    assert_eq!("action NewTodo { name: \"a\" }", texts[0]);
+}
+
+#[test]
+fn filters_can_be_set_by_kind() {
+    let store = store_with(LoggerMiddleware::new().kinds("-Complete".parse().unwrap()));
+    let _ = store.dispatch(new_todo("a"));
+    let _ = store.dispatch(TodoAction::Complete(0));
+    assert_eq!(2, texts().len());
+
+    let store = store_with(LoggerMiddleware::new().kinds(KindFilter::new().allow(["Complete"])));
+    let _ = store.dispatch(new_todo("a"));
+    let _ = store.dispatch(TodoAction::Complete(0));
+    assert_eq!("action Complete(0)", texts()[0]);
+}
+
+#[test]
+fn kinds_can_be_shown_before_actions() {
+    let store = store_with(LoggerMiddleware::new().show_kinds().collapsed(true));
+    let _ = store.dispatch(new_todo("a"));
+    assert_eq!("action [NewTodo] NewTodo { name: \"a\" }", texts()[0]);
+
+    let _ = store.dispatch(TodoAction::Thunk(Thunk::new(|api: &dyn MiddlewareApi<TodoList>, _: &()| {
+        api.dispatch(TodoAction::Complete(0)).map(|_| ())
+    })));
+    let texts = texts();
+    assert_eq!("action [Thunk] Thunk(Thunk)", texts[0]);
+    assert!(texts[1].starts_with("  action [Complete] Complete(0) done in "), "{}", texts[1]);
+}
+
+#[test]
+fn full_states_are_logged_before_and_after() {
+    let store = store_with(LoggerMiddleware::new().states(StateLogging::Full));
+    let _ = store.dispatch(new_todo("a"));
+
+    let texts = texts();
+    assert_eq!("prev state TodoList {", texts[2]);
+    let next = texts.iter().position(|line| line == "next state TodoList {").unwrap();
+    assert!(!texts[..next].contains(&String::from("        \"a\",")), "{:?}", texts);
+    assert!(texts[next..].contains(&String::from("        \"a\",")), "{:?}", texts);
+}
+
+#[test]
+fn diffs_only_show_what_changed() {
+    let store = store_with(LoggerMiddleware::new().states(StateLogging::Diff));
+    let _ = store.dispatch(new_todo("a"));
+    let _ = store.dispatch(TodoAction::Complete(0));
+
+    let texts = texts();
+    assert_eq!("state changes", texts[2]);
+    assert!(texts[3..].contains(&String::from("+         \"a\",")), "{:?}", texts);
+    assert!(!texts.iter().any(|line| line.contains("password")), "{:?}", texts);
+    assert_eq!("state unchanged", texts[texts.len() - 1]);
+}
+
+// holds the todo named "slow" back from the reducer until it's released
+struct Pause {
+    reached: Mutex<Sender<()>>,
+    release: Mutex<Receiver<()>>,
+}
+
+impl Middleware<TodoList> for Pause {
+    fn dispatch(&self, store: &Store<TodoList>, action: TodoAction, next: &DispatchFunc<TodoList>) -> Result<TodoList, String> {
+        if let TodoAction::NewTodo { ref name } = action {
+            if name == "slow" {
+                self.reached.lock().unwrap().send(()).unwrap();
+                self.release.lock().unwrap().recv().unwrap();
+            }
+        }
+        next(store, action)
+    }
+}
+
+#[test]
+fn diffs_only_show_their_own_action_when_threads_overlap() {
+    captured();
+    let (reached, paused) = channel();
+    let (release, released) = channel();
+    let pause = Pause { reached: Mutex::new(reached), release: Mutex::new(released) };
+    let logger = LoggerMiddleware::new().states(StateLogging::Diff);
+    let store : Arc<Store<TodoList>> = Arc::new(Store::new(vec![Box::new(pause), Box::new(logger)]));
+    let _ = store.dispatch(new_todo("first"));
+    lines();
+
+    let s = store.clone();
+    let slow = thread::spawn(move || {
+        let _ = s.dispatch(new_todo("slow"));
+        texts()
+    });
+    paused.recv().unwrap();
+    let _ = store.dispatch(new_todo("fast"));
+    let fast = texts();
+    release.send(()).unwrap();
+    let slow = slow.join().unwrap();
+
+    let changes = |texts: &[String]| -> Vec<String> {
+        texts.iter().filter(|line| line.starts_with('+') || line.starts_with('-')).cloned().collect()
+    };
+    assert_eq!(vec![String::from("+         \"fast\",")], changes(&fast), "{:?}", fast);
+    assert_eq!(vec![String::from("+         \"slow\",")], changes(&slow), "{:?}", slow);
+}
+
+#[test]
+fn diffs_work_alongside_state_listeners() {
+    captured();
+    let listeners = ListenerMiddleware::new();
+    let logger = LoggerMiddleware::new().states(StateLogging::Diff);
+    let store : Arc<Store<TodoList>> = Arc::new(Store::new(vec![Box::new(listeners.clone()), Box::new(logger)]));
+    listeners.attach(&store);
+    listeners.add(Listener::on_state(|state: &TodoList| !state.names.is_empty(), |_, _| {}));
+
+    let _ = store.dispatch(new_todo("a"));
+    listeners.wait_for_handlers();
+    let texts = texts();
+    assert_eq!("state changes", texts[2], "{:?}", texts);
+}
+
+#[test]
+fn nested_dispatches_are_indented_or_collapsed() {
+    let complete_all = || TodoAction::Thunk(Thunk::new(|api: &dyn MiddlewareApi<TodoList>, _: &()| {
+        for index in 0..api.get_state().names.len() {
+            api.dispatch(TodoAction::Complete(index))?;
+        }
+        Ok(())
+    }));
+
+    let store = store_with(LoggerMiddleware::new());
+    let _ = store.dispatch(new_todo("a"));
+    lines();
+    let _ = store.dispatch(complete_all());
+    let indented = texts();
+    assert_eq!(4, indented.len(), "{:?}", indented);
+    assert_eq!("action Thunk(Thunk)", indented[0]);
+    assert_eq!("  action Complete(0)", indented[1]);
+    assert!(indented[2].starts_with("  done in "), "{}", indented[2]);
+    assert!(indented[3].starts_with("done in "), "{}", indented[3]);
+
+    let store = store_with(LoggerMiddleware::new().collapsed(true).states(StateLogging::Full));
+    let _ = store.dispatch(new_todo("a"));
+    let _ = store.dispatch(new_todo("b"));
+    lines();
+    let _ = store.dispatch(complete_all());
+    let collapsed = texts();
+    assert_eq!("action Thunk(Thunk)", collapsed[0]);
+    assert!(collapsed[1].starts_with("  action Complete(0) done in "), "{}", collapsed[1]);
+    assert!(collapsed[2].starts_with("  action Complete(1) done in "), "{}", collapsed[2]);
+    assert!(collapsed[3].starts_with("done in "), "{}", collapsed[3]);
+    assert_eq!("prev state TodoList {", collapsed[4]);
+}
+
+#[test]
+fn a_panicking_dispatch_does_not_indent_later_ones() {
+    let store = store_with(LoggerMiddleware::new());
+    let panics = TodoAction::Thunk(Thunk::new(|_: &dyn MiddlewareApi<TodoList>, _: &()| panic!("the thunk panicked")));
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| store.dispatch(panics))).is_err());
+    lines();
+
+    let _ = store.dispatch(new_todo("a"));
+    assert_eq!("action NewTodo { name: \"a\" }", texts()[0]);
+}
+
+#[test]
+fn sensitive_fields_are_redacted() {
+    let logger = LoggerMiddleware::new()
+        .states(StateLogging::Full)
+        .redact_action(|action| match action {
+            TodoAction::Login { .. } => TodoAction::Login { password: String::from("***") },
+            action => action.clone(),
+        })
+        .redact_state(|state: &TodoList| TodoList { password: String::from("***"), ..state.clone() });
+    let store = store_with(logger);
+    let _ = store.dispatch(TodoAction::Login { password: String::from("hunter2") });
+
+    let texts = texts();
+    assert_eq!("action Login { password: \"***\" }", texts[0]);
+    assert!(!texts.iter().any(|line| line.contains("hunter2")), "{:?}", texts);
+    assert_eq!("hunter2", store.get_state().password);
+}
@@ -0,0 +1,75 @@
+extern crate redux;
+
+use redux::{ActionKind, Dispatched, MetricsMiddleware, Reducer, Store};
+
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug)]
+enum TodoAction {
+    NewTodo { name: String },
+    Complete(usize),
+}
+
+impl ActionKind for TodoAction {
+    fn kind(&self) -> &'static str {
+        match self {
+            TodoAction::NewTodo { .. } => "NewTodo",
+            TodoAction::Complete(_) => "Complete",
+        }
+    }
+
+    fn categories(&self) -> &'static [&'static str] {
+        &["todos"]
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+struct TodoList {
+    names: Vec<String>,
+}
+
+impl Reducer for TodoList {
+    type Action = TodoAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            TodoAction::NewTodo { name } => self.names.push(name),
+            TodoAction::Complete(index) if index >= self.names.len() => return Err(format!("no todo {}", index)),
+            TodoAction::Complete(_) => {},
+        }
+
+        Ok(self.clone())
+    }
+}
+
+fn store_reporting_to(reported: Arc<Mutex<Vec<Dispatched>>>) -> Store<TodoList> {
+    let metrics = MetricsMiddleware::new(move |dispatched| reported.lock().unwrap().push(dispatched.clone()));
+    Store::new(vec![Box::new(metrics)])
+}
+
+#[test]
+fn every_dispatch_is_reported_by_kind() {
+    let reported = Arc::new(Mutex::new(vec![]));
+    let store = store_reporting_to(reported.clone());
+    store.dispatch(TodoAction::NewTodo { name: String::from("a") }).unwrap();
+    store.dispatch(TodoAction::Complete(0)).unwrap();
+
+    let reported = reported.lock().unwrap();
+    let kinds : Vec<&str> = reported.iter().map(|dispatched| dispatched.kind).collect();
+    assert_eq!(vec!["NewTodo", "Complete"], kinds);
+    assert_eq!(&["todos"], reported[0].categories);
+    assert_eq!(None, reported[1].error);
+}
+
+#[test]
+fn failures_are_reported_with_their_error() {
+    let reported = Arc::new(Mutex::new(vec![]));
+    let store = store_reporting_to(reported.clone());
+    assert!(store.dispatch(TodoAction::Complete(3)).is_err());
+
+    let reported = reported.lock().unwrap();
+    assert_eq!(1, reported.len());
+    assert_eq!("Complete", reported[0].kind);
+    assert_eq!(Some(String::from("no todo 3")), reported[0].error);
+}
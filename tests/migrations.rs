@@ -0,0 +1,151 @@
+#![cfg(feature = "persistence")]
+extern crate redux;
+
+use redux::{MemoryStorage, MigrationError, Migrations, Middleware, PersistAction, PersistConfig, Persisted, Persistor, Reducer, Storage, Store};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use std::sync::Arc;
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+struct Todo {
+    title: String,
+    done: bool,
+}
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+struct Todos {
+    todos: Vec<Todo>,
+}
+
+impl Reducer for Todos {
+    type Action = String;
+    type Error = String;
+
+    fn reduce(&mut self, title: Self::Action) -> Result<Self, Self::Error> {
+        self.todos.push(Todo { title, done: false });
+        Ok(self.clone())
+    }
+}
+
+fn each_todo(mut state: Value, change: impl Fn(&mut Value)) -> Result<Value, String> {
+    match state["todos"].as_array_mut() {
+        Some(todos) => {
+            todos.iter_mut().for_each(change);
+            Ok(state)
+        },
+        None => Err(String::from("no todos")),
+    }
+}
+
+// version 0 was a list of names, 1 called them `name`, 2 calls them `title`
+// and adds `done`
+fn migrations() -> Migrations {
+    Migrations::new(2)
+        .add(0, |names| Ok(json!({ "todos": names.as_array().map(|names| names.iter().map(|name| json!({ "name": name })).collect::<Vec<_>>()) })))
+        .add(1, |state| each_todo(state, |todo| {
+            todo["title"] = todo["name"].take();
+            todo["done"] = json!(false);
+        }))
+}
+
+fn start(storage: MemoryStorage) -> (Arc<Store<Persisted<Todos>>>, Persistor<Todos>) {
+    let persistor = Persistor::with_migrations(storage, PersistConfig::new("todos"), migrations());
+    let store = Arc::new(Store::new(vec![Box::new(persistor.clone()) as Box<dyn Middleware<Persisted<Todos>>>]));
+    persistor.attach(&store);
+    (store, persistor)
+}
+
+#[test]
+fn every_chain_reaches_the_current_version() {
+    migrations().check().unwrap();
+    assert_eq!(Err(MigrationError::Missing { from: 1 }), Migrations::new(2).add(0, Ok).check());
+    assert_eq!(Err(MigrationError::FutureVersion { version: 2, current: 2 }), migrations().add(2, Ok).check());
+}
+
+#[test]
+fn fixtures_migrate_from_every_version() {
+    let current = json!({ "todos": [{ "title": "ship it", "done": false, "name": null }] });
+    migrations().assert_migrates(0, json!(["ship it"]), current.clone());
+    migrations().assert_migrates(1, json!({ "todos": [{ "name": "ship it" }] }), current.clone());
+    migrations().assert_migrates(2, current.clone(), current);
+}
+
+#[test]
+fn old_saves_are_migrated_when_rehydrating() {
+    let storage = MemoryStorage::new();
+    // saved before there were versions at all
+    storage.set("todos", r#"["write tests", "ship it"]"#).unwrap();
+
+    let (store, persistor) = start(storage.clone());
+    persistor.wait_until_rehydrated().unwrap();
+    let titles : Vec<String> = store.get_state().todos.iter().map(|todo| todo.title.clone()).collect();
+    assert_eq!(vec!["write tests", "ship it"], titles);
+
+    let _ = store.dispatch(PersistAction::Action(String::from("celebrate")));
+    let saved : Value = serde_json::from_str(&storage.get("todos").unwrap().unwrap()).unwrap();
+    assert_eq!(2, saved["__redux_version"]);
+    assert_eq!(3, saved["state"]["todos"].as_array().unwrap().len());
+}
+
+// version 0 happened to save a `version` of its own next to the todos
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+struct Versioned {
+    version: u32,
+    state: Vec<String>,
+}
+
+impl Reducer for Versioned {
+    type Action = String;
+    type Error = String;
+
+    fn reduce(&mut self, title: Self::Action) -> Result<Self, Self::Error> {
+        self.state.push(title);
+        Ok(self.clone())
+    }
+}
+
+#[test]
+fn unversioned_saves_with_a_version_field_are_not_unwrapped() {
+    let storage = MemoryStorage::new();
+    storage.set("versioned", r#"{ "version": 3, "state": ["ship it"] }"#).unwrap();
+
+    let migrations = Migrations::new(1).add(0, |mut saved| {
+        saved["version"] = json!(4);
+        Ok(saved)
+    });
+    let persistor = Persistor::with_migrations(storage, PersistConfig::new("versioned"), migrations);
+    let store = Arc::new(Store::new(vec![Box::new(persistor.clone()) as Box<dyn Middleware<Persisted<Versioned>>>]));
+    persistor.attach(&store);
+    persistor.wait_until_rehydrated().unwrap();
+    assert_eq!(Versioned { version: 4, state: vec![String::from("ship it")] }, *store.get_state());
+}
+
+#[test]
+fn saves_from_the_future_are_reported_and_kept() {
+    let storage = MemoryStorage::new();
+    let future = r#"{ "__redux_version": 3, "state": { "todos": [] } }"#;
+    storage.set("todos", future).unwrap();
+
+    let (store, persistor) = start(storage.clone());
+    let error = persistor.wait_until_rehydrated().unwrap_err();
+    assert_eq!("The saved state is from schema version 3, but this is only version 2", error);
+
+    let _ = store.dispatch(PersistAction::Action(String::from("not saved")));
+    assert_eq!(1, store.get_state().todos.len());
+    assert_eq!(Some(String::from(future)), storage.get("todos").unwrap());
+
+    persistor.purge().unwrap();
+    let _ = store.dispatch(PersistAction::Action(String::from("saved")));
+    assert!(storage.get("todos").unwrap().is_some());
+}
+
+#[test]
+fn failed_migrations_say_which_step() {
+    let storage = MemoryStorage::new();
+    storage.set("todos", r#"{ "__redux_version": 1, "state": { "items": [] } }"#).unwrap();
+
+    let (_store, persistor) = start(storage);
+    let error = persistor.wait_until_rehydrated().unwrap_err();
+    assert_eq!("The migration from schema version 1 to 2 failed: no todos", error);
+}
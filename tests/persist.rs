@@ -0,0 +1,236 @@
+#![cfg(feature = "persistence")]
+extern crate redux;
+
+use redux::{DispatchFunc, FileStorage, MemoryStorage, Middleware, PersistAction, PersistConfig, Persisted, Persistor, Reducer, Slices, Storage, Store};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+struct App {
+    theme: String,
+    visits: u64,
+    draft: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum AppAction {
+    Theme(String),
+    Visit,
+    Draft(String),
+    Fail,
+}
+
+impl Reducer for App {
+    type Action = AppAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            AppAction::Theme(theme) => self.theme = theme,
+            AppAction::Visit => self.visits += 1,
+            AppAction::Draft(draft) => self.draft = draft,
+            AppAction::Fail => return Err(String::from("can't")),
+        }
+
+        Ok(self.clone())
+    }
+}
+
+fn start<S: 'static + Storage>(storage: S, config: PersistConfig) -> (Arc<Store<Persisted<App>>>, Persistor<App>) {
+    let persistor = Persistor::new(storage, config);
+    let store = Arc::new(Store::new(vec![Box::new(persistor.clone()) as Box<dyn Middleware<Persisted<App>>>]));
+    persistor.attach(&store);
+    (store, persistor)
+}
+
+// the saved state, without its version stamp
+fn saved(storage: &MemoryStorage, key: &str) -> serde_json::Value {
+    let saved : serde_json::Value = serde_json::from_str(&storage.get(key).unwrap().unwrap()).unwrap();
+    saved["state"].clone()
+}
+
+#[test]
+fn state_survives_a_restart() {
+    let storage = MemoryStorage::new();
+    {
+        let (store, persistor) = start(storage.clone(), PersistConfig::new("app"));
+        persistor.wait_until_rehydrated().unwrap();
+        let _ = store.dispatch(PersistAction::Action(AppAction::Theme(String::from("dark"))));
+        let _ = store.dispatch(PersistAction::Action(AppAction::Visit));
+    }
+
+    let (store, persistor) = start(storage, PersistConfig::new("app"));
+    persistor.wait_until_rehydrated().unwrap();
+    let state = store.get_state();
+    assert!(state.is_rehydrated());
+    assert_eq!(App { theme: String::from("dark"), visits: 1, draft: String::new() }, *state.state());
+}
+
+#[test]
+fn files_survive_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    {
+        let (store, persistor) = start(FileStorage::new(dir.path().join("state")), PersistConfig::new("app"));
+        persistor.wait_until_rehydrated().unwrap();
+        let _ = store.dispatch(PersistAction::Action(AppAction::Visit));
+    }
+    assert!(dir.path().join("state").join("app.json").exists());
+
+    let (store, persistor) = start(FileStorage::new(dir.path().join("state")), PersistConfig::new("app"));
+    persistor.wait_until_rehydrated().unwrap();
+    assert_eq!(1, store.get_state().visits);
+
+    persistor.purge().unwrap();
+    assert!(!dir.path().join("state").join("app.json").exists());
+}
+
+#[test]
+fn only_the_selected_slices_are_saved() {
+    let storage = MemoryStorage::new();
+    let whitelist = PersistConfig { slices: Slices::Whitelist(vec![String::from("theme")]), ..PersistConfig::new("white") };
+    let (store, persistor) = start(storage.clone(), whitelist);
+    persistor.wait_until_rehydrated().unwrap();
+    let _ = store.dispatch(PersistAction::Action(AppAction::Theme(String::from("dark"))));
+    let _ = store.dispatch(PersistAction::Action(AppAction::Visit));
+    assert_eq!(json!({ "theme": "dark" }), saved(&storage, "white"));
+
+    let blacklist = PersistConfig { slices: Slices::Blacklist(vec![String::from("draft")]), ..PersistConfig::new("black") };
+    let (store, persistor) = start(storage.clone(), blacklist);
+    persistor.wait_until_rehydrated().unwrap();
+    let _ = store.dispatch(PersistAction::Action(AppAction::Draft(String::from("unsent"))));
+    let _ = store.dispatch(PersistAction::Action(AppAction::Visit));
+    assert_eq!(json!({ "theme": "", "visits": 1 }), saved(&storage, "black"));
+}
+
+#[test]
+fn rehydrating_only_overwrites_the_saved_slices() {
+    let storage = MemoryStorage::new();
+    storage.set("app", r#"{ "theme": "dark", "draft": "stale" }"#).unwrap();
+    let config = PersistConfig { slices: Slices::Blacklist(vec![String::from("draft")]), ..PersistConfig::new("app") };
+
+    let (store, persistor) = start(storage, config);
+    persistor.wait_until_rehydrated().unwrap();
+    assert_eq!(App { theme: String::from("dark"), visits: 0, draft: String::new() }, *store.get_state().state());
+}
+
+#[test]
+fn dispatches_before_rehydrating_are_held_back() {
+    let storage = MemoryStorage::new();
+    storage.set("app", r#"{ "theme": "dark", "visits": 10, "draft": "" }"#).unwrap();
+    let persistor = Persistor::new(storage, PersistConfig::new("app"));
+    let store = Arc::new(Store::new(vec![Box::new(persistor.clone()) as Box<dyn Middleware<Persisted<App>>>]));
+
+    // not attached yet, so there's no way it's rehydrated
+    store.dispatch(PersistAction::Action(AppAction::Visit)).unwrap();
+    let state = store.get_state();
+    assert_eq!(0, state.visits);
+    assert!(!state.is_rehydrated());
+
+    persistor.attach(&store);
+    persistor.wait_until_rehydrated().unwrap();
+    assert_eq!(11, store.get_state().visits);
+}
+
+#[test]
+fn held_dispatches_are_published_once_reduced() {
+    let storage = MemoryStorage::new();
+    storage.set("app", r#"{ "theme": "dark", "visits": 10, "draft": "" }"#).unwrap();
+    let persistor = Persistor::new(storage, PersistConfig::new("app"));
+    let store = Arc::new(Store::new(vec![Box::new(persistor.clone()) as Box<dyn Middleware<Persisted<App>>>]));
+    let seen = Arc::new(Mutex::new(vec![]));
+    let s = seen.clone();
+    store.subscribe(Box::new(move |store, _| s.lock().unwrap().push(store.get_state().visits)));
+
+    store.dispatch(PersistAction::Action(AppAction::Visit)).unwrap();
+    store.dispatch(PersistAction::Action(AppAction::Fail)).unwrap();
+    store.dispatch(PersistAction::Action(AppAction::Visit)).unwrap();
+    assert_eq!(vec![0, 0, 0], *seen.lock().unwrap());
+
+    // the rehydrate, then each held action that reduced
+    persistor.attach(&store);
+    assert_eq!(Err(String::from("Error during dispatch: can't")), persistor.wait_until_rehydrated());
+    assert_eq!(vec![0, 0, 0, 12, 12, 12], *seen.lock().unwrap());
+}
+
+// a middleware that drops the rehydrate before the persistor sees it
+struct DropRehydrate;
+
+impl Middleware<Persisted<App>> for DropRehydrate {
+    fn dispatch(&self, store: &Store<Persisted<App>>, action: PersistAction<AppAction>, next: &DispatchFunc<Persisted<App>>) -> Result<Persisted<App>, String> {
+        match action {
+            PersistAction::Rehydrate(_) => Ok(store.get_state()),
+            action => next(store, action),
+        }
+    }
+}
+
+#[test]
+fn held_dispatches_are_reported_when_the_rehydrate_is_dropped() {
+    let storage = MemoryStorage::new();
+    storage.set("app", r#"{ "theme": "dark", "visits": 10, "draft": "" }"#).unwrap();
+    let persistor = Persistor::new(storage.clone(), PersistConfig::new("app"));
+    let store = Arc::new(Store::new(vec![Box::new(persistor.clone()) as Box<dyn Middleware<Persisted<App>>>, Box::new(DropRehydrate)]));
+
+    store.dispatch(PersistAction::Action(AppAction::Visit)).unwrap();
+    store.dispatch(PersistAction::Action(AppAction::Visit)).unwrap();
+    persistor.attach(&store);
+    assert_eq!(
+        Err(String::from("The rehydrate never reached the persistor, so the saved state wasn't loaded and 2 held actions were dropped.")),
+        persistor.wait_until_rehydrated()
+    );
+
+    // the gate is open again, but the save isn't overwritten
+    store.dispatch(PersistAction::Action(AppAction::Visit)).unwrap();
+    assert_eq!(1, store.get_state().visits);
+    persistor.flush().unwrap();
+    assert_eq!(r#"{ "theme": "dark", "visits": 10, "draft": "" }"#, storage.get("app").unwrap().unwrap());
+}
+
+#[test]
+fn unreadable_saves_are_reported_and_ignored() {
+    let storage = MemoryStorage::new();
+    storage.set("app", "{ not json").unwrap();
+
+    let (store, persistor) = start(storage, PersistConfig::new("app"));
+    assert!(persistor.wait_until_rehydrated().is_err());
+    let _ = store.dispatch(PersistAction::Action(AppAction::Visit));
+    assert_eq!(1, store.get_state().visits);
+}
+
+#[test]
+fn saves_that_dont_fit_the_state_are_left_alone() {
+    let storage = MemoryStorage::new();
+    storage.set("app", r#"{ "theme": 5 }"#).unwrap();
+
+    let (store, persistor) = start(storage.clone(), PersistConfig::new("app"));
+    assert!(persistor.wait_until_rehydrated().is_err());
+    let _ = store.dispatch(PersistAction::Action(AppAction::Visit));
+    persistor.flush().unwrap();
+    assert_eq!(r#"{ "theme": 5 }"#, storage.get("app").unwrap().unwrap());
+}
+
+#[test]
+fn writes_are_throttled() {
+    let storage = MemoryStorage::new();
+    // long enough that only `flush` can write before the test is over
+    let config = PersistConfig { throttle: Duration::from_secs(3600), ..PersistConfig::new("app") };
+    let (store, persistor) = start(storage.clone(), config);
+    persistor.wait_until_rehydrated().unwrap();
+
+    // the rehydration itself was the first write
+    for _ in 0..5 {
+        let _ = store.dispatch(PersistAction::Action(AppAction::Visit));
+    }
+    assert_eq!(0, saved(&storage, "app")["visits"]);
+
+    persistor.flush().unwrap();
+    assert_eq!(5, saved(&storage, "app")["visits"]);
+
+    let _ = store.dispatch(PersistAction::Action(AppAction::Visit));
+    assert_eq!(5, saved(&storage, "app")["visits"]);
+    persistor.flush().unwrap();
+    assert_eq!(6, saved(&storage, "app")["visits"]);
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "property-testing")]
+extern crate redux;
+
+use proptest::prelude::*;
+use quickcheck::{Arbitrary, Gen};
+use redux::testing::Invariants;
+use redux::Reducer;
+
+#[derive(Clone, Debug, PartialEq)]
+enum TodoAction {
+    NewTodo { name: String },
+    Remove(usize),
+}
+
+impl Arbitrary for TodoAction {
+    fn arbitrary(g: &mut Gen) -> Self {
+        match bool::arbitrary(g) {
+            true => TodoAction::NewTodo { name: String::arbitrary(g) },
+            false => TodoAction::Remove(usize::arbitrary(g) % 4),
+        }
+    }
+}
+
+fn todo_action() -> impl Strategy<Value = TodoAction> {
+    prop_oneof![
+        "[a-z]{0,3}".prop_map(|name| TodoAction::NewTodo { name }),
+        (0..4usize).prop_map(TodoAction::Remove),
+    ]
+}
+
+// keeps a count next to the list, which a bug can get out of step
+#[derive(Clone, Default, Debug)]
+struct TodoList {
+    names: Vec<String>,
+    count: i64,
+    buggy: bool,
+}
+
+impl TodoList {
+    fn buggy() -> TodoList {
+        TodoList { buggy: true, ..TodoList::default() }
+    }
+}
+
+impl Reducer for TodoList {
+    type Action = TodoAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            TodoAction::NewTodo { name } => {
+                self.names.push(name);
+                self.count += 1;
+            },
+            TodoAction::Remove(index) => {
+                // the bug: counting the removal before checking there's
+                // anything to remove
+                if self.buggy {
+                    self.count -= 1;
+                }
+                if index >= self.names.len() {
+                    return Err(format!("no todo {}", index));
+                }
+                self.names.remove(index);
+                if !self.buggy {
+                    self.count -= 1;
+                }
+            },
+        }
+
+        Ok(self.clone())
+    }
+}
+
+fn invariants(initial: TodoList) -> Invariants<TodoList> {
+    Invariants::new(initial)
+        .holds("count matches", |todos| todos.count == todos.names.len() as i64)
+        .holds("at most 64 todos", |todos| todos.names.len() <= 64)
+}
+
+#[test]
+fn correct_reducers_pass() {
+    invariants(TodoList::default()).proptest(todo_action()).unwrap();
+    invariants(TodoList::default()).quickcheck().unwrap();
+}
+
+#[test]
+fn proptest_shrinks_to_a_minimal_sequence() {
+    let broken = invariants(TodoList::buggy()).cases(1000).proptest(todo_action()).unwrap_err();
+    assert_eq!("count matches", broken.invariant);
+    assert_eq!(vec![TodoAction::Remove(0)], broken.actions);
+    assert_eq!(Some(0), broken.after);
+    assert_eq!("The invariant `count matches` broke after action #0 (Remove(0)) of [Remove(0)]", broken.to_string());
+}
+
+#[test]
+fn quickcheck_shrinks_to_a_minimal_sequence() {
+    let broken = invariants(TodoList::buggy()).cases(1000).quickcheck().unwrap_err();
+    assert_eq!(1, broken.actions.len());
+    assert!(matches!(broken.actions[0], TodoAction::Remove(_)));
+}
+
+#[test]
+fn the_initial_state_is_checked_too() {
+    let initial = TodoList { count: 3, ..TodoList::default() };
+    let broken = invariants(initial).check(&[]).unwrap_err();
+    assert_eq!(None, broken.after);
+}
@@ -0,0 +1,173 @@
+extern crate redux;
+
+use redux::{Reversible, Store, UndoAction, UndoLog, Verified};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Edit {
+    Insert(usize, String),
+    Delete(usize, usize),
+}
+
+#[derive(Clone, Default, Debug, PartialEq)]
+struct Document {
+    text: String,
+}
+
+impl Reversible for Document {
+    type Action = Edit;
+    type Error = String;
+
+    fn reduce(&mut self, edit: Edit) -> Result<Edit, String> {
+        match edit {
+            Edit::Insert(at, text) => {
+                if at > self.text.len() {
+                    return Err(format!("can't insert at {}", at));
+                }
+                self.text.insert_str(at, &text);
+                Ok(Edit::Delete(at, text.len()))
+            },
+            Edit::Delete(at, len) => {
+                if at + len > self.text.len() {
+                    return Err(format!("can't delete {} at {}", len, at));
+                }
+                let removed = self.text.drain(at..at + len).collect();
+                Ok(Edit::Insert(at, removed))
+            },
+        }
+    }
+}
+
+// forgets to put back what it deleted
+#[derive(Clone, Default, Debug, PartialEq)]
+struct Lossy {
+    text: String,
+}
+
+impl Reversible for Lossy {
+    type Action = Edit;
+    type Error = String;
+
+    fn reduce(&mut self, edit: Edit) -> Result<Edit, String> {
+        match edit {
+            Edit::Insert(at, text) => {
+                self.text.insert_str(at, &text);
+                Ok(Edit::Delete(at, text.len()))
+            },
+            Edit::Delete(at, len) => {
+                self.text.drain(at..at + len);
+                Ok(Edit::Insert(at, String::new()))
+            },
+        }
+    }
+}
+
+// adds to a count, but adding 7 can't be undone: its inverse fails
+#[derive(Clone, Default, Debug, PartialEq)]
+struct Sticky {
+    count: isize,
+}
+
+impl Reversible for Sticky {
+    type Action = isize;
+    type Error = String;
+
+    fn reduce(&mut self, n: isize) -> Result<isize, String> {
+        if n == 0 {
+            return Err(String::from("can't add nothing"));
+        }
+        self.count += n;
+        Ok(if n == 7 { 0 } else { -n })
+    }
+}
+
+fn insert(at: usize, text: &str) -> UndoAction<Edit> {
+    UndoAction::Action(Edit::Insert(at, String::from(text)))
+}
+
+#[test]
+fn undo_and_redo_apply_inverses() {
+    let store : Store<UndoLog<Document>> = Store::new(vec![]);
+    let _ = store.dispatch(insert(0, "world"));
+    let _ = store.dispatch(insert(0, "hello "));
+    let _ = store.dispatch(UndoAction::Action(Edit::Delete(0, 6)));
+    assert_eq!("world", store.get_state().text);
+
+    let _ = store.dispatch(UndoAction::Undo);
+    assert_eq!("hello world", store.get_state().text);
+    let _ = store.dispatch(UndoAction::Jump(-2));
+    assert_eq!("", store.get_state().text);
+    assert!(!store.get_state().can_undo());
+
+    let _ = store.dispatch(UndoAction::Redo);
+    assert_eq!("world", store.get_state().text);
+    let _ = store.dispatch(UndoAction::Jump(2));
+    assert_eq!("world", store.get_state().text);
+    assert_eq!(3, store.get_state().past_len());
+}
+
+#[test]
+fn new_actions_clear_the_redo_log() {
+    let store : Store<UndoLog<Document>> = Store::new(vec![]);
+    let _ = store.dispatch(insert(0, "a"));
+    let _ = store.dispatch(insert(1, "b"));
+    let _ = store.dispatch(UndoAction::Undo);
+    let _ = store.dispatch(insert(1, "c"));
+
+    let state = store.get_state();
+    assert_eq!("ac", state.text);
+    assert!(!state.can_redo());
+    assert_eq!(2, state.past_len());
+}
+
+#[test]
+fn failed_actions_are_not_logged() {
+    let store : Store<UndoLog<Document>> = Store::new(vec![]);
+    let _ = store.dispatch(insert(0, "a"));
+    assert!(store.dispatch(insert(5, "b")).is_err());
+
+    let state = store.get_state();
+    assert_eq!("a", state.text);
+    assert_eq!(1, state.past_len());
+}
+
+#[test]
+fn a_failed_jump_leaves_the_log_as_it_was() {
+    let store : Store<UndoLog<Sticky>> = Store::new(vec![]);
+    for n in [1, 7, 2] {
+        let _ = store.dispatch(UndoAction::Action(n));
+    }
+
+    // undoing the 2 works, undoing the 7 doesn't
+    assert!(store.dispatch(UndoAction::Jump(-3)).is_err());
+    let state = store.get_state();
+    assert_eq!(10, state.count);
+    assert_eq!(3, state.past_len());
+    assert_eq!(0, state.future_len());
+}
+
+#[test]
+fn clear_history_keeps_the_present() {
+    let store : Store<UndoLog<Document>> = Store::new(vec![]);
+    let _ = store.dispatch(insert(0, "a"));
+    let _ = store.dispatch(UndoAction::ClearHistory);
+    let _ = store.dispatch(UndoAction::Undo);
+    assert_eq!("a", store.get_state().text);
+}
+
+#[test]
+fn verified_accepts_correct_inverses() {
+    let store : Store<UndoLog<Verified<Document>>> = Store::new(vec![]);
+    let _ = store.dispatch(insert(0, "hello"));
+    let _ = store.dispatch(UndoAction::Action(Edit::Delete(1, 3)));
+    let _ = store.dispatch(UndoAction::Undo);
+    assert_eq!("hello", store.get_state().text);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "its inverse")]
+fn verified_catches_wrong_inverses() {
+    let mut log : UndoLog<Verified<Lossy>> = UndoLog::default();
+    let _ = redux::Reducer::reduce(&mut log, insert(0, "hello"));
+    let _ = redux::Reducer::reduce(&mut log, UndoAction::Action(Edit::Delete(0, 2)));
+}
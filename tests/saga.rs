@@ -0,0 +1,185 @@
+extern crate redux;
+
+use redux::{Race, Reducer, Saga, SagaMiddleware, SagaResult, SagaTester, Store};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Clone, Debug, PartialEq)]
+enum AuthAction {
+    Login(String),
+    ProfileLoaded(String),
+    Logout,
+    Expired,
+    Ping,
+    Pong,
+    StopPinging,
+}
+
+#[derive(Clone, Default, Debug)]
+struct Auth {
+    user: Option<String>,
+    profile: Option<String>,
+    expired: bool,
+}
+
+impl Reducer for Auth {
+    type Action = AuthAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            AuthAction::Login(user) => self.user = Some(user),
+            AuthAction::ProfileLoaded(profile) => self.profile = Some(profile),
+            AuthAction::Logout => *self = Auth::default(),
+            AuthAction::Expired => {
+                *self = Auth::default();
+                self.expired = true;
+            },
+            _ => {}
+        }
+
+        Ok(self.clone())
+    }
+}
+
+fn is_login(action: &AuthAction) -> bool {
+    matches!(action, AuthAction::Login(_))
+}
+
+fn is_logout(action: &AuthAction) -> bool {
+    *action == AuthAction::Logout
+}
+
+// wait for a login, load the profile, then wait for a logout or expire the
+// session after `session_length`
+fn login_flow(saga: &Saga<Auth>, session_length: Duration) -> SagaResult {
+    loop {
+        saga.take(is_login)?;
+        let user = saga.select(|state: &Auth| state.user.clone())?.unwrap_or_default();
+        let _ = saga.put(AuthAction::ProfileLoaded(format!("{}'s profile", user)));
+
+        if let Race::TimedOut = saga.race(&[&is_logout], Some(session_length))? {
+            let _ = saga.put(AuthAction::Expired);
+        }
+    }
+}
+
+#[test]
+fn step_a_workflow_through_scripted_actions() {
+    let tester = SagaTester::new(Auth { user: Some(String::from("jared")), ..Auth::default() });
+    tester.run(|saga| login_flow(saga, Duration::from_secs(60)));
+    assert_eq!(0, tester.puts().len());
+
+    assert_eq!(vec![AuthAction::ProfileLoaded(String::from("jared's profile"))], tester.step(AuthAction::Login(String::from("jared"))));
+    assert_eq!(0, tester.step(AuthAction::Ping).len());
+    assert_eq!(0, tester.step(AuthAction::Logout).len());
+
+    tester.set_state(Auth { user: Some(String::from("sam")), ..Auth::default() });
+    assert_eq!(vec![AuthAction::ProfileLoaded(String::from("sam's profile"))], tester.step(AuthAction::Login(String::from("sam"))));
+}
+
+#[test]
+fn timeouts_only_fire_when_the_tester_says_so() {
+    let tester = SagaTester::new(Auth::default());
+    tester.run(|saga| login_flow(saga, Duration::from_millis(1)));
+    tester.step(AuthAction::Login(String::from("jared")));
+
+    // a plain take ignores timeouts, a race gives up on them
+    assert_eq!(vec![AuthAction::Expired], tester.time_out());
+    assert_eq!(0, tester.time_out().len());
+}
+
+#[test]
+fn fork_and_cancel_a_child_workflow() {
+    let tester = SagaTester::new(Auth::default());
+    let parent = tester.run(|saga| {
+        let pinger = saga.fork(|saga| {
+            loop {
+                saga.take(|action| *action == AuthAction::Ping)?;
+                let _ = saga.put(AuthAction::Pong);
+            }
+        });
+        saga.take(|action| *action == AuthAction::StopPinging)?;
+        saga.cancel(&pinger);
+        saga.take(|_| false)?;
+        Ok(())
+    });
+
+    assert_eq!(vec![AuthAction::Pong], tester.step(AuthAction::Ping));
+    tester.step(AuthAction::StopPinging);
+    assert_eq!(0, tester.step(AuthAction::Ping).len());
+
+    parent.cancel();
+    parent.join();
+    assert!(parent.is_cancelled());
+    assert!(!parent.is_running());
+}
+
+#[test]
+fn cancelling_the_parent_cancels_forked_children() {
+    let tester = SagaTester::new(Auth::default());
+    let parent = tester.run(|saga| {
+        saga.fork(|saga| {
+            loop {
+                saga.take(|action| *action == AuthAction::Ping)?;
+                let _ = saga.put(AuthAction::Pong);
+            }
+        });
+        saga.take(|_| false)?;
+        Ok(())
+    });
+
+    assert_eq!(vec![AuthAction::Pong], tester.step(AuthAction::Ping));
+    parent.cancel();
+    parent.join();
+    assert_eq!(0, tester.step(AuthAction::Ping).len());
+}
+
+#[test]
+fn workflows_run_against_a_store() {
+    let sagas = SagaMiddleware::new();
+    let store : Arc<Store<Auth>> = Arc::new(Store::new(vec![Box::new(sagas.clone())]));
+    // sessions expiring is up to `timeouts_only_fire_when_the_tester_says_so`,
+    // on virtual time
+    let task = sagas.run(&store, |saga| login_flow(saga, Duration::from_secs(3600)));
+
+    let _ = store.dispatch(AuthAction::Login(String::from("jared")));
+    sagas.wait_until_idle();
+    assert_eq!(Some(String::from("jared's profile")), store.get_state().profile);
+
+    let _ = store.dispatch(AuthAction::Logout);
+    sagas.wait_until_idle();
+    assert_eq!(None, store.get_state().profile);
+
+    let _ = store.dispatch(AuthAction::Login(String::from("sam")));
+    sagas.wait_until_idle();
+    assert_eq!(Some(String::from("sam's profile")), store.get_state().profile);
+    assert!(!store.get_state().expired);
+
+    task.cancel();
+    task.join();
+}
+
+#[test]
+fn dropping_the_store_cancels_its_workflows() {
+    let sagas = SagaMiddleware::new();
+    let store : Arc<Store<Auth>> = Arc::new(Store::new(vec![Box::new(sagas.clone())]));
+    let task = sagas.run(&store, |saga| {
+        loop {
+            saga.take(|action| *action == AuthAction::Ping)?;
+            let _ = saga.put(AuthAction::Pong);
+        }
+    });
+    sagas.wait_until_idle();
+
+    // the workflow doesn't keep the store alive
+    let weak = Arc::downgrade(&store);
+    drop(store);
+    assert!(weak.upgrade().is_none());
+
+    // and once the middleware is gone too, its blocked take gives up
+    drop(sagas);
+    task.join();
+    assert!(task.is_cancelled());
+}
@@ -0,0 +1,170 @@
+#![cfg(feature = "persistence")]
+extern crate redux;
+
+use redux::{LogError, Migrations, Reducer, SnapshotMiddleware, SnapshotOptions, Store};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+struct Tally {
+    total: u64,
+    count: u64,
+}
+
+impl Reducer for Tally {
+    type Action = u64;
+    type Error = String;
+
+    fn reduce(&mut self, n: Self::Action) -> Result<Self, Self::Error> {
+        self.total += n;
+        self.count += 1;
+        Ok(self.clone())
+    }
+}
+
+fn every(actions: usize) -> SnapshotOptions {
+    SnapshotOptions { every_actions: Some(actions), ..SnapshotOptions::default() }
+}
+
+fn files(dir: &Path) -> Vec<String> {
+    let mut names : Vec<String> = fs::read_dir(dir).unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .map(|name| {
+            let (sequence, extension) = name.split_once('.').unwrap();
+            format!("{}.{}", sequence.parse::<u64>().unwrap(), extension)
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+// dispatches 1 to `n`, waiting for any compaction it started
+fn fill(dir: &Path, options: SnapshotOptions, n: u64) {
+    let (state, snapshots) = SnapshotMiddleware::<Tally>::open(dir, options).unwrap();
+    let store = Store::with_state(state, vec![Box::new(snapshots.clone())]);
+    for i in 1..=n {
+        let _ = store.dispatch(i);
+    }
+    snapshots.wait_for_compaction().unwrap();
+}
+
+#[test]
+fn old_segments_are_compacted() {
+    let dir = tempfile::tempdir().unwrap();
+    fill(dir.path(), every(10), 35);
+    assert_eq!(vec!["20.log", "20.snapshot", "30.log", "30.snapshot"], files(dir.path()));
+
+    let store : Store<Tally> = Store::from_snapshots(dir.path(), every(10), vec![]).unwrap();
+    assert_eq!(Tally { total: 630, count: 35 }, store.get_state());
+    let _ = store.dispatch(100);
+    drop(store);
+
+    let store : Store<Tally> = Store::from_snapshots(dir.path(), every(10), vec![]).unwrap();
+    assert_eq!(Tally { total: 730, count: 36 }, store.get_state());
+}
+
+#[test]
+fn a_corrupt_snapshot_falls_back_to_the_previous_one() {
+    let dir = tempfile::tempdir().unwrap();
+    fill(dir.path(), every(10), 35);
+
+    let newest = dir.path().join(format!("{:020}.snapshot", 30));
+    let mut bytes = fs::read(&newest).unwrap();
+    let last = bytes.len() - 2;
+    bytes[last] ^= 0xff;
+    fs::write(&newest, bytes).unwrap();
+
+    let store : Store<Tally> = Store::from_snapshots(dir.path(), every(10), vec![]).unwrap();
+    assert_eq!(Tally { total: 630, count: 35 }, store.get_state());
+}
+
+#[test]
+fn losing_every_snapshot_after_compaction_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    fill(dir.path(), SnapshotOptions { keep: 1, ..every(10) }, 25);
+    fs::write(dir.path().join(format!("{:020}.snapshot", 20)), b"garbage").unwrap();
+
+    match Store::<Tally>::from_snapshots(dir.path(), every(10), vec![]) {
+        Err(LogError::Missing { sequence }) => assert_eq!(0, sequence),
+        other => panic!("expected missing actions, got {:?}", other.err()),
+    }
+}
+
+// version 0 called the total `sum`, and its actions were `{ "add": n }`
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+struct OldTally {
+    sum: u64,
+    count: u64,
+}
+
+impl Reducer for OldTally {
+    type Action = Value;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        self.sum += action["add"].as_u64().ok_or("not an add")?;
+        self.count += 1;
+        Ok(self.clone())
+    }
+}
+
+fn upcasters() -> Migrations {
+    Migrations::new(1).add(0, |action| Ok(action["add"].clone()))
+}
+
+fn migrations() -> Migrations {
+    Migrations::new(1).add(0, |mut state| {
+        state["total"] = state["sum"].take();
+        Ok(state)
+    })
+}
+
+#[test]
+fn old_snapshots_and_segments_are_brought_up_to_date() {
+    let dir = tempfile::tempdir().unwrap();
+    {
+        let (state, snapshots) = SnapshotMiddleware::<OldTally>::open(dir.path(), every(10)).unwrap();
+        let store = Store::with_state(state, vec![Box::new(snapshots)]);
+        for i in 1..=15 {
+            let _ = store.dispatch(json!({ "add": i }));
+        }
+    }
+
+    let store : Store<Tally> = Store::from_versioned_snapshots(dir.path(), every(10), &upcasters(), &migrations(), vec![]).unwrap();
+    assert_eq!(Tally { total: 120, count: 15 }, store.get_state());
+    for i in 16..=20 {
+        let _ = store.dispatch(i);
+    }
+    drop(store);
+
+    let store : Store<Tally> = Store::from_versioned_snapshots(dir.path(), every(10), &upcasters(), &migrations(), vec![]).unwrap();
+    assert_eq!(Tally { total: 210, count: 20 }, store.get_state());
+    drop(store);
+
+    // and an older app can't make sense of the newer snapshot
+    match Store::<Tally>::from_snapshots(dir.path(), every(10), vec![]) {
+        Err(LogError::Migration { sequence, message }) => {
+            assert_eq!(20, sequence);
+            assert_eq!("The saved state is from schema version 1, but this is only version 0", message);
+        },
+        other => panic!("expected a migration error, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn snapshots_can_be_taken_on_a_timer() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = SnapshotOptions { every_actions: None, every: Some(Duration::from_millis(50)), ..SnapshotOptions::default() };
+    let store : Store<Tally> = Store::from_snapshots(dir.path(), options, vec![]).unwrap();
+    let _ = store.dispatch(1);
+    let _ = store.dispatch(2);
+    thread::sleep(Duration::from_millis(60));
+    let _ = store.dispatch(3);
+
+    assert_eq!(vec!["0.log", "3.log", "3.snapshot"], files(dir.path()));
+}
@@ -0,0 +1,326 @@
+#[macro_use]
+extern crate redux;
+
+use redux::testing::{given, Determinism, MockStore};
+use redux::{DispatchFunc, Middleware, MiddlewareApi, Reducer, Store, Thunk, ThunkAction, ThunkMiddleware};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Clone, Debug)]
+enum TodoAction {
+    NewTodo { name: String },
+    Complete(usize),
+    Thunk(Thunk<TodoList>),
+}
+
+// thunks are never equal, they're functions
+impl PartialEq for TodoAction {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (TodoAction::NewTodo { name }, TodoAction::NewTodo { name: other }) => name == other,
+            (TodoAction::Complete(index), TodoAction::Complete(other)) => index == other,
+            _ => false,
+        }
+    }
+}
+
+impl ThunkAction<TodoList> for TodoAction {
+    fn into_thunk(self) -> Result<Thunk<TodoList>, Self> {
+        match self {
+            TodoAction::Thunk(thunk) => Ok(thunk),
+            action => Err(action),
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug, PartialEq)]
+struct TodoList {
+    names: Vec<String>,
+    done: Vec<usize>,
+}
+
+impl Reducer for TodoList {
+    type Action = TodoAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            TodoAction::NewTodo { name } => self.names.push(name),
+            TodoAction::Complete(index) => self.done.push(index),
+            TodoAction::Thunk(_) => return Err(String::from("a thunk reached the reducer")),
+        }
+
+        Ok(self.clone())
+    }
+}
+
+fn new_todo(name: &str) -> TodoAction {
+    TodoAction::NewTodo { name: String::from(name) }
+}
+
+fn todos(names: &[&str]) -> TodoList {
+    TodoList { names: names.iter().map(|name| String::from(*name)).collect(), done: vec![] }
+}
+
+// completes every todo there is
+fn complete_all() -> TodoAction {
+    TodoAction::Thunk(Thunk::new(|api: &dyn MiddlewareApi<TodoList>, _: &()| {
+        for index in 0..api.get_state().names.len() {
+            api.dispatch(TodoAction::Complete(index))?;
+        }
+        Ok(())
+    }))
+}
+
+// turns empty todos into nothing at all
+struct SkipEmpty;
+
+impl Middleware<TodoList> for SkipEmpty {
+    fn dispatch(&self, store: &Store<TodoList>, action: TodoAction, next: &DispatchFunc<TodoList>) -> Result<TodoList, String> {
+        match action {
+            TodoAction::NewTodo { ref name } if name.is_empty() => Ok(store.get_state()),
+            action => next(store, action),
+        }
+    }
+}
+
+#[test]
+fn thunks_are_tested_against_a_scripted_state() {
+    let store = MockStore::new(todos(&["a", "b"]), vec![Box::new(ThunkMiddleware::new())]);
+    let _ = store.dispatch(complete_all());
+    assert_eq!(vec![TodoAction::Complete(0), TodoAction::Complete(1)], store.actions());
+    // nothing was reduced
+    assert_eq!(todos(&["a", "b"]), store.get_state());
+
+    store.clear_actions();
+    store.set_state(todos(&["a", "b", "c"]));
+    let _ = store.dispatch(complete_all());
+    assert_dispatched!(store, TodoAction::Complete(2));
+    assert_not_dispatched!(store, TodoAction::Complete(n) if *n > 2);
+    assert_not_dispatched!(store, TodoAction::Thunk(_));
+}
+
+#[test]
+fn middleware_is_tested_on_its_own() {
+    let store = MockStore::new(TodoList::default(), vec![]);
+    let next = store.next();
+    let _ = SkipEmpty.dispatch(&store, new_todo(""), &next);
+    let _ = SkipEmpty.dispatch(&store, new_todo("kept"), &next);
+
+    assert_eq!(vec![new_todo("kept")], store.actions());
+    assert_dispatched!(store, TodoAction::NewTodo { ref name } if name == "kept");
+}
+
+#[test]
+fn subscribers_are_called_without_a_reducer() {
+    let store = MockStore::new(TodoList::default(), vec![]);
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    store.subscribe(Box::new(move |_, _| {
+        counted.fetch_add(1, Ordering::SeqCst);
+    }));
+
+    let _ = store.dispatch(new_todo("one"));
+    let _ = store.dispatch(new_todo("two"));
+    assert_eq!(2, calls.load(Ordering::SeqCst));
+}
+
+#[test]
+fn reducing_mocks_run_the_reducer() {
+    let store = MockStore::new(TodoList::default(), vec![Box::new(SkipEmpty)]).reducing();
+    let _ = store.dispatch(new_todo(""));
+    let _ = store.dispatch(new_todo("real"));
+    assert_eq!(todos(&["real"]), store.get_state());
+    assert_eq!(vec![new_todo("real")], store.actions());
+
+    let next = store.next();
+    let _ = next(&store, TodoAction::Complete(0));
+    assert_eq!(vec![0], store.get_state().done);
+}
+
+#[test]
+#[should_panic(expected = "to be dispatched, but got [NewTodo")]
+fn missing_actions_are_reported() {
+    let store = MockStore::new(TodoList::default(), vec![]);
+    let _ = store.dispatch(new_todo("only"));
+    assert_dispatched!(store, TodoAction::Complete(_));
+}
+
+fn panic_message<F: FnOnce() + std::panic::UnwindSafe>(test: F) -> String {
+    let payload = std::panic::catch_unwind(test).unwrap_err();
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => String::from(*payload.downcast::<&str>().unwrap()),
+    }
+}
+
+#[derive(Clone, Default, Debug, PartialEq)]
+struct Strict {
+    names: Vec<String>,
+}
+
+impl Reducer for Strict {
+    type Action = String;
+    type Error = String;
+
+    fn reduce(&mut self, name: Self::Action) -> Result<Self, Self::Error> {
+        if self.names.contains(&name) {
+            return Err(format!("{} is already there", name));
+        }
+        self.names.push(name);
+        Ok(self.clone())
+    }
+}
+
+fn strict(names: &[&str]) -> Strict {
+    Strict { names: names.iter().map(|name| String::from(*name)).collect() }
+}
+
+#[test]
+fn scenarios_chain_expectations() {
+    given(strict(&["a"]))
+        .when(vec![String::from("b"), String::from("c")])
+        .then(strict(&["a", "b", "c"]))
+        .when(vec![String::from("d"), String::from("a"), String::from("e")])
+        .then_error("a is already there")
+        .then_state(|state| assert_eq!(4, state.names.len()))
+        .when(vec![String::from("e")])
+        .then(strict(&["a", "b", "c", "d", "e"]));
+}
+
+#[test]
+fn mismatches_show_the_action_and_a_diff() {
+    let message = panic_message(|| {
+        given(Strict::default())
+            .when(vec![String::from("a"), String::from("b")])
+            .then(strict(&["a", "c"]));
+    });
+
+    assert!(message.starts_with("the state after action #1 (\"b\") isn't the expected one"), "{}", message);
+    assert!(message.contains("\n-         \"c\",\n+         \"b\",\n"), "{}", message);
+    assert!(message.contains("\n          \"a\",\n"), "{}", message);
+}
+
+#[test]
+fn long_states_diff_only_what_changed() {
+    let names : Vec<String> = (0..5000).map(|n| n.to_string()).collect();
+    let names : Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+    let mut expected = names.clone();
+    expected.push("a");
+
+    let message = panic_message(|| {
+        given(strict(&names))
+            .when(vec![String::from("b")])
+            .then(strict(&expected));
+    });
+    let changes : Vec<&str> = message.lines().skip(1).filter(|line| !line.starts_with(' ')).collect();
+    assert_eq!(vec!["-         \"a\",", "+         \"b\","], changes);
+}
+
+#[test]
+fn unexpected_errors_show_the_action() {
+    let message = panic_message(|| {
+        given(strict(&["a"]))
+            .when(vec![String::from("b"), String::from("a")])
+            .then(strict(&["a", "b"]));
+    });
+    assert_eq!("action #1 (\"a\") failed: a is already there", message);
+
+    let message = panic_message(|| {
+        given(strict(&["a"]))
+            .when(vec![String::from("b")])
+            .then_error("b is already there");
+    });
+    assert!(message.starts_with("expected an action to fail with \"b is already there\", but every one succeeded after action #0 (\"b\")"), "{}", message);
+}
+
+static TICKS : AtomicUsize = AtomicUsize::new(0);
+
+// `Stamp` reads a global, like a clock
+#[derive(Clone, Default, Debug, PartialEq)]
+struct Stamped {
+    count: usize,
+    stamp: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum StampAction {
+    Add(usize),
+    Stamp,
+}
+
+impl Reducer for Stamped {
+    type Action = StampAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            StampAction::Add(n) => self.count += n,
+            StampAction::Stamp => self.stamp = TICKS.fetch_add(1, Ordering::SeqCst),
+        }
+        Ok(self.clone())
+    }
+}
+
+// remembers the last action in a field that clones don't copy
+#[derive(Default, Debug)]
+struct Forgetful {
+    total: usize,
+    last: usize,
+}
+
+impl Clone for Forgetful {
+    fn clone(&self) -> Self {
+        Forgetful { total: self.total, last: 0 }
+    }
+}
+
+impl PartialEq for Forgetful {
+    fn eq(&self, other: &Self) -> bool {
+        self.total == other.total
+    }
+}
+
+impl Reducer for Forgetful {
+    type Action = usize;
+    type Error = String;
+
+    fn reduce(&mut self, n: Self::Action) -> Result<Self, Self::Error> {
+        if n == self.last {
+            return Err(String::from("same again"));
+        }
+        self.total += n;
+        self.last = n;
+        Ok(self.clone())
+    }
+}
+
+#[test]
+fn pure_reducers_replay_the_same() {
+    let actions : Vec<String> = ["a", "b", "a", "c"].iter().map(|name| String::from(*name)).collect();
+    Determinism::new(Strict::default()).runs(5).check(&actions).unwrap();
+}
+
+#[test]
+fn reading_globals_is_caught_at_the_first_action_it_changes() {
+    let actions = vec![StampAction::Add(1), StampAction::Add(2), StampAction::Stamp, StampAction::Add(3)];
+    let divergence = Determinism::new(Stamped::default()).check(&actions).unwrap_err();
+    assert_eq!(2, divergence.index);
+    assert_eq!(StampAction::Stamp, divergence.action);
+    assert_eq!("on replay #1", divergence.replay);
+
+    let report = divergence.to_string();
+    assert!(report.starts_with("action #2 (Stamp) reduced differently on replay #1 (- first run, + this one):\n"), "{}", report);
+    assert!(report.contains("\n      count: 3,\n"), "{}", report);
+}
+
+#[test]
+fn state_hidden_from_clones_is_caught() {
+    let divergence = Determinism::new(Forgetful::default()).check(&[1, 2, 2, 3]).unwrap_err();
+    assert_eq!(2, divergence.index);
+    assert_eq!("reducing a fresh clone each time", divergence.replay);
+    assert_eq!(Some(String::from("same again")), divergence.expected.1);
+    assert_eq!(None, divergence.actual.1);
+    assert!(divergence.to_string().contains("\n- error: Some(\"same again\")\n+ error: None\n"));
+}
@@ -0,0 +1,114 @@
+extern crate redux;
+
+use redux::{MiddlewareApi, Reducer, Store, Thunk, ThunkAction, ThunkMiddleware};
+
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug)]
+enum TodoAction {
+    NewTodo { name: String },
+    Thunk(Thunk<TodoList, Arc<Mutex<Vec<String>>>>),
+}
+
+impl ThunkAction<TodoList, Arc<Mutex<Vec<String>>>> for TodoAction {
+    fn into_thunk(self) -> Result<Thunk<TodoList, Arc<Mutex<Vec<String>>>>, Self> {
+        match self {
+            TodoAction::Thunk(thunk) => Ok(thunk),
+            action => Err(action),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct TodoList {
+    names: Vec<String>,
+}
+
+impl Reducer for TodoList {
+    type Action = TodoAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            TodoAction::NewTodo { name } => self.names.push(name),
+            TodoAction::Thunk(_) => return Err(String::from("a thunk reached the reducer")),
+        }
+
+        Ok(self.clone())
+    }
+}
+
+type Api<'a> = &'a dyn MiddlewareApi<TodoList>;
+type Outbox = Arc<Mutex<Vec<String>>>;
+
+fn new_todo(name: &str) -> TodoAction {
+    TodoAction::NewTodo { name: String::from(name) }
+}
+
+fn store_with_outbox() -> (Store<TodoList>, Outbox) {
+    let outbox = Arc::new(Mutex::new(vec![]));
+    let thunks = ThunkMiddleware::with_extra_argument(outbox.clone());
+    (Store::new(vec![Box::new(thunks)]), outbox)
+}
+
+#[test]
+fn plain_actions_pass_through() {
+    let (store, _) = store_with_outbox();
+    let _ = store.dispatch(new_todo("Grocery Shopping"));
+    assert_eq!(vec![String::from("Grocery Shopping")], store.get_state().names);
+}
+
+#[test]
+fn thunks_dispatch_and_read_state() {
+    let (store, _) = store_with_outbox();
+    let thunk = Thunk::new(|api: Api, _: &Outbox| {
+        api.dispatch(new_todo("Grocery Shopping"))?;
+        if api.get_state().names.len() == 1 {
+            api.dispatch(new_todo("Put the groceries away"))?;
+        }
+        Ok(())
+    });
+
+    assert!(store.dispatch(TodoAction::Thunk(thunk)).is_ok());
+    assert_eq!(2, store.get_state().names.len());
+}
+
+#[test]
+fn thunks_can_dispatch_thunks() {
+    let (store, _) = store_with_outbox();
+    let inner = Thunk::new(|api: Api, _: &Outbox| {
+        api.dispatch(new_todo("Inner"))?;
+        Ok(())
+    });
+    let outer = Thunk::new(move |api: Api, _: &Outbox| {
+        api.dispatch(TodoAction::Thunk(inner.clone()))?;
+        api.dispatch(new_todo("Outer"))?;
+        Ok(())
+    });
+
+    let _ = store.dispatch(TodoAction::Thunk(outer));
+    assert_eq!(vec![String::from("Inner"), String::from("Outer")], store.get_state().names);
+}
+
+#[test]
+fn thunks_get_the_extra_argument() {
+    let (store, outbox) = store_with_outbox();
+    let thunk = Thunk::new(|api: Api, outbox: &Outbox| {
+        api.dispatch(new_todo("Write the report"))?;
+        outbox.lock().unwrap().push(String::from("report started"));
+        Ok(())
+    });
+
+    let _ = store.dispatch(TodoAction::Thunk(thunk));
+    assert_eq!(vec![String::from("report started")], *outbox.lock().unwrap());
+}
+
+#[test]
+fn thunk_errors_fail_the_dispatch() {
+    let (store, _) = store_with_outbox();
+    let thunk = Thunk::new(|_: Api, _: &Outbox| Err(String::from("offline")));
+
+    let result = store.dispatch(TodoAction::Thunk(thunk));
+    assert_eq!(Err(String::from("Error during dispatch: offline")), result.map(|_| ()));
+    assert_eq!(0, store.get_state().names.len());
+}
@@ -0,0 +1,234 @@
+#![cfg(feature = "tracing")]
+
+extern crate redux;
+
+use redux::{ActionKind, DispatchFunc, Middleware, Reducer, Store};
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+#[derive(Clone, Debug, Default)]
+struct Recorded {
+    name: &'static str,
+    parent: Option<usize>,
+    fields: HashMap<&'static str, String>,
+}
+
+impl Visit for Recorded {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.insert(field.name(), String::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.fields.insert(field.name(), format!("{:?}", value));
+    }
+}
+
+// keeps every span, with the one that was entered when it was made as its
+// parent; span ids are their index plus one
+#[derive(Clone, Default)]
+struct Spans {
+    spans: Arc<Mutex<Vec<Recorded>>>,
+    entered: Arc<Mutex<Vec<usize>>>,
+}
+
+impl Subscriber for Spans {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn new_span(&self, attributes: &Attributes) -> Id {
+        let mut span = Recorded {
+            name: attributes.metadata().name(),
+            parent: self.entered.lock().unwrap().last().cloned(),
+            fields: HashMap::new(),
+        };
+        attributes.record(&mut span);
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(span);
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, id: &Id, values: &Record) {
+        values.record(&mut self.spans.lock().unwrap()[id.into_u64() as usize - 1]);
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event) {}
+
+    fn enter(&self, id: &Id) {
+        self.entered.lock().unwrap().push(id.into_u64() as usize - 1);
+    }
+
+    fn exit(&self, _: &Id) {
+        self.entered.lock().unwrap().pop();
+    }
+}
+
+impl Spans {
+    fn recorded(&self) -> Vec<Recorded> {
+        self.spans.lock().unwrap().clone()
+    }
+}
+
+// runs `test` with its spans recorded, on this thread only
+fn traced<F: FnOnce()>(test: F) -> Vec<Recorded> {
+    let spans = Spans::default();
+    tracing::subscriber::with_default(spans.clone(), test);
+    spans.recorded()
+}
+
+#[derive(Clone, Debug)]
+enum TodoAction {
+    NewTodo { name: String },
+    Complete(usize),
+}
+
+fn kind(action: &TodoAction) -> &'static str {
+    match action {
+        TodoAction::NewTodo { .. } => "NewTodo",
+        TodoAction::Complete(_) => "Complete",
+    }
+}
+
+impl ActionKind for TodoAction {
+    fn kind(&self) -> &'static str {
+        match self {
+            TodoAction::NewTodo { .. } => "todos/new",
+            TodoAction::Complete(_) => "todos/complete",
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+struct TodoList {
+    names: Vec<String>,
+}
+
+impl Reducer for TodoList {
+    type Action = TodoAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            TodoAction::NewTodo { name } => self.names.push(name),
+            TodoAction::Complete(index) if index >= self.names.len() => return Err(format!("no todo {}", index)),
+            TodoAction::Complete(_) => {},
+        }
+
+        Ok(self.clone())
+    }
+}
+
+struct Inner;
+
+impl Middleware<TodoList> for Inner {
+    fn dispatch(&self, store: &Store<TodoList>, action: TodoAction, next: &DispatchFunc<TodoList>) -> Result<TodoList, String> {
+        next(store, action)
+    }
+}
+
+struct Outer;
+
+impl Middleware<TodoList> for Outer {
+    fn dispatch(&self, store: &Store<TodoList>, action: TodoAction, next: &DispatchFunc<TodoList>) -> Result<TodoList, String> {
+        next(store, action)
+    }
+
+    fn name(&self) -> &'static str {
+        "outer"
+    }
+}
+
+fn field<'a>(span: &'a Recorded, name: &str) -> &'a str {
+    span.fields.get(name).map(|value| value.as_str()).unwrap_or("")
+}
+
+#[test]
+fn dispatches_nest_middleware_and_the_reduce() {
+    let spans = traced(|| {
+        let store = Store::new(vec![Box::new(Inner), Box::new(Outer)]).action_kinds(kind);
+        store.dispatch(TodoAction::NewTodo { name: String::from("a") }).unwrap();
+    });
+
+    let names : Vec<&str> = spans.iter().map(|span| span.name).collect();
+    assert_eq!(vec!["dispatch", "middleware", "middleware", "reduce"], names);
+    assert_eq!(vec![None, Some(0), Some(1), Some(2)], spans.iter().map(|span| span.parent).collect::<Vec<_>>());
+    assert_eq!("outer", field(&spans[1], "middleware"));
+    assert!(field(&spans[2], "middleware").ends_with("Inner"), "{:?}", spans[2]);
+    for span in &spans {
+        assert_eq!("NewTodo", field(span, "action"));
+        assert_eq!("ok", field(span, "outcome"));
+        assert!(!span.fields.contains_key("error"));
+    }
+}
+
+#[test]
+fn failures_are_recorded_on_every_span_they_go_through() {
+    let spans = traced(|| {
+        let store = Store::new(vec![Box::new(Outer)]).action_kinds(kind);
+        assert!(store.dispatch(TodoAction::Complete(3)).is_err());
+    });
+
+    assert_eq!(3, spans.len());
+    for span in &spans {
+        assert_eq!("Complete", field(span, "action"));
+        assert_eq!("error", field(span, "outcome"));
+    }
+    assert_eq!("no todo 3", field(&spans[2], "error"));
+    assert_eq!("Error during dispatch: no todo 3", field(&spans[0], "error"));
+}
+
+#[test]
+fn subscriptions_are_traced_under_the_dispatch() {
+    let spans = traced(|| {
+        let store = Store::new(vec![]).action_kinds(kind);
+        store.subscribe(Box::new(|_, _| {}));
+        let once = AtomicBool::new(true);
+        let second = store.subscribe(Box::new(move |store: &Store<TodoList>, _| {
+            if once.swap(false, Ordering::SeqCst) {
+                let _ = store.dispatch(TodoAction::Complete(0));
+            }
+        }));
+        assert_eq!(1, second.id());
+        store.dispatch(TodoAction::NewTodo { name: String::from("a") }).unwrap();
+    });
+
+    let names : Vec<&str> = spans.iter().map(|span| span.name).collect();
+    assert_eq!(vec!["dispatch", "reduce", "subscription", "subscription", "dispatch", "reduce", "subscription", "subscription"], names);
+    assert_eq!("0", field(&spans[2], "subscription"));
+    assert_eq!("1", field(&spans[3], "subscription"));
+    assert_eq!(Some(0), spans[2].parent);
+    assert_eq!("NewTodo", field(&spans[3], "action"));
+    // the dispatch from the second subscription nests under it
+    assert_eq!(Some(3), spans[4].parent);
+    assert_eq!("Complete", field(&spans[7], "action"));
+}
+
+#[test]
+fn actions_are_named_after_their_type_by_default() {
+    let spans = traced(|| {
+        let store : Store<TodoList> = Store::new(vec![]);
+        store.dispatch(TodoAction::Complete(0)).unwrap_err();
+    });
+
+    assert!(field(&spans[0], "action").ends_with("TodoAction"), "{:?}", spans[0]);
+}
+
+#[test]
+fn actions_can_be_named_by_their_kind() {
+    let spans = traced(|| {
+        let store = Store::new(vec![Box::new(Outer)]).named_by_kind();
+        store.dispatch(TodoAction::NewTodo { name: String::from("a") }).unwrap();
+    });
+
+    for span in &spans {
+        assert_eq!("todos/new", field(span, "action"));
+    }
+}
@@ -0,0 +1,170 @@
+extern crate redux;
+
+use redux::{Reducer, Store, UndoAction, UndoConfig, Undoable};
+
+#[derive(Clone, Debug, PartialEq)]
+enum EditorAction {
+    Type(char),
+    Delete,
+    MoveCursor(usize),
+}
+
+#[derive(Clone, Default, Debug, PartialEq)]
+struct Editor {
+    text: String,
+    cursor: usize,
+}
+
+impl Reducer for Editor {
+    type Action = EditorAction;
+    type Error = String;
+
+    fn reduce(&mut self, action: Self::Action) -> Result<Self, Self::Error> {
+        match action {
+            EditorAction::Type(c) => self.text.push(c),
+            EditorAction::Delete => {
+                if self.text.pop().is_none() {
+                    return Err(String::from("nothing to delete"));
+                }
+            },
+            EditorAction::MoveCursor(position) => self.cursor = position,
+        }
+
+        Ok(self.clone())
+    }
+}
+
+struct EditorHistory;
+
+impl UndoConfig<Editor> for EditorHistory {
+    fn limit() -> Option<usize> {
+        Some(3)
+    }
+
+    fn filter(action: &EditorAction) -> bool {
+        !matches!(action, EditorAction::MoveCursor(_))
+    }
+
+    fn group(action: &EditorAction) -> Option<&'static str> {
+        match action {
+            EditorAction::Type(' ') => None,
+            EditorAction::Type(_) => Some("typing"),
+            _ => None,
+        }
+    }
+}
+
+fn edit(c: char) -> UndoAction<EditorAction> {
+    UndoAction::Action(EditorAction::Type(c))
+}
+
+fn type_each(store: &Store<Undoable<Editor>>, text: &str) {
+    for c in text.chars() {
+        let _ = store.dispatch(edit(c));
+    }
+}
+
+#[test]
+fn undo_and_redo() {
+    let store : Store<Undoable<Editor>> = Store::new(vec![]);
+    type_each(&store, "abc");
+
+    let _ = store.dispatch(UndoAction::Undo);
+    let _ = store.dispatch(UndoAction::Undo);
+    assert_eq!("a", store.get_state().text);
+    assert_eq!(1, store.get_state().past_len());
+    assert_eq!(2, store.get_state().future_len());
+
+    let _ = store.dispatch(UndoAction::Redo);
+    assert_eq!("ab", store.get_state().text);
+
+    // a new action forgets the future
+    type_each(&store, "x");
+    assert_eq!("abx", store.get_state().text);
+    assert!(!store.get_state().can_redo());
+}
+
+#[test]
+fn undo_and_redo_at_the_ends_of_history_do_nothing() {
+    let store : Store<Undoable<Editor>> = Store::new(vec![]);
+    let _ = store.dispatch(UndoAction::Undo);
+    type_each(&store, "a");
+    let _ = store.dispatch(UndoAction::Redo);
+    assert_eq!("a", store.get_state().text);
+    assert_eq!(1, store.get_state().past_len());
+}
+
+#[test]
+fn jump_through_history() {
+    let store : Store<Undoable<Editor>> = Store::new(vec![]);
+    type_each(&store, "abcd");
+
+    let _ = store.dispatch(UndoAction::Jump(-3));
+    assert_eq!("a", store.get_state().text);
+    let _ = store.dispatch(UndoAction::Jump(2));
+    assert_eq!("abc", store.get_state().text);
+
+    // too far is ignored
+    let _ = store.dispatch(UndoAction::Jump(5));
+    assert_eq!("abc", store.get_state().text);
+}
+
+#[test]
+fn clear_history_keeps_the_present() {
+    let store : Store<Undoable<Editor>> = Store::new(vec![]);
+    type_each(&store, "abc");
+    let _ = store.dispatch(UndoAction::Undo);
+    let _ = store.dispatch(UndoAction::ClearHistory);
+
+    let state = store.get_state();
+    assert_eq!("ab", state.text);
+    assert!(!state.can_undo());
+    assert!(!state.can_redo());
+}
+
+#[test]
+fn failed_actions_leave_history_alone() {
+    let store : Store<Undoable<Editor>> = Store::new(vec![]);
+    assert!(store.dispatch(UndoAction::Action(EditorAction::Delete)).is_err());
+    assert_eq!(0, store.get_state().past_len());
+}
+
+#[test]
+fn history_limit_drops_the_oldest_steps() {
+    let store : Store<Undoable<Editor, EditorHistory>> = Store::new(vec![]);
+    for word in ["a", "b", "c", "d", "e"].iter() {
+        let _ = store.dispatch(UndoAction::Action(EditorAction::Type(word.chars().next().unwrap())));
+        let _ = store.dispatch(UndoAction::Action(EditorAction::Type(' ')));
+    }
+    assert_eq!(3, store.get_state().past_len());
+
+    let _ = store.dispatch(UndoAction::Jump(-3));
+    assert_eq!("a b c d", store.get_state().text);
+}
+
+#[test]
+fn filtered_actions_are_not_undo_steps() {
+    let store : Store<Undoable<Editor, EditorHistory>> = Store::new(vec![]);
+    let _ = store.dispatch(UndoAction::Action(EditorAction::Type(' ')));
+    let _ = store.dispatch(UndoAction::Action(EditorAction::MoveCursor(4)));
+    assert_eq!(1, store.get_state().past_len());
+    assert_eq!(4, store.get_state().cursor);
+
+    let _ = store.dispatch(UndoAction::Undo);
+    assert_eq!(Editor::default(), *store.get_state().present());
+}
+
+#[test]
+fn grouped_actions_undo_together() {
+    let store : Store<Undoable<Editor, EditorHistory>> = Store::new(vec![]);
+    for c in "hello world".chars() {
+        let _ = store.dispatch(UndoAction::Action(EditorAction::Type(c)));
+    }
+
+    let _ = store.dispatch(UndoAction::Undo);
+    assert_eq!("hello ", store.get_state().text);
+    let _ = store.dispatch(UndoAction::Undo);
+    assert_eq!("hello", store.get_state().text);
+    let _ = store.dispatch(UndoAction::Undo);
+    assert_eq!("", store.get_state().text);
+}